@@ -0,0 +1,81 @@
+//! Times the Pearson matrix of a synthetic survey with 150 interval fields, once with the default
+//! scipy engine, which passes every pair of columns to Python, and once with `--engine native`,
+//! which needs no Python. The scipy run is skipped when scipy cannot be imported. Matrix rows
+//! are computed on the rayon pool, so compare `RAYON_NUM_THREADS=1 cargo bench` with
+//! `cargo bench` to see what the parallel rows buy.
+
+use clap::Parser;
+use ntpu_market_research_statistical_tool::{run_correlations, Cli, Command, Field};
+use polars::{export::rayon, prelude::*};
+use serde_json::{from_value, json, Value};
+use std::time::Instant;
+
+const COLUMNS: usize = 150;
+const ROWS: usize = 500;
+const RUNS: u32 = 3;
+
+/// Answers on a 1–7 scale from a fixed linear congruential generator, so every run sees the
+/// same data.
+fn dataframe() -> DataFrame {
+    let mut state = 0x2545_f491_u64;
+    let columns = (0..COLUMNS)
+        .map(|column| {
+            let answers = (0..ROWS)
+                .map(|_| {
+                    state = state
+                        .wrapping_mul(6_364_136_223_846_793_005)
+                        .wrapping_add(1_442_695_040_888_963_407);
+                    f64::from(u32::try_from(state >> 61).unwrap()) + 1.0
+                })
+                .collect::<Vec<f64>>();
+            Series::new(&format!("q{column}"), answers)
+        })
+        .collect();
+    DataFrame::new(columns).unwrap()
+}
+
+/// Prints the mean time of [`RUNS`] Pearson matrices computed with `engine`.
+fn time(dataframe: &DataFrame, fields: &[Field], engine: &str) {
+    let arguments = [
+        "tool",
+        "analyze",
+        "data.csv",
+        "fields.json",
+        "--no-spearman",
+        "--no-kendall",
+        "--python-retries",
+        "0",
+        "--engine",
+        engine,
+    ];
+    let Command::Analyze(options) = Cli::parse_from(arguments).command else {
+        unreachable!()
+    };
+    let started = Instant::now();
+    for _ in 0..RUNS {
+        match run_correlations(dataframe, fields, &options) {
+            Ok(matrices) => assert!(matrices.pearson.is_some()),
+            Err(error) => {
+                println!("{engine} pearson matrix skipped: {error}");
+                return;
+            }
+        }
+    }
+    println!(
+        "{engine} pearson matrix, {COLUMNS} columns × {ROWS} rows, {} threads: {:.2?} per run",
+        rayon::current_num_threads(),
+        started.elapsed() / RUNS
+    );
+}
+
+fn main() {
+    let dataframe = dataframe();
+    let fields: Vec<Field> = from_value(Value::Array(
+        (0..COLUMNS)
+            .map(|column| json!({ "name": format!("q{column}"), "scale": "Interval" }))
+            .collect(),
+    ))
+    .unwrap();
+    time(&dataframe, &fields, "scipy");
+    time(&dataframe, &fields, "native");
+}
@@ -0,0 +1,908 @@
+use clap::{ArgAction, Args, Parser, Subcommand, ValueEnum};
+use serde::Serialize;
+use std::{
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Seconds to wait for an http(s) source when `--timeout` is not available or not given.
+pub const FETCH_TIMEOUT: u64 = 30;
+
+pub fn is_url(source: &str) -> bool {
+    source.starts_with("http://") || source.starts_with("https://")
+}
+
+/// The last path segment of a URL source, so reports land in the working directory; local paths
+/// are returned unchanged.
+pub fn local_name(source: &str) -> &str {
+    if !is_url(source) {
+        return source;
+    }
+    let path = source.split(['?', '#']).next().unwrap_or_default();
+    match path.rsplit('/').next() {
+        Some(name) if !name.is_empty() && !path.ends_with("//") => name,
+        _ => "download",
+    }
+}
+
+#[derive(Parser, Debug)]
+#[command(version, about = "臺北大學行銷研究課用到的統計工具")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+    /// Log progress to stderr: `-v` for each step, `-vv` for every field as well.
+    #[arg(short, long, action = ArgAction::Count, global = true)]
+    pub verbose: u8,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Run the full analysis and write the report.
+    Analyze(Box<Options>),
+    /// List the columns of a CSV file with their types and non-null counts.
+    Describe {
+        /// CSV or .xlsx file to inspect.
+        source: String,
+        #[command(flatten)]
+        format: SourceFormat,
+    },
+    /// Print a starter field description JSON with a guessed scale for every column.
+    FieldsTemplate {
+        /// CSV or .xlsx file to scan.
+        source: String,
+        #[command(flatten)]
+        format: SourceFormat,
+    },
+    /// Check that the CSV file and field description file can be used together.
+    Validate {
+        /// CSV or .xlsx file to check.
+        source: String,
+        /// Field description JSON or YAML file to check.
+        fields: String,
+        #[command(flatten)]
+        format: SourceFormat,
+    },
+    /// List the correlations whose significance or sign changed between two JSON reports.
+    Diff {
+        /// JSON report of the earlier data.
+        old: String,
+        /// JSON report of the later data.
+        new: String,
+    },
+    /// Tabulate one correlation across several JSON reports with a fixed-effects pooled estimate.
+    Merge {
+        /// JSON reports of the studies, one per dataset.
+        #[arg(required = true, num_args = 2..)]
+        reports: Vec<String>,
+        /// The two variables whose correlation is pooled.
+        #[arg(long, value_name = "VAR1:VAR2")]
+        pair: String,
+        /// Only use correlations computed with this method, such as `Pearson`.
+        #[arg(long)]
+        method: Option<String>,
+    },
+    /// Print the version of this tool.
+    Version,
+}
+
+/// How to read the source file.
+#[derive(Args, Serialize, Debug, Clone)]
+pub struct SourceFormat {
+    /// Sheet of an .xlsx source; the first sheet when omitted.
+    #[arg(long)]
+    pub sheet: Option<String>,
+    /// Column delimiter of a CSV source, such as `;` or `tab`.
+    #[arg(long, default_value = ",", value_parser = parse_delimiter)]
+    pub delimiter: u8,
+    /// Text encoding of a CSV source, such as `big5`; any codec name Python knows is accepted.
+    #[arg(long)]
+    pub encoding: Option<String>,
+}
+
+fn parse_delimiter(source: &str) -> Result<u8, String> {
+    match source {
+        "tab" | "\\t" | "\t" => Ok(b'\t'),
+        _ => match source.as_bytes() {
+            &[delimiter] => Ok(delimiter),
+            _ => Err(format!(
+                "expected a single ASCII character or `tab`, not `{source}`"
+            )),
+        },
+    }
+}
+
+#[derive(Args, Serialize, Debug)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct Options {
+    /// CSV file to analyze, or an http(s) URL to fetch it from.
+    pub source: String,
+    /// Field description JSON or YAML file (by extension); not needed with `--describe-only`.
+    #[arg(required_unless_present = "describe_only")]
+    pub fields: Option<String>,
+    /// Second CSV file with the same columns to compare against.
+    #[arg(long)]
+    pub compare: Option<String>,
+    /// Report path, or `-` for standard output; each format replaces its extension. Defaults to
+    /// `<source>.md`, or `<source>.compare.md` when comparing.
+    #[arg(long, default_value = "", hide_default_value = true)]
+    pub output: String,
+    /// Report file name built from the `{stem}` and `{dir}` of the source, the `{date}`, the
+    /// `{format}` and the `{alpha}`; the format's extension is appended.
+    #[arg(long, conflicts_with = "output")]
+    pub name_template: Option<String>,
+    /// Overwrite existing output files.
+    #[arg(long, visible_alias = "assume-yes")]
+    pub force: bool,
+    /// Comma-separated report formats to write from the same analysis.
+    #[arg(
+        long,
+        visible_alias = "output-format",
+        value_enum,
+        value_delimiter = ',',
+        default_value = "md"
+    )]
+    pub format: Vec<OutputFormat>,
+    /// Layout of the Markdown correlation tables.
+    #[arg(long, value_enum, default_value_t = ReportStyle::Default, conflicts_with = "compare")]
+    pub style: ReportStyle,
+    /// How the tables in the Markdown report are drawn.
+    #[arg(long, value_enum, default_value_t = TableStyle::Markdown)]
+    pub table_style: TableStyle,
+    /// How the r, p and descriptive values in the Markdown report are written; the JSON report
+    /// always uses plain numbers.
+    #[arg(long, value_enum, default_value_t = NumberLocale::Plain)]
+    pub number_locale: NumberLocale,
+    /// Decimal places of every statistic in the report: the correlation tables, the descriptive
+    /// statistics, the factor loadings and the natively computed tests.
+    #[arg(long, default_value_t = 5)]
+    pub precision: usize,
+    /// Render correlations with fewer observations than this as insufficient.
+    #[arg(long)]
+    pub min_n: Option<usize>,
+    /// Also write the correlations in long format to `<source>.tidy.csv`.
+    #[arg(long)]
+    pub tidy: bool,
+    /// Browse the correlations in an interactive terminal UI instead of writing the report.
+    #[arg(long, conflicts_with = "compare")]
+    #[serde(skip)]
+    pub tui: bool,
+    /// Write the average ranks of the observed values of the non-nominal fields to this CSV file;
+    /// missing values are left empty.
+    #[arg(long, conflicts_with = "compare")]
+    pub emit_ranks: Option<String>,
+    /// Write the two columns exactly as they enter the correlation of `VAR1:VAR2` to a CSV file.
+    #[arg(long, num_args = 2, value_names = ["VAR1:VAR2", "PATH"], conflicts_with = "compare")]
+    pub dump_pair: Option<Vec<String>>,
+    /// Split every correlation matrix into blocks of this many columns, rendered as successive
+    /// tables with the row names repeated.
+    #[arg(long, value_name = "K")]
+    pub page_size: Option<NonZeroUsize>,
+    /// Write the significant correlations as a Graphviz DOT graph to this file: edge width follows
+    /// |r| and color the sign.
+    #[arg(long, value_name = "PATH", conflicts_with = "compare")]
+    pub network: Option<String>,
+    /// Write every computed correlation matrix as a color-coded heatmap to this SVG or PNG file.
+    #[arg(long, value_name = "PATH.svg|PATH.png", value_parser = parse_heatmap_path, conflicts_with = "compare")]
+    pub heatmap: Option<String>,
+    /// Write every analyzed field with its declared scale, detected dtype, distinct-value count
+    /// and whether the scale looks plausible to this JSON file.
+    #[arg(long)]
+    pub emit_field_report: Option<String>,
+    /// Comma-separated rater columns to compute ICC(2,1) and ICC(2,k) over.
+    #[arg(long, value_delimiter = ',', value_name = "COLUMNS")]
+    pub icc: Vec<String>,
+    /// Run a paired-samples t-test (scipy's `ttest_rel`) between two measurements of the same
+    /// respondents, written as `PRE:POST`.
+    #[arg(long, value_name = "PRE:POST")]
+    pub paired_ttest: Option<String>,
+    /// Fit an ordinary least squares regression of one field on one or more others, written as
+    /// `Y ~ X1,X2,X3`.
+    #[arg(long, value_name = "Y ~ X1,X2")]
+    pub regression: Option<String>,
+    /// Compare the mean of a numeric field across the groups of another, written as
+    /// `METRIC by GROUP`: an independent-samples t-test for two groups, one-way ANOVA for more,
+    /// each with Levene's test.
+    #[arg(long, value_name = "METRIC by GROUP")]
+    pub group_compare: Option<String>,
+    /// Add the Spearman-Brown predicted reliability of every group at this many times its item
+    /// count (2 when given without a value) to the reliability table.
+    #[arg(long, value_name = "FACTOR", num_args = 0..=1, default_missing_value = "2")]
+    pub spearman_brown: Option<f64>,
+    /// Report the condition number of the factor fields' correlation matrix.
+    #[arg(long)]
+    pub condition_number: bool,
+    /// Report the number and share of non-missing responses to every analyzed field.
+    #[arg(long)]
+    pub response_rate: bool,
+    /// Warn about the fields whose response rate falls below this share.
+    #[arg(long, default_value_t = 0.8, requires = "response_rate")]
+    pub min_response_rate: f64,
+    /// Add the variance-covariance matrix of the interval and ratio fields to the report.
+    #[arg(long)]
+    pub emit_covariance: bool,
+    /// Drop duplicate rows before anything else, keeping the first; rows count as duplicates when
+    /// they share the `--id-column` value, or every value without one.
+    #[arg(long)]
+    pub dedupe_rows: bool,
+    /// Column identifying the respondents, such as an ID or timestamp, kept in the data but left
+    /// out of every statistic.
+    #[arg(long)]
+    pub id_column: Option<String>,
+    /// Column holding survey weights.
+    #[arg(long)]
+    pub weights: Option<String>,
+    /// Comma-separated fields to use as the rows of a cross-correlation block.
+    #[arg(
+        long,
+        value_delimiter = ',',
+        requires = "cols",
+        conflicts_with = "compare"
+    )]
+    pub rows: Vec<String>,
+    /// Comma-separated fields to use as the columns of a cross-correlation block.
+    #[arg(long, value_delimiter = ',', requires = "rows")]
+    pub cols: Vec<String>,
+    /// Correlate the `--rolling-fields` within each window of this many consecutive rows, ordered
+    /// by the date column, and write the results to `<source>.rolling.csv`.
+    #[arg(
+        long,
+        value_name = "DATE_COLUMN:WINDOW",
+        value_parser = parse_rolling,
+        requires = "rolling_fields"
+    )]
+    pub rolling: Option<Rolling>,
+    /// Comma-separated fields whose pairs are correlated with `--rolling`.
+    #[arg(long, value_delimiter = ',', requires = "rolling")]
+    pub rolling_fields: Vec<String>,
+    /// Order the rows by this date column and correlate the detrended series instead of the raw
+    /// ones.
+    #[arg(long, value_name = "DATE_COLUMN", conflicts_with_all = ["weights", "rolling"])]
+    pub detrend: Option<String>,
+    /// How `--detrend` removes the trend.
+    #[arg(long, value_enum, default_value_t = DetrendMethod::Difference, requires = "detrend")]
+    pub detrend_method: DetrendMethod,
+    /// Only analyze the described fields whose names match this regular expression.
+    #[arg(long, value_name = "PATTERN")]
+    pub only_regex: Option<String>,
+    /// Only analyze the rows matching `column OP value` comparisons joined by AND/OR.
+    #[arg(long)]
+    pub filter: Option<String>,
+    /// Analyze a random sample of this many rows instead of the whole file.
+    #[arg(long)]
+    pub sample: Option<usize>,
+    /// Print the first this many rows of the data, after deduplication, filtering and sampling,
+    /// to stderr before analyzing it.
+    #[arg(long, value_name = "N")]
+    pub preview: Option<usize>,
+    /// Seed for the random number generator.
+    #[arg(long, default_value_t = 0)]
+    pub seed: u64,
+    /// Delta degrees of freedom of the reported standard deviations and covariances: 1 divides by
+    /// n − 1 (sample), 0 by n (population).
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u8).range(0..=1))]
+    pub ddof: u8,
+    /// Comma-separated percentiles, each between 0 and 1, to list in the descriptive statistics.
+    #[arg(
+        long,
+        value_delimiter = ',',
+        value_parser = parse_percentile,
+        default_value = "0.05,0.25,0.5,0.75,0.95"
+    )]
+    pub percentiles: Vec<f64>,
+    /// Add a crosstab of two nominal fields, written as `A,B`, to the frequency tables; may be
+    /// repeated.
+    #[arg(long, value_name = "A,B", value_parser = parse_crosstab)]
+    pub crosstab: Vec<(String, String)>,
+    /// Which variant of Kendall's tau to compute.
+    #[arg(long, value_enum, default_value_t = KendallVariant::B)]
+    pub kendall_variant: KendallVariant,
+    /// Z-score every analyzed column before computing the correlations and factor analysis.
+    #[arg(long)]
+    pub standardize: bool,
+    /// Compute the Pearson correlations natively, the whole matrix in one pass over the z-scored
+    /// data, instead of calling scipy for every pair.
+    #[arg(long)]
+    pub native_pearson: bool,
+    /// Which engine computes the Pearson and Spearman correlations; Kendall and the factor
+    /// analysis always use Python.
+    #[arg(long, value_enum, default_value_t = Engine::Scipy)]
+    pub engine: Engine,
+    /// Which coefficient to compute between two ordinal fields.
+    #[arg(long, value_enum, default_value_t = OrdinalCoefficient::Spearman)]
+    pub ordinal_coefficient: OrdinalCoefficient,
+    /// Which test to compute between two fields when either is nominal: the chi-square test of
+    /// independence with Cramér's V by default, or Kendall's tau over the category codes.
+    #[arg(long, value_enum, default_value_t = NominalTest::Chisq)]
+    pub nominal_test: NominalTest,
+    /// Also test every Pearson correlation against this hypothesized value with the Fisher z
+    /// test.
+    #[arg(long, allow_negative_numbers = true)]
+    pub test_against: Option<f64>,
+    /// Confidence level of the Fisher z interval reported with every Pearson, Spearman and
+    /// Kendall correlation.
+    #[arg(long, default_value_t = 0.95, value_parser = parse_confidence_level)]
+    pub confidence_level: f64,
+    /// Skip the nominal correlations of fields with more than this many categories.
+    #[arg(long)]
+    pub max_categories: Option<usize>,
+    /// Keep only the first of fields whose cleaned values are identical.
+    #[arg(long)]
+    pub dedup_columns: bool,
+    /// Keep fields whose observed values are all the same, with every correlation marked as a
+    /// constant field, instead of leaving them out of the analysis.
+    #[arg(long)]
+    pub include_zero_variance_as_note: bool,
+    /// Show the number of tied pairs in each Kendall cell.
+    #[arg(long)]
+    pub show_ties: bool,
+    /// Count the concordant, discordant and tied pairs behind each Kendall cell natively and list
+    /// them with the native tau, in the JSON output and as notes under the Markdown table.
+    #[arg(long)]
+    pub kendall_detail: bool,
+    /// List the share of tied observations of every Spearman field under the Markdown table.
+    #[arg(long)]
+    pub spearman_detail: bool,
+    /// Append the wall-clock time of loading, describing, each correlation matrix, the factor
+    /// analysis and rendering to the Markdown report.
+    #[arg(long)]
+    pub timings: bool,
+    /// How many times to retry a failing Python computation.
+    #[arg(long, default_value_t = 2)]
+    pub python_retries: usize,
+    /// Significance level a p-value must fall below.
+    #[arg(long, default_value_t = 0.05)]
+    pub alpha: f64,
+    /// Significance level for the Pearson correlations; `--alpha` when omitted.
+    #[arg(long)]
+    pub alpha_pearson: Option<f64>,
+    /// Significance level for the Kendall correlations; `--alpha` when omitted.
+    #[arg(long)]
+    pub alpha_kendall: Option<f64>,
+    /// Correct the p-values for multiple comparisons across the unique pairs of each matrix.
+    #[arg(long, value_enum, default_value_t = Correction::None)]
+    pub correction: Correction,
+    /// Which p-value decides significance and bolding; the adjusted one when `--correction` is
+    /// set, otherwise the raw one.
+    #[arg(long, value_enum)]
+    pub significance_basis: Option<SignificanceBasis>,
+    /// Only bold significant correlations that are positive.
+    #[arg(long)]
+    pub bold_positive_only: bool,
+    /// Text written above every correlation table, where `{title}` is replaced by the table's
+    /// title; `表：{title} 相關係數矩陣` when omitted.
+    #[arg(long, value_name = "TEXT")]
+    pub caption: Option<String>,
+    /// Text written below every correlation table in place of the default significance legend.
+    #[arg(long, value_name = "TEXT")]
+    pub legend: Option<String>,
+    /// Comma-separated list of sections to compute; all sections when omitted.
+    #[arg(long, value_enum, value_delimiter = ',')]
+    pub sections: Vec<Section>,
+    /// Skip every computation that needs Python and run only the native ones.
+    #[arg(long, conflicts_with = "python_warnings")]
+    pub offline: bool,
+    /// Capture the warnings Python emits during the correlations and factor analysis and list
+    /// them in an appendix.
+    #[arg(long)]
+    pub python_warnings: bool,
+    /// Check that converting the factor analysis data to pandas keeps its shape, column names and
+    /// non-null counts, and warn about any difference.
+    #[arg(long)]
+    pub round_trip_check: bool,
+    /// Whether the factor analysis is fitted on the data or on a polychoric correlation matrix;
+    /// `auto` picks the matrix when every factor-analysis field is ordinal.
+    #[arg(long, value_enum, default_value_t = FactorInput::Auto)]
+    pub factor_input: FactorInput,
+    /// Rotation of the factor solution.
+    #[arg(long, value_enum, default_value_t = Rotation::Promax)]
+    pub rotation: Rotation,
+    /// Number of factors to extract.
+    #[arg(long, default_value_t = 3, value_parser = clap::value_parser!(u16).range(1..))]
+    pub n_factors: u16,
+    /// Give up fetching an http(s) source after this many seconds.
+    #[arg(long, default_value_t = FETCH_TIMEOUT)]
+    pub timeout: u64,
+    #[command(flatten)]
+    pub source_format: SourceFormat,
+    /// Abandon the factor analysis after this many seconds.
+    #[arg(long)]
+    pub factor_timeout: Option<u64>,
+    /// Refuse the factor analysis when the item correlation matrix alone would take more than
+    /// this many megabytes.
+    #[arg(long, default_value_t = 1024, value_name = "MB")]
+    pub factor_memory_limit: u64,
+    /// Leave factor loadings whose absolute value is below this cutoff blank in the report.
+    #[arg(long)]
+    pub loading_cutoff: Option<f64>,
+    /// List the factor loadings one item per row, grouped under the factor each item loads on
+    /// most strongly.
+    #[arg(long)]
+    pub group_loadings: bool,
+    /// Only compute the descriptive statistics of every column.
+    #[arg(long, conflicts_with_all = ["rows", "tui"])]
+    pub describe_only: bool,
+    /// Skip the descriptive statistics.
+    #[arg(long)]
+    pub no_describe: bool,
+    /// Skip the Pearson correlation matrix.
+    #[arg(long)]
+    pub no_pearson: bool,
+    /// Skip the Spearman correlation matrix.
+    #[arg(long)]
+    pub no_spearman: bool,
+    /// Skip the Kendall correlation matrix.
+    #[arg(long)]
+    pub no_kendall: bool,
+    /// Skip the point-biserial table between binary nominal fields and the other scales.
+    #[arg(long)]
+    pub no_point_biserial: bool,
+    /// Skip the reliability analysis.
+    #[arg(long)]
+    pub no_reliability: bool,
+    /// Skip Little's MCAR test for missing values.
+    #[arg(long)]
+    pub no_mcar: bool,
+    /// Skip the Shapiro-Wilk normality tests.
+    #[arg(long)]
+    pub no_normality: bool,
+    /// Skip the factor analysis.
+    #[arg(long)]
+    pub no_factor: bool,
+    /// After writing the reports, check that the correlation between two fields is `significant`
+    /// or `not-significant`, and exit with a non-zero code if it is not; repeatable.
+    #[arg(long = "assert", value_name = "VAR1:VAR2:EXPECTATION", value_parser = parse_assertion)]
+    pub assertions: Vec<Assertion>,
+    /// Exit with an error listing the pairs instead of writing a report when any r or p-value is
+    /// NaN.
+    #[arg(long)]
+    pub no_nan: bool,
+    /// Follow each report section with a short note on how to read it.
+    #[arg(long)]
+    pub explain: bool,
+    /// Print the resolved options as JSON and exit.
+    #[arg(long)]
+    #[serde(skip)]
+    pub explain_config: bool,
+    /// Load the source and field file, list every field with its scale, whether the column was
+    /// found, its type and non-null count, and exit without analyzing anything.
+    #[arg(long)]
+    #[serde(skip)]
+    pub dry_run: bool,
+}
+
+#[derive(ValueEnum, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Section {
+    Describe,
+    Pearson,
+    Spearman,
+    Kendall,
+    PointBiserial,
+    Reliability,
+    Mcar,
+    Normality,
+    Factor,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Rolling {
+    pub column: String,
+    pub window: usize,
+}
+
+fn parse_confidence_level(source: &str) -> Result<f64, String> {
+    match source.parse() {
+        Ok(level) if 0.0 < level && level < 1.0 => Ok(level),
+        _ => Err(format!("expected a level between 0 and 1, not `{source}`")),
+    }
+}
+
+fn parse_percentile(source: &str) -> Result<f64, String> {
+    match source.trim().parse() {
+        Ok(percentile) if 0.0 < percentile && percentile < 1.0 => Ok(percentile),
+        _ => Err(format!(
+            "expected a percentile between 0 and 1, not `{source}`"
+        )),
+    }
+}
+
+fn parse_heatmap_path(source: &str) -> Result<String, String> {
+    if Path::new(source).extension().is_some_and(|extension| {
+        extension.eq_ignore_ascii_case("svg") || extension.eq_ignore_ascii_case("png")
+    }) {
+        Ok(source.to_owned())
+    } else {
+        Err(String::from(
+            "the heatmap is written as SVG or PNG; use a .svg or .png path",
+        ))
+    }
+}
+
+fn parse_crosstab(source: &str) -> Result<(String, String), String> {
+    match source.split_once(',') {
+        Some((rows, columns)) if !rows.is_empty() && !columns.is_empty() => {
+            Ok((rows.to_owned(), columns.to_owned()))
+        }
+        _ => Err(String::from("expected `A,B`")),
+    }
+}
+
+fn parse_rolling(source: &str) -> Result<Rolling, String> {
+    let Some((column, window)) = source.rsplit_once(':') else {
+        return Err(String::from("expected `DATE_COLUMN:WINDOW`"));
+    };
+    match window.parse() {
+        Ok(window) if window >= 2 => Ok(Rolling {
+            column: column.to_owned(),
+            window,
+        }),
+        _ => Err(format!(
+            "the window must be a whole number of at least 2 rows, not `{window}`"
+        )),
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Assertion {
+    pub var1: String,
+    pub var2: String,
+    pub expectation: Expectation,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Expectation {
+    Significant,
+    NotSignificant,
+}
+
+impl Expectation {
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Significant => "significant",
+            Self::NotSignificant => "not-significant",
+        }
+    }
+}
+
+fn parse_assertion(source: &str) -> Result<Assertion, String> {
+    let mut parts = source.rsplitn(3, ':');
+    let (Some(expectation), Some(var2), Some(var1)) = (parts.next(), parts.next(), parts.next())
+    else {
+        return Err(String::from("expected `VAR1:VAR2:EXPECTATION`"));
+    };
+    let expectation = match expectation {
+        "significant" => Expectation::Significant,
+        "not-significant" => Expectation::NotSignificant,
+        _ => {
+            return Err(format!(
+                "the expectation must be `significant` or `not-significant`, not `{expectation}`"
+            ))
+        }
+    };
+    Ok(Assertion {
+        var1: var1.to_owned(),
+        var2: var2.to_owned(),
+        expectation,
+    })
+}
+
+#[derive(ValueEnum, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    Md,
+    Json,
+    /// The Markdown report as a standalone HTML page.
+    Html,
+    /// The correlation matrices as numeric r and p values, one file per matrix.
+    Csv,
+}
+
+/// The matrices `--format csv` writes, each to `<report>.<matrix>.csv`.
+pub const CSV_MATRICES: [&str; 5] = ["pearson", "ordinal", "nominal", "cross", "point-biserial"];
+
+impl OutputFormat {
+    pub const fn extension(self) -> &'static str {
+        match self {
+            Self::Md => "md",
+            Self::Json => "json",
+            Self::Html => "html",
+            Self::Csv => "csv",
+        }
+    }
+}
+
+#[derive(ValueEnum, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportStyle {
+    Default,
+    /// Numbered variables with M and SD, the lower triangle only, and significance stars.
+    Apa,
+}
+
+#[derive(ValueEnum, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TableStyle {
+    /// Padded pipe tables.
+    Markdown,
+    /// Compact GitHub-flavored pipe tables.
+    Github,
+    /// Fixed-width tables with ASCII borders.
+    Ascii,
+    /// Comma-separated values.
+    Csv,
+}
+
+#[derive(ValueEnum, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum NumberLocale {
+    /// 1234.56789
+    Plain,
+    /// 1,234.56789
+    En,
+    /// 1.234,56789
+    De,
+    /// 1 234,56789
+    Fr,
+}
+
+impl NumberLocale {
+    /// The thousands separator and decimal mark.
+    pub const fn separators(self) -> (&'static str, &'static str) {
+        match self {
+            Self::Plain => ("", "."),
+            Self::En => (",", "."),
+            Self::De => (".", ","),
+            Self::Fr => ("\u{a0}", ","),
+        }
+    }
+}
+
+#[derive(ValueEnum, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DetrendMethod {
+    /// Replace every value by its change from the previous row, dropping the first row.
+    Difference,
+    /// Replace every value by its residual from a least-squares line over the row order.
+    Linear,
+}
+
+#[derive(ValueEnum, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum KendallVariant {
+    B,
+    C,
+}
+
+impl KendallVariant {
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::B => "b",
+            Self::C => "c",
+        }
+    }
+}
+
+#[derive(ValueEnum, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Engine {
+    /// Call scipy for every pair.
+    Scipy,
+    /// Compute r, and the p-value from the t distribution, in Rust.
+    Native,
+}
+
+#[derive(ValueEnum, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum OrdinalCoefficient {
+    Spearman,
+    /// Goodman and Kruskal's gamma.
+    Gamma,
+}
+
+#[derive(ValueEnum, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum NominalTest {
+    Kendall,
+    /// Pearson's chi-square test of independence, with Cramér's V as the effect size.
+    Chisq,
+}
+
+#[derive(ValueEnum, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FactorInput {
+    Auto,
+    Data,
+    Polychoric,
+}
+
+/// The rotations `factor_analyzer` accepts.
+#[derive(ValueEnum, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Rotation {
+    Promax,
+    Varimax,
+    Oblimin,
+    Oblimax,
+    Quartimin,
+    Quartimax,
+    Equamax,
+    GeominObl,
+    GeominOrt,
+    None,
+}
+
+impl Rotation {
+    /// The `rotation` argument of `FactorAnalyzer`; `None` leaves the solution unrotated.
+    pub const fn python_name(self) -> Option<&'static str> {
+        match self {
+            Self::Promax => Some("promax"),
+            Self::Varimax => Some("varimax"),
+            Self::Oblimin => Some("oblimin"),
+            Self::Oblimax => Some("oblimax"),
+            Self::Quartimin => Some("quartimin"),
+            Self::Quartimax => Some("quartimax"),
+            Self::Equamax => Some("equamax"),
+            Self::GeominObl => Some("geomin_obl"),
+            Self::GeominOrt => Some("geomin_ort"),
+            Self::None => None,
+        }
+    }
+}
+
+#[derive(ValueEnum, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SignificanceBasis {
+    Raw,
+    Adjusted,
+}
+
+#[derive(ValueEnum, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Correction {
+    None,
+    Bonferroni,
+    /// Holm's step-down procedure, which is uniformly more powerful than Bonferroni.
+    Holm,
+}
+
+impl Correction {
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Bonferroni => "Bonferroni",
+            Self::Holm => "Holm",
+        }
+    }
+}
+
+impl Options {
+    #[must_use]
+    pub fn resolve(mut self) -> Self {
+        if self.output.is_empty() {
+            self.output = if self.compare.is_some() {
+                format!("{}.compare.md", local_name(&self.source))
+            } else {
+                format!("{}.md", local_name(&self.source))
+            };
+        }
+        self.caption
+            .get_or_insert_with(|| String::from("表：{title} 相關係數矩陣"));
+        self.legend
+            .get_or_insert_with(|| match (self.style, self.bold_positive_only) {
+                (ReportStyle::Apa, _) => {
+                    String::from("\\* *p* < .05. \\*\\* *p* < .01. \\*\\*\\* *p* < .001.")
+                }
+                (ReportStyle::Default, false) => String::from("粗體為達顯著水準的相關係數。"),
+                (ReportStyle::Default, true) => String::from("粗體為達顯著水準的正相關係數。"),
+            });
+        self
+    }
+
+    /// The `--format`s, followed by a JSON sidecar whenever the reports are written to files, so
+    /// that every Markdown or HTML report comes with its numbers in a parseable form.
+    #[must_use]
+    pub fn report_formats(&self) -> Vec<OutputFormat> {
+        let mut formats = self.format.clone();
+        if self.output != "-" && !formats.contains(&OutputFormat::Json) {
+            formats.push(OutputFormat::Json);
+        }
+        formats
+    }
+
+    #[must_use]
+    pub fn report_path(&self, report_format: OutputFormat) -> PathBuf {
+        let Some(template) = &self.name_template else {
+            return Path::new(&self.output).with_extension(report_format.extension());
+        };
+        let source = Path::new(local_name(&self.source));
+        let directory = source
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .unwrap_or_else(|| Path::new("."));
+        let name = template
+            .replace(
+                "{stem}",
+                &source.file_stem().unwrap_or_default().to_string_lossy(),
+            )
+            .replace("{dir}", &directory.to_string_lossy())
+            .replace("{date}", &today())
+            .replace("{format}", report_format.extension())
+            .replace("{alpha}", &self.alpha.to_string());
+        PathBuf::from(format!("{name}.{}", report_format.extension()))
+    }
+
+    /// Where the report of the `--compare` file's own analysis goes, next to that file.
+    #[must_use]
+    pub fn compare_report_path(&self, compare: &str, report_format: OutputFormat) -> PathBuf {
+        PathBuf::from(format!(
+            "{}.{}",
+            local_name(compare),
+            report_format.extension()
+        ))
+    }
+
+    /// Where `--format csv` writes `matrix`: next to the report, or next to the `--compare` file
+    /// for the analysis of that file.
+    #[must_use]
+    pub fn matrix_path(&self, compare: Option<&str>, matrix: &str) -> PathBuf {
+        compare
+            .map_or_else(
+                || self.report_path(OutputFormat::Csv),
+                |compare| self.compare_report_path(compare, OutputFormat::Csv),
+            )
+            .with_extension(format!("{matrix}.csv"))
+    }
+
+    #[must_use]
+    pub const fn significance_basis(&self) -> SignificanceBasis {
+        match (self.significance_basis, self.correction) {
+            (Some(basis), _) => basis,
+            (None, Correction::None) => SignificanceBasis::Raw,
+            (None, _) => SignificanceBasis::Adjusted,
+        }
+    }
+
+    #[must_use]
+    pub fn includes(&self, section: Section) -> bool {
+        let skipped = match section {
+            Section::Describe => self.no_describe,
+            Section::Pearson => self.no_pearson,
+            Section::Spearman => self.no_spearman,
+            Section::Kendall => self.no_kendall,
+            Section::PointBiserial => self.no_point_biserial,
+            Section::Reliability => self.no_reliability,
+            Section::Mcar => self.no_mcar,
+            Section::Normality => self.no_normality,
+            Section::Factor => self.no_factor,
+        };
+        if self.describe_only {
+            return section == Section::Describe;
+        }
+        !skipped && (self.sections.is_empty() || self.sections.contains(&section))
+    }
+}
+
+/// Today's UTC date as `YYYY-MM-DD`, converted from the days since the epoch with Howard
+/// Hinnant's civil-from-days algorithm.
+fn today() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let days = i64::try_from(seconds / 86_400).unwrap_or_default() + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
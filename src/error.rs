@@ -0,0 +1,111 @@
+use crate::python::PythonError;
+use polars::prelude::PolarsError;
+use pyo3::PyErr;
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    io,
+};
+
+/// Why an analysis could not be carried out, so that the library can report the failure to its
+/// caller instead of panicking or exiting.
+#[derive(Debug)]
+pub enum AnalysisError {
+    /// The source could not be parsed as a table.
+    Csv { source: String, error: PolarsError },
+    /// A column the analysis needs is not in the data.
+    MissingColumn(String),
+    /// Described fields that are not in the data, each with the nearest column name when one is
+    /// close enough to be a typo.
+    MissingFields(Vec<String>),
+    /// A column holds values that cannot be used as numbers.
+    Cast { column: String, message: String },
+    /// Python, or one of its packages, failed.
+    Python(String),
+    /// A result could not be written.
+    Io { file_name: String, error: io::Error },
+    /// A source, field file or report could not be read.
+    Read { source: String, message: String },
+    /// The interactive browser could not take over the terminal.
+    Terminal(io::Error),
+    /// The command line or the field file asks for something the data cannot give.
+    Usage(String),
+    /// The source has too few rows to compute anything.
+    InsufficientData(String),
+    /// With `--no-nan`, the correlations that came out NaN.
+    NotANumber(Vec<String>),
+    /// The `--assert` expectations that did not hold.
+    AssertionFailed(Vec<String>),
+}
+
+/// Exit code used when the CSV file has too few rows to compute anything, so scripts can tell it
+/// apart from other failures.
+const INSUFFICIENT_DATA_EXIT_CODE: i32 = 3;
+
+/// Exit code used when an `--assert` expectation does not hold, so CI can tell it apart from
+/// other failures.
+const ASSERTION_FAILED_EXIT_CODE: i32 = 4;
+
+impl Display for AnalysisError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Csv { source, error } => write!(f, "Unable to open CSV file {source}: {error}"),
+            Self::MissingColumn(column) => write!(f, "Column {column} not found in the data."),
+            Self::MissingFields(fields) => {
+                write!(f, "Fields not found in the CSV file: {}", fields.join(", "))
+            }
+            Self::Cast { column, message } => write!(f, "Column {column} {message}."),
+            Self::Io { file_name, error } => write!(f, "Unable to write {file_name}: {error}"),
+            Self::Read { source, message } => write!(f, "Unable to read {source}: {message}"),
+            Self::Terminal(error) => write!(f, "Unable to run the interactive browser: {error}"),
+            Self::Python(message) | Self::Usage(message) | Self::InsufficientData(message) => {
+                write!(f, "{message}")
+            }
+            Self::NotANumber(correlations) => write!(f, "{}", correlations.join("\n")),
+            Self::AssertionFailed(failures) => write!(f, "{}", failures.join("\n")),
+        }
+    }
+}
+
+impl Error for AnalysisError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Csv { error, .. } => Some(error),
+            Self::Io { error, .. } | Self::Terminal(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<PyErr> for AnalysisError {
+    fn from(error: PyErr) -> Self {
+        Self::Python(error.to_string())
+    }
+}
+
+impl From<PythonError> for AnalysisError {
+    fn from(error: PythonError) -> Self {
+        Self::Python(error.to_string())
+    }
+}
+
+impl AnalysisError {
+    /// The exit code of the command line tool for this error: 1 unless scripts need to tell the
+    /// failure apart.
+    #[must_use]
+    pub const fn exit_code(&self) -> i32 {
+        match self {
+            Self::InsufficientData(_) => INSUFFICIENT_DATA_EXIT_CODE,
+            Self::AssertionFailed(_) => ASSERTION_FAILED_EXIT_CODE,
+            _ => 1,
+        }
+    }
+
+    /// An `Io` error for `file_name`, from the error of a polars writer as well as of the file.
+    pub fn io(file_name: impl Display, error: impl Into<Box<dyn Error + Send + Sync>>) -> Self {
+        Self::Io {
+            file_name: file_name.to_string(),
+            error: io::Error::other(error),
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use polars::prelude::{col, lit, Expr};
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Text { text: String, quoted: bool },
+    Operator(&'static str),
+    And,
+    Or,
+    Open,
+    Close,
+}
+
+const OPERATORS: [&str; 6] = [">=", "<=", "!=", "==", ">", "<"];
+
+/// Compiles `column OP value` comparisons joined by `AND`/`OR` (AND binds tighter, parentheses
+/// group) into a polars expression. Column names and text values containing spaces or
+/// operators can be quoted with `"` or `` ` ``.
+pub fn parse_filter(source: &str) -> Result<Expr, String> {
+    let tokens = tokenize(source)?;
+    let mut position = 0;
+    let expr = parse_or(&tokens, &mut position)?;
+    if position < tokens.len() {
+        return Err(format!("unexpected {:?}", tokens[position]));
+    }
+    Ok(expr)
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = source.trim_start();
+    while let Some(character) = rest.chars().next() {
+        if let Some(operator) = OPERATORS
+            .into_iter()
+            .find(|operator| rest.starts_with(operator))
+        {
+            tokens.push(Token::Operator(operator));
+            rest = &rest[operator.len()..];
+        } else if character == '(' || character == ')' {
+            tokens.push(if character == '(' {
+                Token::Open
+            } else {
+                Token::Close
+            });
+            rest = &rest[1..];
+        } else if character == '"' || character == '`' {
+            let Some(end) = rest[1..].find(character) else {
+                return Err(format!("unterminated {character} quote"));
+            };
+            tokens.push(Token::Text {
+                text: rest[1..=end].to_owned(),
+                quoted: true,
+            });
+            rest = &rest[end + 2..];
+        } else {
+            let end = rest
+                .find(|character: char| {
+                    character.is_whitespace() || "()<>=!\"`".contains(character)
+                })
+                .unwrap_or(rest.len());
+            if end == 0 {
+                return Err(format!("unexpected `{character}`"));
+            }
+            let word = &rest[..end];
+            tokens.push(match word.to_uppercase().as_str() {
+                "AND" => Token::And,
+                "OR" => Token::Or,
+                _ => Token::Text {
+                    text: word.to_owned(),
+                    quoted: false,
+                },
+            });
+            rest = &rest[end..];
+        }
+        rest = rest.trim_start();
+    }
+    Ok(tokens)
+}
+
+fn parse_or(tokens: &[Token], position: &mut usize) -> Result<Expr, String> {
+    let mut expr = parse_and(tokens, position)?;
+    while tokens.get(*position) == Some(&Token::Or) {
+        *position += 1;
+        expr = expr.or(parse_and(tokens, position)?);
+    }
+    Ok(expr)
+}
+
+fn parse_and(tokens: &[Token], position: &mut usize) -> Result<Expr, String> {
+    let mut expr = parse_comparison(tokens, position)?;
+    while tokens.get(*position) == Some(&Token::And) {
+        *position += 1;
+        expr = expr.and(parse_comparison(tokens, position)?);
+    }
+    Ok(expr)
+}
+
+fn parse_comparison(tokens: &[Token], position: &mut usize) -> Result<Expr, String> {
+    if tokens.get(*position) == Some(&Token::Open) {
+        *position += 1;
+        let expr = parse_or(tokens, position)?;
+        if tokens.get(*position) != Some(&Token::Close) {
+            return Err(String::from("missing `)`"));
+        }
+        *position += 1;
+        return Ok(expr);
+    }
+    let (Some(Token::Text { text: column, .. }), Some(Token::Operator(operator)), Some(value)) = (
+        tokens.get(*position),
+        tokens.get(*position + 1),
+        tokens.get(*position + 2),
+    ) else {
+        return Err(format!(
+            "expected `column OP value` at {:?}",
+            tokens.get(*position)
+        ));
+    };
+    let value = match value {
+        Token::Text {
+            text,
+            quoted: false,
+        } => text.parse::<f64>().map_or_else(|_| lit(text.as_str()), lit),
+        Token::Text { text, quoted: true } => lit(text.as_str()),
+        token => return Err(format!("expected a value, found {token:?}")),
+    };
+    *position += 3;
+    let column = col(column);
+    Ok(match *operator {
+        ">=" => column.gt_eq(value),
+        "<=" => column.lt_eq(value),
+        "!=" => column.neq(value),
+        "==" => column.eq(value),
+        ">" => column.gt(value),
+        _ => column.lt(value),
+    })
+}
@@ -0,0 +1,94 @@
+use statrs::distribution::{ContinuousCDF, FisherSnedecor};
+
+pub struct Anova {
+    pub f: f64,
+    pub degrees_of_freedom: (f64, f64),
+    pub p_value: f64,
+    /// The share of the total sum of squares between the groups.
+    pub eta_squared: f64,
+    /// η² corrected for its upward bias, `(SS_b − df_b · MS_w) / (SS_t + MS_w)`.
+    pub omega_squared: f64,
+}
+
+/// One-way ANOVA of `groups`, each holding at least one value. With two groups F is the square
+/// of the pooled-variance t statistic.
+pub fn one_way_anova(groups: &[Vec<f64>]) -> Result<Anova, &'static str> {
+    let total = groups.iter().map(Vec::len).sum::<usize>();
+    let grand_mean = groups.iter().flatten().sum::<f64>() / total as f64;
+    let means = groups
+        .iter()
+        .map(|group| group.iter().sum::<f64>() / group.len() as f64)
+        .collect::<Vec<f64>>();
+    let between = groups
+        .iter()
+        .zip(&means)
+        .map(|(group, mean)| group.len() as f64 * (mean - grand_mean).powi(2))
+        .sum::<f64>();
+    let within = groups
+        .iter()
+        .zip(&means)
+        .map(|(group, mean)| {
+            group
+                .iter()
+                .map(|value| (value - mean).powi(2))
+                .sum::<f64>()
+        })
+        .sum::<f64>();
+    if within == 0.0 {
+        return Err("組內沒有變異，無法檢定");
+    }
+    let degrees_of_freedom = ((groups.len() - 1) as f64, (total - groups.len()) as f64);
+    let f = (between / degrees_of_freedom.0) / (within / degrees_of_freedom.1);
+    let p_value = FisherSnedecor::new(degrees_of_freedom.0, degrees_of_freedom.1)
+        .map_or(f64::NAN, |distribution| distribution.sf(f));
+    let mean_square_within = within / degrees_of_freedom.1;
+    Ok(Anova {
+        f,
+        degrees_of_freedom,
+        p_value,
+        eta_squared: between / (between + within),
+        omega_squared: degrees_of_freedom.0.mul_add(-mean_square_within, between)
+            / (between + within + mean_square_within),
+    })
+}
+
+/// Levene's test centered on the group medians (Brown & Forsythe, 1974), scipy's default: the
+/// ANOVA of every value's absolute deviation from its group's median.
+pub fn levene(groups: &[Vec<f64>]) -> Result<Anova, &'static str> {
+    let deviations = groups
+        .iter()
+        .map(|group| {
+            let mut sorted = group.clone();
+            sorted.sort_by(f64::total_cmp);
+            let middle = sorted.len() / 2;
+            let median = if sorted.len() % 2 == 0 {
+                f64::midpoint(sorted[middle - 1], sorted[middle])
+            } else {
+                sorted[middle]
+            };
+            group.iter().map(|value| (value - median).abs()).collect()
+        })
+        .collect::<Vec<Vec<f64>>>();
+    one_way_anova(&deviations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{levene, one_way_anova};
+
+    #[test]
+    fn anova_and_levene_of_two_groups() {
+        let groups = vec![vec![1.0, 2.0, 3.0], vec![2.0, 4.0, 6.0]];
+        // Pooled variance 2.5, so t = 2 / √(2.5 · 2/3) and F = t² = 2.4.
+        let anova = one_way_anova(&groups).unwrap();
+        assert_eq!(anova.degrees_of_freedom, (1.0, 4.0));
+        assert!((anova.f - 2.4).abs() < 1e-12);
+        // SS_b = 6 and SS_w = 10, so η² = 6 / 16 and ω² = (6 − 2.5) / (16 + 2.5).
+        assert!((anova.eta_squared - 0.375).abs() < 1e-12);
+        assert!((anova.omega_squared - 3.5 / 18.5).abs() < 1e-12);
+        // Deviations from the medians are [1, 0, 1] and [2, 0, 2].
+        let levene = levene(&groups).unwrap();
+        assert!((levene.f - 0.8).abs() < 1e-12);
+        assert!(one_way_anova(&[vec![1.0, 1.0], vec![2.0, 2.0]]).is_err());
+    }
+}
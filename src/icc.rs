@@ -0,0 +1,107 @@
+use statrs::distribution::{ContinuousCDF, FisherSnedecor};
+
+pub struct IccEstimate {
+    pub value: f64,
+    pub lower: f64,
+    pub upper: f64,
+}
+
+pub struct Icc {
+    pub subjects: usize,
+    pub raters: usize,
+    /// ICC(2,1): the agreement of a single rater.
+    pub single: IccEstimate,
+    /// ICC(2,k): the agreement of the mean of the k raters.
+    pub average: IccEstimate,
+    pub f: f64,
+    pub degrees_of_freedom: (f64, f64),
+    pub p_value: f64,
+}
+
+/// Two-way random-effects ICC (Shrout & Fleiss, 1979) and its 95% confidence intervals, from
+/// the ANOVA decomposition of the rows rated by every column. Rows with a missing rating are
+/// dropped.
+pub fn icc(columns: &[Vec<Option<f64>>]) -> Result<Icc, &'static str> {
+    let raters = columns.len();
+    if raters < 2 {
+        return Err("至少需要 2 個評分者欄位");
+    }
+    let rows = (0..columns[0].len())
+        .filter_map(|row| columns.iter().map(|column| column[row]).collect())
+        .collect::<Vec<Vec<f64>>>();
+    let subjects = rows.len();
+    if subjects < 2 {
+        return Err("完整評分的資料少於 2 筆");
+    }
+    let (n, k) = (subjects as f64, raters as f64);
+    let grand_mean = rows.iter().flatten().sum::<f64>() / (n * k);
+    let total = rows
+        .iter()
+        .flatten()
+        .map(|value| (value - grand_mean).powi(2))
+        .sum::<f64>();
+    let between_subjects = k * rows
+        .iter()
+        .map(|row| (row.iter().sum::<f64>() / k - grand_mean).powi(2))
+        .sum::<f64>();
+    let between_raters = n
+        * (0..raters)
+            .map(|rater| (rows.iter().map(|row| row[rater]).sum::<f64>() / n - grand_mean).powi(2))
+            .sum::<f64>();
+    let subject_square = between_subjects / (n - 1.0);
+    let rater_square = between_raters / (k - 1.0);
+    let error_square = (total - between_subjects - between_raters) / ((n - 1.0) * (k - 1.0));
+    if error_square <= 0.0 {
+        return Err("殘差變異為 0，無法計算");
+    }
+
+    let single = (subject_square - error_square)
+        / (k - 1.0).mul_add(
+            error_square,
+            k.mul_add((rater_square - error_square) / n, subject_square),
+        );
+    let average =
+        (subject_square - error_square) / (subject_square + (rater_square - error_square) / n);
+    let statistic = subject_square / error_square;
+    let degrees_of_freedom = (n - 1.0, (n - 1.0) * (k - 1.0));
+    let p_value = FisherSnedecor::new(degrees_of_freedom.0, degrees_of_freedom.1)
+        .map_or(f64::NAN, |distribution| 1.0 - distribution.cdf(statistic));
+
+    let rater_weight = k * single / (n * (1.0 - single));
+    let error_weight = 1.0 + k * single * (n - 1.0) / (n * (1.0 - single));
+    let satterthwaite = (rater_weight * rater_square + error_weight * error_square).powi(2)
+        / ((rater_weight * rater_square).powi(2) / (k - 1.0)
+            + (error_weight * error_square).powi(2) / ((n - 1.0) * (k - 1.0)));
+    let quantile = |first: f64, second: f64| {
+        FisherSnedecor::new(first, second)
+            .map_or(f64::NAN, |distribution| distribution.inverse_cdf(0.975))
+    };
+    let (upper_f, lower_f) = (
+        quantile(n - 1.0, satterthwaite),
+        quantile(satterthwaite, n - 1.0),
+    );
+    let spread = k.mul_add(rater_square, (k.mul_add(n, -k) - n) * error_square);
+    let lower = n * upper_f.mul_add(-error_square, subject_square)
+        / upper_f.mul_add(spread, n * subject_square);
+    let upper = n * lower_f.mul_add(subject_square, -error_square)
+        / (n * lower_f).mul_add(subject_square, spread);
+    let stepped_up = |value: f64| k * value / (k - 1.0).mul_add(value, 1.0);
+
+    Ok(Icc {
+        subjects,
+        raters,
+        single: IccEstimate {
+            value: single,
+            lower,
+            upper,
+        },
+        average: IccEstimate {
+            value: average,
+            lower: stepped_up(lower),
+            upper: stepped_up(upper),
+        },
+        f: statistic,
+        degrees_of_freedom,
+        p_value,
+    })
+}
@@ -0,0 +1,200 @@
+use crate::{error::AnalysisError, PairCounts};
+use serde::{Deserialize, Serialize};
+use serde_json::from_reader;
+use statrs::distribution::{ContinuousCDF, Normal};
+use std::{collections::BTreeMap, fmt::Write, fs::File};
+
+#[derive(Serialize, Deserialize)]
+pub struct JsonReport {
+    pub source: String,
+    pub variables: Vec<String>,
+    pub correlations: Vec<JsonCorrelation>,
+    /// The descriptive statistics of each numeric field, keyed by statistic.
+    #[serde(default)]
+    pub descriptives: BTreeMap<String, BTreeMap<String, f64>>,
+    #[serde(default)]
+    pub factor_loadings: BTreeMap<String, Vec<f64>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scipy_version: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct JsonCorrelation {
+    pub method: String,
+    pub var1: String,
+    pub var2: String,
+    pub r: Option<f64>,
+    pub p_value: Option<f64>,
+    pub adjusted_p_value: Option<f64>,
+    pub n: Option<usize>,
+    pub significant: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pair_counts: Option<PairCounts>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub standard_error: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ci_low: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ci_high: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chi_square: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub degrees_of_freedom: Option<usize>,
+}
+
+impl JsonCorrelation {
+    fn same_pair(&self, other: &Self) -> bool {
+        self.method == other.method
+            && ((self.var1 == other.var1 && self.var2 == other.var2)
+                || (self.var1 == other.var2 && self.var2 == other.var1))
+    }
+
+    fn sign(&self) -> Option<bool> {
+        self.r.filter(|r| *r != 0.0).map(|r| r > 0.0)
+    }
+}
+
+pub fn load_report(file_name: &str) -> Result<JsonReport, AnalysisError> {
+    let read_error = |message: String| AnalysisError::Read {
+        source: file_name.to_owned(),
+        message,
+    };
+    let file = File::open(file_name).map_err(|error| read_error(error.to_string()))?;
+    from_reader(file).map_err(|error| read_error(format!("not a valid JSON report: {error}")))
+}
+
+pub fn diff_reports(old: &JsonReport, new: &JsonReport) -> String {
+    let mut result = format!("# {} → {}\n\n", old.source, new.source);
+    let added = new
+        .variables
+        .iter()
+        .filter(|name| !old.variables.contains(name));
+    let removed = old
+        .variables
+        .iter()
+        .filter(|name| !new.variables.contains(name));
+    for (title, names) in [
+        ("新增的變數", added.collect::<Vec<_>>()),
+        ("移除的變數", removed.collect()),
+    ] {
+        if !names.is_empty() {
+            writeln!(result, "## {title}\n").unwrap();
+            for name in names {
+                writeln!(result, "- {name}").unwrap();
+            }
+            result.push('\n');
+        }
+    }
+
+    let mut significance = Vec::new();
+    let mut sign = Vec::new();
+    for new_correlation in &new.correlations {
+        let Some(old_correlation) = old
+            .correlations
+            .iter()
+            .find(|old_correlation| old_correlation.same_pair(new_correlation))
+        else {
+            continue;
+        };
+        let description = format!(
+            "- {}：{} × {}（r: {} → {}）",
+            new_correlation.method,
+            new_correlation.var1,
+            new_correlation.var2,
+            format_r(old_correlation.r),
+            format_r(new_correlation.r),
+        );
+        if old_correlation.significant != new_correlation.significant {
+            significance.push(format!(
+                "{description}：{} → {}",
+                significance_label(old_correlation.significant),
+                significance_label(new_correlation.significant)
+            ));
+        }
+        if let (Some(old_sign), Some(new_sign)) = (old_correlation.sign(), new_correlation.sign()) {
+            if old_sign != new_sign {
+                sign.push(description);
+            }
+        }
+    }
+    for (title, lines) in [("顯著性改變", significance), ("正負號改變", sign)] {
+        if !lines.is_empty() {
+            writeln!(result, "## {title}\n\n{}\n", lines.join("\n")).unwrap();
+        }
+    }
+    result
+}
+
+/// One row per report with the correlation of `var1` × `var2`, then the fixed-effects pooled
+/// estimate: the Fisher z of every r weighted by n − 3, transformed back to r.
+pub fn merge_reports(
+    reports: &[JsonReport],
+    (var1, var2): (&str, &str),
+    method: Option<&str>,
+) -> String {
+    let mut result = format!(
+        "# {var1} × {var2}\n\n| study | method | r | p value | n |\n| --- | --- | --- | --- | --- |\n"
+    );
+    let (mut weight_sum, mut weighted_z) = (0.0, 0.0);
+    for report in reports {
+        let correlation = report.correlations.iter().find(|correlation| {
+            method.is_none_or(|method| correlation.method == method)
+                && ((correlation.var1 == var1 && correlation.var2 == var2)
+                    || (correlation.var1 == var2 && correlation.var2 == var1))
+        });
+        let Some(correlation) = correlation else {
+            writeln!(result, "| {} | - | - | - | - |", report.source).unwrap();
+            continue;
+        };
+        writeln!(
+            result,
+            "| {} | {} | {} | {} | {} |",
+            report.source,
+            correlation.method,
+            format_r(correlation.r),
+            format_r(correlation.p_value),
+            correlation
+                .n
+                .map_or_else(|| "-".to_owned(), |n| n.to_string())
+        )
+        .unwrap();
+        if let (Some(r), Some(n)) = (correlation.r, correlation.n) {
+            if n > 3 && r.abs() < 1.0 {
+                let weight = n as f64 - 3.0;
+                weight_sum += weight;
+                weighted_z += weight * r.atanh();
+            }
+        }
+    }
+    if weight_sum == 0.0 {
+        result.push_str("\n沒有可合併的相關係數。\n");
+        return result;
+    }
+    let z = weighted_z / weight_sum;
+    let standard_error = weight_sum.sqrt().recip();
+    let normal = Normal::standard();
+    let p_value = 2.0 * (1.0 - normal.cdf((z / standard_error).abs()));
+    let margin = normal.inverse_cdf(0.975) * standard_error;
+    writeln!(
+        result,
+        "\n固定效果合併估計：r = {:.5}，95% CI [{:.5}, {:.5}]，p value = {:.5}（總權重 n − 3 = {weight_sum}）",
+        z.tanh(),
+        (z - margin).tanh(),
+        (z + margin).tanh(),
+        p_value
+    )
+    .unwrap();
+    result
+}
+
+fn format_r(r: Option<f64>) -> String {
+    r.map_or_else(|| "-".to_owned(), |r| format!("{r:.5}"))
+}
+
+const fn significance_label(significant: bool) -> &'static str {
+    if significant {
+        "顯著"
+    } else {
+        "不顯著"
+    }
+}
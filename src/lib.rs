@@ -0,0 +1,5339 @@
+#![warn(clippy::all, clippy::pedantic, clippy::nursery)]
+#![allow(clippy::cast_precision_loss)]
+
+mod cli;
+mod error;
+mod filter;
+mod groups;
+mod icc;
+mod json;
+mod logging;
+mod mcar;
+mod png;
+mod polychoric;
+mod python;
+mod regression;
+mod report;
+mod tui;
+mod xlsx;
+
+pub use cli::{Cli, Command, Options};
+pub use error::AnalysisError;
+pub use logging::init as init_logging;
+pub use python::{FactorSolution, PythonError};
+
+use cli::{
+    is_url, local_name, Correction, DetrendMethod, Engine, Expectation, FactorInput,
+    KendallVariant, NominalTest, NumberLocale, OrdinalCoefficient, OutputFormat, ReportStyle,
+    Section, SignificanceBasis, SourceFormat, TableStyle, CSV_MATRICES, FETCH_TIMEOUT,
+};
+use filter::parse_filter;
+use groups::{levene, one_way_anova};
+use icc::{icc, Icc};
+use json::{diff_reports, load_report, merge_reports, JsonReport};
+use log::{debug, info};
+use mcar::{invert, little_mcar, symmetric_eigenvalues, LittleMcar};
+use polars::{
+    export::rayon::prelude::{
+        IndexedParallelIterator, IntoParallelIterator, IntoParallelRefIterator, ParallelIterator,
+    },
+    prelude::*,
+};
+use polychoric::polychoric_matrix;
+use python::{
+    capturing_warnings, check_scipy, chi2_contingency, decode, factor_analysis, fisher_exact,
+    importable, kendall, mcdonald_omega, pearson, scipy_version, shapiro, spearman, with_retries,
+    with_timeout,
+};
+use regex::Regex;
+use regression::{ols, Ols};
+use report::{
+    html_report, json_report, render_analysis, render_comparison, timing_section, write_heatmap,
+    write_matrices, write_network, write_pair, write_ranks, write_rolling, write_tidy,
+};
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize};
+use serde_json::{from_str, json, to_string_pretty, to_writer_pretty, Value};
+use statrs::distribution::{Continuous, ContinuousCDF, Normal, StudentsT};
+use std::{
+    collections::{BTreeMap, HashMap},
+    env,
+    fmt::Write as _,
+    fs::File,
+    io::{self, stdout, BufWriter, Cursor, Read, Write},
+    iter::once,
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+use xlsx::xlsx_to_csv;
+
+/// A question in the field file. Build it by deserializing the field file's JSON or YAML.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Field {
+    pub name: String,
+    pub scale: Scale,
+    #[serde(default, deserialize_with = "deserialize_recode")]
+    recode: Vec<(f64, f64)>,
+    clamp: Option<(f64, f64)>,
+    round: Option<i32>,
+    #[serde(default)]
+    reverse: bool,
+    reliability_group: Option<String>,
+    /// Includes this field in the factor analysis.
+    #[serde(default)]
+    factor: bool,
+    /// Makes this a derived field: the row sum of the named fields after their own cleaning.
+    #[serde(default)]
+    sum_of: Vec<String>,
+    /// Reads the column with this type instead of the one polars infers.
+    dtype: Option<ColumnType>,
+}
+
+impl Field {
+    /// Reverse-scoring mirrors values within the clamp range, or the observed range without one.
+    fn clean(&self, data: Vec<Option<f64>>) -> Vec<Option<f64>> {
+        let data = data
+            .into_iter()
+            .map(|data| {
+                data.map(|value| {
+                    let value = self
+                        .recode
+                        .iter()
+                        .find(|(from, _)| (from - value).abs() < f64::EPSILON)
+                        .map_or(value, |(_, to)| *to);
+                    let value = self.clamp.map_or(value, |(min, max)| value.clamp(min, max));
+                    self.round.map_or(value, |digits| {
+                        let factor = 10_f64.powi(digits);
+                        (value * factor).round() / factor
+                    })
+                })
+            })
+            .collect::<Vec<Option<f64>>>();
+        if !self.reverse {
+            return data;
+        }
+        let (min, max) = self.clamp.unwrap_or_else(|| {
+            data.iter()
+                .flatten()
+                .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), value| {
+                    (min.min(*value), max.max(*value))
+                })
+        });
+        data.into_iter()
+            .map(|data| data.map(|value| min + max - value))
+            .collect()
+    }
+}
+
+/// Forces `coefficient` on one pair of fields regardless of their scales.
+#[derive(Deserialize, Debug)]
+struct Override {
+    fields: (String, String),
+    coefficient: Coefficient,
+}
+
+/// The field description file is either the bare list of fields or an object that also lists
+/// coefficient overrides.
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+enum FieldFile {
+    Fields(Vec<Field>),
+    Config {
+        fields: Vec<Field>,
+        #[serde(default)]
+        overrides: Vec<Override>,
+    },
+}
+
+fn deserialize_recode<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<(f64, f64)>, D::Error> {
+    HashMap::<String, f64>::deserialize(deserializer)?
+        .into_iter()
+        .map(|(from, to)| {
+            from.parse::<f64>()
+                .map(|from| (from, to))
+                .map_err(|_| D::Error::custom(format!("invalid recode key `{from}`")))
+        })
+        .collect()
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scale {
+    Nominal,
+    Ordinal,
+    Interval,
+    Ratio,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+enum ColumnType {
+    Float,
+    Integer,
+    Text,
+}
+
+impl ColumnType {
+    const fn data_type(self) -> DataType {
+        match self {
+            Self::Float => DataType::Float64,
+            Self::Integer => DataType::Int64,
+            Self::Text => DataType::Utf8,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+enum Coefficient {
+    Pearson,
+    Spearman,
+    Gamma,
+    Kendall,
+    Biserial,
+    PointBiserial,
+    ChiSquare,
+}
+
+impl Coefficient {
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Pearson => "Pearson",
+            Self::Spearman => "Spearman",
+            Self::Gamma => "Gamma",
+            Self::Kendall => "Kendall",
+            Self::Biserial => "Biserial",
+            Self::PointBiserial => "Point-biserial",
+            Self::ChiSquare => "Chi-square",
+        }
+    }
+
+    const fn section(self) -> Section {
+        match self {
+            Self::Pearson | Self::Biserial => Section::Pearson,
+            Self::Spearman | Self::Gamma => Section::Spearman,
+            Self::Kendall | Self::ChiSquare => Section::Kendall,
+            Self::PointBiserial => Section::PointBiserial,
+        }
+    }
+}
+
+/// The coefficient used for a pair of fields is decided by the weaker of the two scales:
+/// Pearson needs both to be interval or ratio, an ordinal field falls back to `ordinal`
+/// (Spearman or gamma), and any nominal field falls back to `nominal` (chi-square or Kendall).
+const fn coefficient_for(
+    a: Scale,
+    b: Scale,
+    ordinal: Coefficient,
+    nominal: Coefficient,
+) -> Coefficient {
+    match (a, b) {
+        (Scale::Nominal, _) | (_, Scale::Nominal) => nominal,
+        (Scale::Ordinal, _) | (_, Scale::Ordinal) => ordinal,
+        (Scale::Interval | Scale::Ratio, Scale::Interval | Scale::Ratio) => Coefficient::Pearson,
+    }
+}
+
+#[derive(Clone)]
+pub enum CorrelationValue {
+    Valid(Box<CorrelationResult>),
+    /// A field with itself.
+    Diagonal,
+    NotValid,
+    /// Either field has the same value in every row the pair is computed over.
+    Constant,
+    InsufficientSample,
+    TooManyCategories,
+    Skipped,
+    Failed(PythonError),
+}
+
+pub struct CorrelationMatrix {
+    pub names: Vec<String>,
+    pub cells: Vec<Vec<CorrelationValue>>,
+    /// How many p-values `--correction` adjusted for; `None` without a correction.
+    pub comparisons: Option<usize>,
+}
+
+/// The matrices of `run_correlations`; a matrix is `None` when its section is left out.
+pub struct CorrelationMatrices {
+    pub pearson: Option<CorrelationMatrix>,
+    pub spearman: Option<CorrelationMatrix>,
+    pub kendall: Option<CorrelationMatrix>,
+}
+
+impl CorrelationMatrix {
+    fn upper_triangle(&self) -> impl Iterator<Item = (&str, &str, &CorrelationValue)> {
+        self.cells
+            .iter()
+            .enumerate()
+            .flat_map(move |(row, values)| {
+                values
+                    .iter()
+                    .enumerate()
+                    .skip(row + 1)
+                    .map(move |(column, value)| {
+                        (self.names[row].as_str(), self.names[column].as_str(), value)
+                    })
+            })
+    }
+}
+
+struct CovarianceMatrix {
+    names: Vec<String>,
+    cells: Vec<Vec<f64>>,
+}
+
+type Column = Vec<Option<f64>>;
+
+/// A processed column and which of its values were observed.
+type ObservedColumn<'a> = (&'a [f64], &'a [bool]);
+
+/// The fields that could be cast to numbers, their columns, and the warnings about the others.
+type NumericColumns<'a> = (Vec<&'a Field>, Vec<Column>, Vec<String>);
+
+/// The correlation matrices, then the `--rows`/`--cols` and point-biserial blocks.
+type Correlations = (
+    [Option<CorrelationMatrix>; 3],
+    [Option<CrossCorrelation>; 2],
+);
+
+impl CorrelationValue {
+    const fn is_significant(&self) -> bool {
+        match self {
+            Self::NotValid
+            | Self::Diagonal
+            | Self::Constant
+            | Self::InsufficientSample
+            | Self::TooManyCategories
+            | Self::Skipped
+            | Self::Failed(_) => false,
+            Self::Valid(result) => result.is_significant(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct CorrelationResult {
+    pub r: f64,
+    pub p_value: f64,
+    adjusted_p_value: Option<f64>,
+    pub n: usize,
+    /// Rows left out of `n` by pairwise deletion because either field was missing.
+    missing_rows: usize,
+    tied_pairs: Option<usize>,
+    pair_counts: Option<PairCounts>,
+    standard_error: Option<f64>,
+    significant: bool,
+    bold: bool,
+    locale: NumberLocale,
+    precision: usize,
+    python_warnings: Vec<String>,
+    statistic: &'static str,
+    exact_test: bool,
+    reference: Option<(f64, f64)>,
+    /// The chi-square statistic and its degrees of freedom behind a Cramér's V.
+    chi_square: Option<(f64, usize)>,
+    /// The level of `ci_low` and `ci_high`; `None` for statistics without an interval.
+    confidence_level: Option<f64>,
+    /// `None` when the sample is too small for the interval.
+    pub ci_low: Option<f64>,
+    pub ci_high: Option<f64>,
+    basis: SignificanceBasis,
+}
+
+impl CorrelationResult {
+    const fn is_significant(&self) -> bool {
+        self.significant
+    }
+
+    /// The p-value that decides significance.
+    fn tested_p_value(&self) -> f64 {
+        match self.basis {
+            SignificanceBasis::Raw => self.p_value,
+            SignificanceBasis::Adjusted => self.adjusted_p_value.unwrap_or(self.p_value),
+        }
+    }
+
+    fn apply_significance_basis(&mut self, basis: SignificanceBasis, alpha: f64) {
+        self.basis = basis;
+        self.significant = self.tested_p_value() < alpha;
+        self.bold = self.significant;
+    }
+
+    fn apply_bold_rule(&mut self, positive_only: bool) {
+        self.bold = self.significant && (!positive_only || self.r > 0.0);
+    }
+}
+
+impl From<(f64, f64, usize)> for CorrelationResult {
+    fn from(value: (f64, f64, usize)) -> Self {
+        Self {
+            r: value.0,
+            p_value: value.1,
+            adjusted_p_value: None,
+            n: value.2,
+            missing_rows: 0,
+            tied_pairs: None,
+            pair_counts: None,
+            standard_error: None,
+            significant: value.1 < 0.05,
+            bold: value.1 < 0.05,
+            locale: NumberLocale::Plain,
+            precision: 5,
+            python_warnings: Vec::new(),
+            statistic: "r",
+            exact_test: false,
+            reference: None,
+            chi_square: None,
+            confidence_level: None,
+            ci_low: None,
+            ci_high: None,
+            basis: SignificanceBasis::Raw,
+        }
+    }
+}
+
+/// The pairs of respondents behind a Kendall cell, split by whether they are tied on neither,
+/// one or both variables, with the tau computed natively from them.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+struct PairCounts {
+    concordant: usize,
+    discordant: usize,
+    x_ties: usize,
+    y_ties: usize,
+    joint_ties: usize,
+    tau: f64,
+}
+
+#[allow(clippy::struct_excessive_bools)]
+struct Analysis {
+    duplicates: Option<usize>,
+    filter: Option<(String, usize, usize)>,
+    sample: Option<(usize, usize)>,
+    standardized: bool,
+    detrend: Option<(String, DetrendMethod)>,
+    offline: bool,
+    scipy_version: Option<String>,
+    warnings: Vec<String>,
+    weights: Option<String>,
+    ordinal: Coefficient,
+    nominal: Coefficient,
+    loading_cutoff: Option<f64>,
+    group_loadings: bool,
+    page_size: Option<usize>,
+    spearman_brown: Option<f64>,
+    correction: Correction,
+    style: ReportStyle,
+    table_style: TableStyle,
+    number_locale: NumberLocale,
+    precision: usize,
+    explain: bool,
+    caption: String,
+    legend: String,
+    moments: HashMap<String, (f64, f64)>,
+    tie_proportions: Vec<(String, usize, f64)>,
+    description: Option<DataFrame>,
+    frequencies: Vec<Frequency>,
+    crosstabs: Vec<Crosstab>,
+    weighted_description: Option<DataFrame>,
+    pearson: Option<CorrelationMatrix>,
+    spearman: Option<CorrelationMatrix>,
+    kendall: Option<CorrelationMatrix>,
+    covariance: Option<CovarianceMatrix>,
+    cross: Option<CrossCorrelation>,
+    /// Binary nominal fields by the ordinal, interval and ratio fields.
+    point_biserial: Option<CrossCorrelation>,
+    reliability: Vec<Reliability>,
+    condition_number: Option<f64>,
+    response_rates: Option<Vec<ResponseRate>>,
+    min_response_rate: f64,
+    mcar: Option<Result<LittleMcar, &'static str>>,
+    normality: Option<Vec<Normality>>,
+    /// The significance level of the normality tests.
+    alpha: f64,
+    icc: Option<Result<Icc, &'static str>>,
+    paired_t_test: Option<Result<PairedTTest, String>>,
+    regression: Option<Regression>,
+    group_comparison: Option<GroupComparison>,
+    factor: Option<Result<FactorSolution, PythonError>>,
+    factor_warnings: Vec<String>,
+    /// Wall-clock time of each step, for `--timings`.
+    timings: Vec<(String, Duration)>,
+}
+
+impl Analysis {
+    const fn matrices(&self) -> [(Coefficient, &Option<CorrelationMatrix>); 3] {
+        [
+            (Coefficient::Pearson, &self.pearson),
+            (self.ordinal, &self.spearman),
+            (self.nominal, &self.kendall),
+        ]
+    }
+
+    fn correlation_pairs(&self) -> Vec<(&str, &str, &'static str, &CorrelationValue)> {
+        let mut pairs = Vec::new();
+        for (coefficient, matrix) in self.matrices() {
+            for (name, other_name, value) in
+                matrix.iter().flat_map(CorrelationMatrix::upper_triangle)
+            {
+                pairs.push((name, other_name, coefficient.name(), value));
+            }
+        }
+        for cross in self.cross.iter().chain(&self.point_biserial) {
+            for (column_name, values) in cross.column_names.iter().zip(&cross.cells) {
+                for (row_name, (coefficient, value)) in cross.row_names.iter().zip(values) {
+                    pairs.push((
+                        row_name.as_str(),
+                        column_name.as_str(),
+                        coefficient.name(),
+                        value,
+                    ));
+                }
+            }
+        }
+        pairs
+    }
+
+    fn python_warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        for (name, other_name, coefficient, value) in self.correlation_pairs() {
+            if let CorrelationValue::Valid(result) = value {
+                warnings.extend(
+                    result.python_warnings.iter().map(|message| {
+                        format!("{name} × {other_name} 的 {coefficient}：{message}")
+                    }),
+                );
+            }
+        }
+        warnings.extend(
+            self.factor_warnings
+                .iter()
+                .map(|message| format!("因子分析：{message}")),
+        );
+        warnings
+    }
+}
+
+/// Unlike [`CorrelationMatrix`] the block is not symmetric, so `cells` is indexed by column and
+/// then by row.
+struct CrossCorrelation {
+    row_names: Vec<String>,
+    column_names: Vec<String>,
+    cells: Vec<Vec<(Coefficient, CorrelationValue)>>,
+    comparisons: Option<usize>,
+}
+
+struct RollingCorrelation {
+    start: String,
+    end: String,
+    var1: String,
+    var2: String,
+    coefficient: Coefficient,
+    value: CorrelationValue,
+}
+
+/// The `--group-compare` groups and the test of their means.
+struct GroupComparison {
+    metric: String,
+    group: String,
+    /// Each group's label, size, mean and standard deviation of the metric.
+    groups: Vec<(String, usize, f64, f64)>,
+    test: Result<GroupTest, String>,
+}
+
+struct GroupTest {
+    /// `t` for two groups, `F` for more.
+    statistic_name: &'static str,
+    statistic: f64,
+    degrees_of_freedom: Vec<f64>,
+    p_value: f64,
+    /// Levene's W and its p-value; `None` when it cannot be computed.
+    levene: Option<(f64, f64)>,
+    eta_squared: f64,
+    omega_squared: f64,
+}
+
+/// The `--regression` model and its fit.
+struct Regression {
+    dependent: String,
+    predictors: Vec<String>,
+    fit: Result<Ols, &'static str>,
+}
+
+struct PairedTTest {
+    pre: String,
+    post: String,
+    pairs: usize,
+    t: f64,
+    degrees_of_freedom: f64,
+    p_value: f64,
+    mean_difference: f64,
+    /// The 95% confidence interval of the mean difference.
+    interval: (f64, f64),
+}
+
+/// The Shapiro-Wilk test of a field's observed values, or why it was not run.
+struct Normality {
+    field: String,
+    n: usize,
+    /// W and its p-value.
+    test: Result<(f64, f64), String>,
+}
+
+/// The categories of a nominal field, most frequent first and ties in order of their labels.
+struct Frequency {
+    field: String,
+    /// Each category with its count; `None` counts the missing values, after the categories.
+    counts: Vec<(Option<String>, usize)>,
+}
+
+impl Frequency {
+    fn total(&self) -> usize {
+        self.counts.iter().map(|(_, count)| count).sum()
+    }
+}
+
+/// The counts of every combination of the categories of two nominal fields.
+struct Crosstab {
+    rows: String,
+    columns: String,
+    row_levels: Vec<Option<String>>,
+    column_levels: Vec<Option<String>>,
+    counts: Vec<Vec<usize>>,
+}
+
+struct ResponseRate {
+    field: String,
+    answered: usize,
+    total: usize,
+}
+
+impl ResponseRate {
+    fn rate(&self) -> f64 {
+        self.answered as f64 / self.total as f64
+    }
+}
+
+struct Reliability {
+    group: String,
+    items: usize,
+    alpha: f64,
+    negative_items: Vec<(String, f64)>,
+    omega: Option<Result<f64, PythonError>>,
+}
+
+/// Runs a parsed command line: `analyze` writes its reports, the other commands print or write
+/// their own output.
+///
+/// # Errors
+///
+/// Returns the [`AnalysisError`] that stopped the run, which the command line tool prints before
+/// exiting with its [`AnalysisError::exit_code`].
+///
+/// # Panics
+///
+/// Panics when the options cannot be serialized for `--explain-config`.
+pub fn run(command: Command) -> Result<(), AnalysisError> {
+    let options = match command {
+        Command::Analyze(options) => options.resolve(),
+        command => return run_command(command),
+    };
+
+    if options.explain_config {
+        println!("{}", to_string_pretty(&options).unwrap());
+        return Ok(());
+    }
+
+    let Options {
+        source: source_file_name,
+        fields: field_file_name,
+        ..
+    } = &options;
+
+    let (fields, overrides) = match field_file_name {
+        Some(field_file_name) => load_fields(field_file_name)?,
+        None => (Vec::new(), Vec::new()),
+    };
+    let all_fields = fields.clone();
+    let fields = select_fields(fields, &options)?;
+
+    if options.dry_run {
+        set_env(TableStyle::Markdown);
+        let dataframe = load_dataframe(
+            source_file_name,
+            &options.source_format,
+            options.timeout,
+            &all_fields,
+        )?;
+        println!("{}", field_inventory(&dataframe, &fields));
+        return Ok(());
+    }
+
+    if !options.force && !options.tui {
+        refuse_overwrite(&options)?;
+    }
+
+    check_formats(&options)?;
+    set_env(options.table_style);
+    check_python_packages(&options)?;
+    analyze_and_write(&options, (&all_fields, &fields), &overrides)
+}
+
+/// Analyzes the source, and the `--compare` file alongside it, and writes every requested
+/// output. The first step that fails stops the run with its error.
+fn analyze_and_write(
+    options: &Options,
+    (all_fields, fields): (&[Field], &[Field]),
+    overrides: &[Override],
+) -> Result<(), AnalysisError> {
+    let Options {
+        source: source_file_name,
+        compare: compare_file_name,
+        ..
+    } = options;
+    let (orig_dataframe, loading_time) =
+        load_checked_source(source_file_name, all_fields, fields, options)?;
+    if let Some(file_name) = &options.emit_field_report {
+        write_field_report(file_name, &orig_dataframe, fields)?;
+    }
+    let column_names = orig_dataframe
+        .get_column_names()
+        .into_iter()
+        .map(str::to_owned)
+        .collect::<Vec<String>>();
+
+    let mut analysis = analyze(orig_dataframe, fields, overrides, options)?;
+    analysis
+        .timings
+        .insert(0, (String::from("讀取資料"), loading_time));
+    reject_nan(options, &analysis)?;
+    if options.tui {
+        return tui::browse(&json_report(source_file_name, &analysis))
+            .map_err(AnalysisError::Terminal);
+    }
+
+    let compare = match compare_file_name {
+        Some(compare_file_name) => {
+            let compare_dataframe = load_source(compare_file_name, all_fields, options)?;
+            if compare_dataframe.get_column_names() != column_names {
+                return Err(AnalysisError::Usage(format!(
+                    "{source_file_name} and {compare_file_name} do not share the same columns."
+                )));
+            }
+            let compare_analysis = analyze(compare_dataframe, fields, overrides, options)?;
+            reject_nan(options, &compare_analysis)?;
+            Some((compare_file_name, compare_analysis))
+        }
+        None => None,
+    };
+
+    if options.tidy {
+        write_tidy(
+            &format!("{}.tidy.csv", local_name(source_file_name)),
+            &analysis,
+        )?;
+        if let Some((compare_file_name, compare_analysis)) = &compare {
+            write_tidy(
+                &format!("{}.tidy.csv", local_name(compare_file_name)),
+                compare_analysis,
+            )?;
+        }
+    }
+
+    if let Some(file_name) = &options.network {
+        write_network(file_name, &analysis)?;
+    }
+    if let Some(file_name) = &options.heatmap {
+        write_heatmap(file_name, &analysis)?;
+    }
+
+    for format in options.report_formats() {
+        write_report(options, format, &analysis, compare.as_ref())?;
+    }
+
+    check_assertions(options, &analysis)
+}
+
+/// The subcommands other than `analyze`, which each print their result.
+fn run_command(command: Command) -> Result<(), AnalysisError> {
+    match command {
+        Command::Analyze(_) => unreachable!("analyze is handled by main"),
+        Command::Describe { source, format } => {
+            set_env(TableStyle::Markdown);
+            let dataframe = load_dataframe(&source, &format, FETCH_TIMEOUT, &[])?;
+            println!("{}", column_inventory(&dataframe));
+        }
+        Command::FieldsTemplate { source, format } => {
+            let dataframe = load_dataframe(&source, &format, FETCH_TIMEOUT, &[])?;
+            println!(
+                "{}",
+                to_string_pretty(&fields_template(&dataframe)).unwrap()
+            );
+        }
+        Command::Validate {
+            source,
+            fields,
+            format,
+        } => {
+            let fields = load_fields(&fields)?.0;
+            let dataframe = load_dataframe(&source, &format, FETCH_TIMEOUT, &fields)?;
+            validate(&dataframe, &fields)?;
+        }
+        Command::Diff { old, new } => {
+            print!("{}", diff_reports(&load_report(&old)?, &load_report(&new)?));
+        }
+        Command::Merge {
+            reports,
+            pair,
+            method,
+        } => {
+            let Some((var1, var2)) = pair.rsplit_once(':') else {
+                return Err(AnalysisError::Usage(format!(
+                    "--pair expects `VAR1:VAR2`, got `{pair}`."
+                )));
+            };
+            let reports = reports
+                .iter()
+                .map(|file_name| load_report(file_name))
+                .collect::<Result<Vec<JsonReport>, AnalysisError>>()?;
+            print!(
+                "{}",
+                merge_reports(&reports, (var1, var2), method.as_deref())
+            );
+        }
+        Command::Version => {
+            println!("{}", env!("CARGO_PKG_VERSION"));
+        }
+    }
+    Ok(())
+}
+
+fn check_formats(options: &Options) -> Result<(), AnalysisError> {
+    if options.format.contains(&OutputFormat::Html)
+        && matches!(options.table_style, TableStyle::Ascii | TableStyle::Csv)
+    {
+        return Err(AnalysisError::Usage(format!(
+            "--format html needs markdown or github tables, not --table-style {:?}.",
+            options.table_style
+        )));
+    }
+    if options.format.contains(&OutputFormat::Csv) && options.output == "-" {
+        return Err(AnalysisError::Usage(String::from(
+            "--format csv writes a file per matrix and cannot go to standard output.",
+        )));
+    }
+    Ok(())
+}
+
+/// Markdown sections are written as they are rendered, so that report is never held whole in
+/// memory; only the HTML page needs the whole Markdown report before it can be translated.
+fn write_report(
+    options: &Options,
+    format: OutputFormat,
+    analysis: &Analysis,
+    compare: Option<&(&String, Analysis)>,
+) -> Result<(), AnalysisError> {
+    if format == OutputFormat::Csv {
+        write_matrices(|matrix| options.matrix_path(None, matrix), analysis)?;
+        if let Some((compare_file_name, compare_analysis)) = compare {
+            write_matrices(
+                |matrix| options.matrix_path(Some(compare_file_name), matrix),
+                compare_analysis,
+            )?;
+        }
+        return Ok(());
+    }
+    let (mut writer, destination): (BufWriter<Box<dyn Write>>, _) = if options.output == "-" {
+        (
+            BufWriter::new(Box::new(stdout().lock())),
+            String::from("standard output"),
+        )
+    } else {
+        let file_name = options.report_path(format);
+        let file = File::create(&file_name).map_err(|error| AnalysisError::Io {
+            file_name: file_name.display().to_string(),
+            error,
+        })?;
+        (
+            BufWriter::new(Box::new(file)),
+            file_name.display().to_string(),
+        )
+    };
+    let written = match format {
+        OutputFormat::Md => write_markdown(options, analysis, compare, &mut writer),
+        OutputFormat::Html => {
+            let mut report = Vec::new();
+            write_markdown(options, analysis, compare, &mut report).and_then(|()| {
+                let markdown = String::from_utf8_lossy(&report);
+                writer.write_all(html_report(&options.source, &markdown).as_bytes())
+            })
+        }
+        OutputFormat::Json => {
+            if let Some((compare_file_name, compare_analysis)) = compare {
+                write_compare_json(options, compare_file_name, compare_analysis)?;
+            }
+            to_writer_pretty(&mut writer, &json_report(&options.source, analysis))
+                .map_err(io::Error::from)
+        }
+        OutputFormat::Csv => unreachable!("the matrices are written above"),
+    };
+    written
+        .and_then(|()| writer.flush())
+        .map_err(|error| AnalysisError::Io {
+            file_name: destination,
+            error,
+        })
+}
+
+/// The JSON report of the `--compare` file goes next to that file, keeping the shape `diff` and
+/// `merge` read, rather than into the report of the source.
+fn write_compare_json(
+    options: &Options,
+    compare_file_name: &str,
+    compare_analysis: &Analysis,
+) -> Result<(), AnalysisError> {
+    let file_name = options.compare_report_path(compare_file_name, OutputFormat::Json);
+    let written = File::create(&file_name).and_then(|file| {
+        let mut writer = BufWriter::new(file);
+        to_writer_pretty(
+            &mut writer,
+            &json_report(compare_file_name, compare_analysis),
+        )?;
+        writer.flush()
+    });
+    written.map_err(|error| AnalysisError::Io {
+        file_name: file_name.display().to_string(),
+        error,
+    })
+}
+
+/// Renders the Markdown report into `out`, followed by the `--timings` section, which includes
+/// the time spent rendering.
+fn write_markdown(
+    options: &Options,
+    analysis: &Analysis,
+    compare: Option<&(&String, Analysis)>,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let started = Instant::now();
+    match compare {
+        Some((compare_file_name, compare_analysis)) => render_comparison(
+            (&options.source, analysis),
+            (compare_file_name, compare_analysis),
+            out,
+        ),
+        None => render_analysis(analysis, out),
+    }?;
+    if options.timings {
+        out.write_all(
+            timing_section(&analysis.timings, started.elapsed(), analysis.table_style).as_bytes(),
+        )?;
+    }
+    Ok(())
+}
+
+fn check_assertions(options: &Options, analysis: &Analysis) -> Result<(), AnalysisError> {
+    let pairs = analysis.correlation_pairs();
+    let mut failures = Vec::new();
+    for assertion in &options.assertions {
+        let (var1, var2) = (assertion.var1.as_str(), assertion.var2.as_str());
+        let Some((_, _, coefficient, value)) = pairs.iter().find(|(name, other_name, _, _)| {
+            (*name == var1 && *other_name == var2) || (*name == var2 && *other_name == var1)
+        }) else {
+            failures.push(format!(
+                "Assertion failed: no correlation between {var1} and {var2} was computed."
+            ));
+            continue;
+        };
+        let significant = value.is_significant();
+        if significant != (assertion.expectation == Expectation::Significant) {
+            failures.push(format!(
+                "Assertion failed: the {coefficient} correlation between {var1} and {var2} is {}, expected {}.",
+                if significant { "significant" } else { "not significant" },
+                assertion.expectation.name()
+            ));
+        }
+    }
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(AnalysisError::AssertionFailed(failures))
+    }
+}
+
+/// With `--no-nan`, a NaN r or p-value stops the run before any report is written.
+fn reject_nan(options: &Options, analysis: &Analysis) -> Result<(), AnalysisError> {
+    if !options.no_nan {
+        return Ok(());
+    }
+    let offending = analysis
+        .correlation_pairs()
+        .into_iter()
+        .filter(|(_, _, _, value)| {
+            matches!(value, CorrelationValue::Valid(result) if result.r.is_nan() || result.p_value.is_nan())
+        })
+        .map(|(name, other_name, coefficient, _)| {
+            format!("The {coefficient} correlation between {name} and {other_name} is NaN.")
+        })
+        .collect::<Vec<String>>();
+    if offending.is_empty() {
+        Ok(())
+    } else {
+        Err(AnalysisError::NotANumber(offending))
+    }
+}
+
+fn refuse_overwrite(options: &Options) -> Result<(), AnalysisError> {
+    let mut file_names = Vec::new();
+    if options.output != "-" {
+        file_names.extend(
+            options
+                .report_formats()
+                .into_iter()
+                .filter(|&format| format != OutputFormat::Csv)
+                .map(|format| options.report_path(format)),
+        );
+    }
+    if let Some(compare) = &options.compare {
+        if options.output != "-" || options.format.contains(&OutputFormat::Json) {
+            file_names.push(options.compare_report_path(compare, OutputFormat::Json));
+        }
+    }
+    if options.format.contains(&OutputFormat::Csv) {
+        for compare in once(None).chain(options.compare.as_deref().map(Some)) {
+            file_names.extend(
+                CSV_MATRICES
+                    .iter()
+                    .map(|matrix| options.matrix_path(compare, matrix)),
+            );
+        }
+    }
+    if options.tidy {
+        file_names.extend(
+            once(&options.source)
+                .chain(&options.compare)
+                .map(|source| PathBuf::from(format!("{}.tidy.csv", local_name(source)))),
+        );
+    }
+    file_names.extend(
+        options
+            .network
+            .iter()
+            .chain(&options.heatmap)
+            .map(PathBuf::from),
+    );
+    let existing = file_names
+        .iter()
+        .filter(|file_name| file_name.exists())
+        .map(|file_name| format!("{} already exists.", file_name.display()))
+        .collect::<Vec<String>>();
+    if existing.is_empty() {
+        Ok(())
+    } else {
+        Err(AnalysisError::Usage(format!(
+            "{}\nPass --force to overwrite.",
+            existing.join("\n")
+        )))
+    }
+}
+
+/// Loads the source and checks that it has every described field and the `--id-column`.
+fn load_checked_source(
+    source_file_name: &str,
+    all_fields: &[Field],
+    fields: &[Field],
+    options: &Options,
+) -> Result<(DataFrame, Duration), AnalysisError> {
+    info!("讀取 {source_file_name}");
+    let started = Instant::now();
+    let orig_dataframe = load_source(source_file_name, all_fields, options)?;
+    let loading_time = started.elapsed();
+    info!(
+        "讀取完成：{} 列、{} 欄（{:.2?}）",
+        orig_dataframe.height(),
+        orig_dataframe.width(),
+        loading_time
+    );
+    require_columns(
+        &orig_dataframe,
+        fields.iter().map(|field| field.name.as_str()),
+    )?;
+    info!("{} 個欄位皆存在於資料中", fields.len());
+    if let Some(id_column) = &options.id_column {
+        if orig_dataframe.column(id_column).is_err() {
+            return Err(AnalysisError::MissingColumn(id_column.clone()));
+        }
+    }
+    Ok((orig_dataframe, loading_time))
+}
+
+fn check_python_packages(options: &Options) -> Result<(), AnalysisError> {
+    if options.offline {
+        return Ok(());
+    }
+    let correlations = [Section::Pearson, Section::Spearman, Section::Kendall]
+        .into_iter()
+        .filter(|&section| options.includes(section))
+        .filter(|&section| {
+            section != Section::Kendall
+                || options.weights.is_none()
+                || options.nominal_test == NominalTest::Chisq
+        })
+        .filter(|&section| section != Section::Pearson || !is_native(Coefficient::Pearson, options))
+        .filter(|&section| section != Section::Pearson || options.weights.is_none())
+        .filter(|&section| {
+            section != Section::Spearman || !is_native(Coefficient::Spearman, options)
+        })
+        .map(|section| format!("{section:?}").to_lowercase())
+        .chain((!options.rows.is_empty()).then(|| String::from("cross-correlation")))
+        .chain(
+            options
+                .paired_ttest
+                .is_some()
+                .then(|| String::from("paired-ttest")),
+        )
+        .chain(
+            options
+                .includes(Section::Normality)
+                .then(|| String::from("normality")),
+        )
+        .collect::<Vec<String>>();
+    let factor = options
+        .includes(Section::Factor)
+        .then(|| String::from("factor"))
+        .into_iter()
+        .collect::<Vec<String>>();
+    let mut missing = Vec::new();
+    for (module, package, sections) in [
+        ("scipy.stats", "scipy", &correlations),
+        ("factor_analyzer", "factor-analyzer", &factor),
+        ("polars", "polars", &factor),
+        ("pyarrow", "pyarrow", &factor),
+    ] {
+        if sections.is_empty() || importable(module) {
+            continue;
+        }
+        missing.push(format!(
+            "Python module {module} is needed by {} but is not importable; install it with `pip install {package}` or skip with --no-<section>.",
+            sections.join(", ")
+        ));
+    }
+    if !missing.is_empty() {
+        return Err(AnalysisError::Python(missing.join("\n")));
+    }
+    if options.includes(Section::Reliability) && !importable("factor_analyzer") {
+        eprintln!("Python module factor_analyzer is not importable, McDonald's ω will be unavailable; install it with `pip install factor-analyzer`.");
+    }
+    Ok(())
+}
+
+/// The parse error prefixed with `file:line:column`, followed by the offending line and a caret
+/// under the column when the parser reported one.
+fn parse_error_message(
+    file_name: &str,
+    text: &str,
+    error: &str,
+    location: Option<(usize, usize)>,
+) -> String {
+    let Some((line, column)) = location else {
+        return format!("Unable to parse fields from {file_name}: {error}");
+    };
+    let mut message = format!("Unable to parse fields from {file_name}:{line}:{column}: {error}");
+    if let Some(snippet) = text.lines().nth(line.saturating_sub(1)) {
+        let gutter = line.to_string();
+        let offset = snippet
+            .chars()
+            .take(column.saturating_sub(1))
+            .map(|character| if character == '\t' { '\t' } else { ' ' })
+            .collect::<String>();
+        write!(
+            message,
+            "\n{gutter} | {snippet}\n{} | {offset}^",
+            " ".repeat(gutter.len())
+        )
+        .unwrap();
+    }
+    message
+}
+
+fn load_fields(field_file_name: &str) -> Result<(Vec<Field>, Vec<Override>), AnalysisError> {
+    let text = std::fs::read_to_string(field_file_name).map_err(|error| AnalysisError::Read {
+        source: field_file_name.to_owned(),
+        message: error.to_string(),
+    })?;
+    let is_yaml = Path::new(field_file_name)
+        .extension()
+        .is_some_and(|extension| extension == "yaml" || extension == "yml");
+    let parse = if is_yaml {
+        serde_yaml::from_str(&text).map_err(|error| {
+            let location = error
+                .location()
+                .map(|location| (location.line(), location.column()));
+            (error.to_string(), location)
+        })
+    } else {
+        from_str(&text).map_err(|error| {
+            let location = (error.line() > 0).then(|| (error.line(), error.column()));
+            let message = error.to_string();
+            let suffix = format!(" at line {} column {}", error.line(), error.column());
+            let message = message
+                .strip_suffix(&suffix)
+                .unwrap_or(&message)
+                .to_string();
+            (message, location)
+        })
+    };
+
+    let field_file: FieldFile = parse.map_err(|(error, location)| {
+        AnalysisError::Usage(parse_error_message(
+            field_file_name,
+            &text,
+            &error,
+            location,
+        ))
+    })?;
+    let (fields, overrides) = match field_file {
+        FieldFile::Fields(fields) => (fields, Vec::new()),
+        FieldFile::Config { fields, overrides } => (fields, overrides),
+    };
+    let duplicates = duplicate_names(fields.iter().map(|field| field.name.as_str()));
+    if !duplicates.is_empty() {
+        return Err(AnalysisError::Usage(format!(
+            "Fields described more than once in {field_file_name}: {}",
+            duplicates.join(", ")
+        )));
+    }
+    for Override { fields: (a, b), .. } in &overrides {
+        if let Some(name) = [a, b]
+            .into_iter()
+            .find(|name| !fields.iter().any(|field| &field.name == *name))
+        {
+            return Err(AnalysisError::Usage(format!(
+                "The override for {a} × {b} names {name}, which is not a described field."
+            )));
+        }
+    }
+    for field in fields.iter().filter(|field| !field.sum_of.is_empty()) {
+        if let Some(name) = field.sum_of.iter().find(|name| {
+            !fields
+                .iter()
+                .any(|item| &item.name == *name && item.sum_of.is_empty())
+        }) {
+            return Err(AnalysisError::Usage(format!(
+                "{} sums {name}, which is not a described field read from the CSV file.",
+                field.name
+            )));
+        }
+    }
+    Ok((fields, overrides))
+}
+
+/// The data to analyze: loaded, checked to have enough rows, and extended with derived fields.
+fn load_source(
+    source_file_name: &str,
+    fields: &[Field],
+    options: &Options,
+) -> Result<DataFrame, AnalysisError> {
+    let dataframe = load_dataframe(
+        source_file_name,
+        &options.source_format,
+        options.timeout,
+        fields,
+    )?;
+    require_columns(
+        &dataframe,
+        fields
+            .iter()
+            .flat_map(|field| &field.sum_of)
+            .map(String::as_str),
+    )?;
+    derive_columns(
+        require_rows(dataframe, source_file_name)?,
+        fields,
+        source_file_name,
+    )
+}
+
+/// Appends a column for every field with `sum_of`. A row's sum is missing when any of its items
+/// is, and each item is cleaned (including reverse-scoring) before it is summed.
+fn derive_columns(
+    mut dataframe: DataFrame,
+    fields: &[Field],
+    source_file_name: &str,
+) -> Result<DataFrame, AnalysisError> {
+    for field in fields.iter().filter(|field| !field.sum_of.is_empty()) {
+        if dataframe.column(&field.name).is_ok() {
+            return Err(AnalysisError::Usage(format!(
+                "{} is derived in the field file but is already a column of {source_file_name}.",
+                field.name
+            )));
+        }
+        let items = field
+            .sum_of
+            .iter()
+            .map(|name| {
+                let item = fields.iter().find(|item| &item.name == name).unwrap();
+                let data = dataframe
+                    .column(name)
+                    .ok()
+                    .and_then(|column| numeric_column(column, item).ok());
+                data.map(|(data, _)| data)
+                    .ok_or_else(|| AnalysisError::Cast {
+                        column: name.clone(),
+                        message: format!("is summed into {} but is not numeric", field.name),
+                    })
+            })
+            .collect::<Result<Vec<Column>, AnalysisError>>()?;
+        let sum = (0..dataframe.height())
+            .map(|row| items.iter().map(|item| item[row]).sum::<Option<f64>>())
+            .collect::<Column>();
+        dataframe
+            .with_column(Series::new(&field.name, sum))
+            .unwrap();
+    }
+    Ok(dataframe)
+}
+
+/// The `--id-column` and the `--weights` column are never analyzed, even when the field file
+/// describes them.
+fn select_fields(fields: Vec<Field>, options: &Options) -> Result<Vec<Field>, AnalysisError> {
+    let fields = fields
+        .into_iter()
+        .filter(|field| options.id_column.as_ref() != Some(&field.name))
+        .filter(|field| options.weights.as_ref() != Some(&field.name))
+        .collect::<Vec<Field>>();
+    let Some(pattern) = &options.only_regex else {
+        return Ok(fields);
+    };
+    let regex = Regex::new(pattern).map_err(|error| {
+        AnalysisError::Usage(format!("Invalid regular expression `{pattern}`: {error}"))
+    })?;
+    let fields = fields
+        .into_iter()
+        .filter(|field| regex.is_match(&field.name))
+        .collect::<Vec<Field>>();
+    if fields.is_empty() && !options.describe_only {
+        return Err(AnalysisError::Usage(format!(
+            "No described field matches `{pattern}`."
+        )));
+    }
+    Ok(fields)
+}
+
+fn column_inventory(dataframe: &DataFrame) -> DataFrame {
+    let columns = dataframe.get_columns();
+    DataFrame::new(vec![
+        Series::new(
+            "欄位",
+            columns
+                .iter()
+                .map(|series| series.name().to_owned())
+                .collect::<Vec<String>>(),
+        ),
+        Series::new(
+            "型別",
+            columns
+                .iter()
+                .map(|series| series.dtype().to_string())
+                .collect::<Vec<String>>(),
+        ),
+        Series::new(
+            "非空值數",
+            columns
+                .iter()
+                .map(|series| (series.len() - series.null_count()) as u64)
+                .collect::<Vec<u64>>(),
+        ),
+    ])
+    .unwrap()
+}
+
+/// The `--dry-run` table: each field's scale and, when the source has the column, its type and
+/// non-null count. Fields summed from other items are marked as derived.
+fn field_inventory(dataframe: &DataFrame, fields: &[Field]) -> DataFrame {
+    let columns = fields
+        .iter()
+        .map(|field| dataframe.column(&field.name).ok())
+        .collect::<Vec<Option<&Series>>>();
+    DataFrame::new(vec![
+        Series::new(
+            "欄位",
+            fields
+                .iter()
+                .map(|field| field.name.as_str())
+                .collect::<Vec<&str>>(),
+        ),
+        Series::new(
+            "尺度",
+            fields
+                .iter()
+                .map(|field| format!("{:?}", field.scale))
+                .collect::<Vec<String>>(),
+        ),
+        Series::new(
+            "存在",
+            fields
+                .iter()
+                .zip(&columns)
+                .map(|(field, column)| match column {
+                    Some(_) => String::from("是"),
+                    None if !field.sum_of.is_empty() => {
+                        format!("衍生（{} 題加總）", field.sum_of.len())
+                    }
+                    None => String::from("否"),
+                })
+                .collect::<Vec<String>>(),
+        ),
+        Series::new(
+            "型別",
+            columns
+                .iter()
+                .map(|column| {
+                    column.map_or_else(|| String::from("-"), |series| series.dtype().to_string())
+                })
+                .collect::<Vec<String>>(),
+        ),
+        Series::new(
+            "非空值數",
+            columns
+                .iter()
+                .map(|column| {
+                    column.map_or_else(
+                        || String::from("-"),
+                        |series| (series.len() - series.null_count()).to_string(),
+                    )
+                })
+                .collect::<Vec<String>>(),
+        ),
+    ])
+    .unwrap()
+}
+
+/// Integer columns with at most this many distinct values are guessed to be Likert-style items.
+const MAX_ORDINAL_LEVELS: usize = 7;
+
+fn fields_template(dataframe: &DataFrame) -> Vec<Value> {
+    dataframe
+        .get_columns()
+        .iter()
+        .map(|series| json!({ "name": series.name(), "scale": guess_scale(series) }))
+        .collect()
+}
+
+/// Text columns and columns with at most two distinct values are guessed to be nominal.
+fn guess_scale(series: &Series) -> Scale {
+    let distinct = series.n_unique().unwrap_or(usize::MAX);
+    let integral = series.cast(&DataType::Float64).is_ok_and(|values| {
+        values
+            .f64()
+            .unwrap()
+            .into_iter()
+            .flatten()
+            .all(|value| value.fract() == 0.0)
+    });
+    if series.dtype() == &DataType::Utf8 || distinct <= 2 {
+        Scale::Nominal
+    } else if integral && distinct <= MAX_ORDINAL_LEVELS {
+        Scale::Ordinal
+    } else {
+        Scale::Interval
+    }
+}
+
+/// A declared scale looks plausible unless it is stronger than the one [`guess_scale`] guesses,
+/// with interval and ratio counted as equally strong.
+fn field_report(dataframe: &DataFrame, fields: &[Field]) -> Vec<Value> {
+    let strength = |scale| match scale {
+        Scale::Nominal => 0,
+        Scale::Ordinal => 1,
+        Scale::Interval | Scale::Ratio => 2,
+    };
+    fields
+        .iter()
+        .map(|field| {
+            let Ok(series) = dataframe.column(&field.name) else {
+                return json!({ "name": field.name, "scale": field.scale, "found": false });
+            };
+            let guessed = guess_scale(series);
+            json!({
+                "name": field.name,
+                "scale": field.scale,
+                "found": true,
+                "dtype": series.dtype().to_string(),
+                "distinct": series.n_unique().ok(),
+                "guessed_scale": guessed,
+                "plausible": strength(field.scale) <= strength(guessed),
+            })
+        })
+        .collect()
+}
+
+fn write_field_report(
+    file_name: &str,
+    dataframe: &DataFrame,
+    fields: &[Field],
+) -> Result<(), AnalysisError> {
+    let file = File::create(file_name).map_err(|error| AnalysisError::io(file_name, error))?;
+    to_writer_pretty(BufWriter::new(file), &field_report(dataframe, fields))
+        .map_err(|error| AnalysisError::io(file_name, error))
+}
+
+fn validate(dataframe: &DataFrame, fields: &[Field]) -> Result<(), AnalysisError> {
+    require_columns(
+        dataframe,
+        fields
+            .iter()
+            .filter(|field| field.sum_of.is_empty())
+            .map(|field| field.name.as_str()),
+    )?;
+    println!("{} fields validated.", fields.len());
+    Ok(())
+}
+
+/// Fails listing every name missing from the columns, each with the most similar column name as
+/// a suggestion, so that all the typos can be fixed at once.
+fn require_columns<'a>(
+    dataframe: &DataFrame,
+    names: impl IntoIterator<Item = &'a str>,
+) -> Result<(), AnalysisError> {
+    let column_names = dataframe.get_column_names();
+    let missing_fields = names
+        .into_iter()
+        .filter(|name| !column_names.contains(name))
+        .map(|name| {
+            closest_name(name, &column_names).map_or_else(
+                || name.to_owned(),
+                |suggestion| format!("{name} (did you mean `{suggestion}`?)"),
+            )
+        })
+        .collect::<Vec<String>>();
+    if missing_fields.is_empty() {
+        Ok(())
+    } else {
+        Err(AnalysisError::MissingFields(missing_fields))
+    }
+}
+
+/// The candidate nearest to `name` in Levenshtein distance, unless even that one differs in more
+/// than a third of `name`'s characters.
+fn closest_name<'a>(name: &str, candidates: &[&'a str]) -> Option<&'a str> {
+    let limit = (name.chars().count() / 3).max(1);
+    candidates
+        .iter()
+        .map(|candidate| (levenshtein(name, candidate), *candidate))
+        .filter(|&(distance, _)| distance <= limit)
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, candidate)| candidate)
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<char>>();
+    let mut previous = (0..=b.len()).collect::<Vec<usize>>();
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, &b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != b);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Reads a CSV source, or a sheet of an .xlsx one, from a path or an http(s) URL. The columns
+/// whose field declares a `dtype` are read with that type rather than the inferred one.
+fn load_dataframe(
+    source_file_name: &str,
+    format: &SourceFormat,
+    timeout: u64,
+    fields: &[Field],
+) -> Result<DataFrame, AnalysisError> {
+    let dtypes = fields
+        .iter()
+        .filter(|field| field.sum_of.is_empty())
+        .filter_map(|field| {
+            field
+                .dtype
+                .map(|dtype| polars::prelude::Field::new(&field.name, dtype.data_type()))
+        })
+        .collect::<Schema>();
+    let dtypes = (!dtypes.is_empty()).then(|| Arc::new(dtypes));
+    let is_xlsx = Path::new(local_name(source_file_name))
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("xlsx"));
+    if format.sheet.is_some() && !is_xlsx {
+        return Err(AnalysisError::Usage(format!(
+            "--sheet only applies to .xlsx sources, not {source_file_name}."
+        )));
+    }
+    if is_xlsx && (format.delimiter != b',' || format.encoding.is_some()) {
+        return Err(AnalysisError::Usage(format!(
+            "--delimiter and --encoding only apply to CSV sources, not {source_file_name}."
+        )));
+    }
+    let read = |reader: CsvReader<Cursor<Vec<u8>>>, delimiter| {
+        reader
+            .infer_schema(None)
+            .with_dtypes(dtypes.clone())
+            .with_delimiter(delimiter)
+            .has_header(true)
+            .finish()
+    };
+    let parsed = if is_xlsx {
+        let workbook = read_source(source_file_name, timeout)?;
+        let csv = xlsx_to_csv(&workbook, format.sheet.as_deref()).map_err(|error| {
+            AnalysisError::Read {
+                source: source_file_name.to_owned(),
+                message: format!("not a readable Excel file: {error}"),
+            }
+        })?;
+        read(CsvReader::new(Cursor::new(csv)), b',')
+    } else if let Some(encoding) = &format.encoding {
+        let bytes = read_source(source_file_name, timeout)?;
+        let text = decode(&bytes, encoding).map_err(|error| AnalysisError::Read {
+            source: source_file_name.to_owned(),
+            message: format!("cannot be decoded as {encoding}: {error}"),
+        })?;
+        read(
+            CsvReader::new(Cursor::new(text.into_bytes())),
+            format.delimiter,
+        )
+    } else if is_url(source_file_name) {
+        let bytes = fetch(source_file_name, timeout)?;
+        read(CsvReader::new(Cursor::new(bytes)), format.delimiter)
+    } else {
+        CsvReader::from_path(source_file_name).and_then(|csv| {
+            csv.infer_schema(None)
+                .with_dtypes(dtypes.clone())
+                .with_delimiter(format.delimiter)
+                .has_header(true)
+                .finish()
+        })
+    };
+    let mut dataframe = parsed.map_err(|error| AnalysisError::Csv {
+        source: source_file_name.to_owned(),
+        error,
+    })?;
+    strip_byte_order_mark(&mut dataframe);
+    let duplicates = duplicated_headers(&dataframe.get_column_names());
+    if !duplicates.is_empty() {
+        return Err(AnalysisError::Usage(format!(
+            "Columns appearing more than once in the header of {source_file_name}: {}",
+            duplicates.join(", ")
+        )));
+    }
+    Ok(dataframe)
+}
+
+/// Every name that occurs more than once, in the order of first occurrence.
+fn duplicate_names<'a>(names: impl IntoIterator<Item = &'a str>) -> Vec<&'a str> {
+    let mut counts = Vec::<(&str, usize)>::new();
+    for name in names {
+        match counts.iter_mut().find(|(seen, _)| *seen == name) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((name, 1)),
+        }
+    }
+    counts
+        .into_iter()
+        .filter(|&(_, count)| count > 1)
+        .map(|(name, _)| name)
+        .collect()
+}
+
+/// Polars renames a repeated header to `<name>_duplicated_<n>`, which no field would match; this
+/// recovers the original names from those columns.
+fn duplicated_headers<'a>(column_names: &[&'a str]) -> Vec<&'a str> {
+    let mut duplicates = Vec::new();
+    for &name in column_names {
+        let Some((original, suffix)) = name.rsplit_once("_duplicated_") else {
+            continue;
+        };
+        if suffix.parse::<usize>().is_ok()
+            && column_names.contains(&original)
+            && !duplicates.contains(&original)
+        {
+            duplicates.push(original);
+        }
+    }
+    duplicates
+}
+
+/// The whole file or response body, for sources that have to be converted before `CsvReader`.
+fn read_source(source_file_name: &str, timeout: u64) -> Result<Vec<u8>, AnalysisError> {
+    if is_url(source_file_name) {
+        return fetch(source_file_name, timeout);
+    }
+    std::fs::read(source_file_name).map_err(|error| AnalysisError::Read {
+        source: source_file_name.to_owned(),
+        message: error.to_string(),
+    })
+}
+
+/// The whole response body, read into memory for `CsvReader`.
+fn fetch(url: &str, timeout: u64) -> Result<Vec<u8>, AnalysisError> {
+    let failed = |message: String| AnalysisError::Read {
+        source: url.to_owned(),
+        message,
+    };
+    let response = match ureq::get(url).timeout(Duration::from_secs(timeout)).call() {
+        Ok(response) if response.status() == 200 => response,
+        Ok(response) | Err(ureq::Error::Status(_, response)) => {
+            return Err(failed(format!(
+                "the server responded with HTTP {} {}",
+                response.status(),
+                response.status_text()
+            )));
+        }
+        Err(error) => return Err(failed(error.to_string())),
+    };
+    let mut body = Vec::new();
+    response
+        .into_reader()
+        .read_to_end(&mut body)
+        .map_err(|error| failed(error.to_string()))?;
+    Ok(body)
+}
+
+fn require_rows(dataframe: DataFrame, source_file_name: &str) -> Result<DataFrame, AnalysisError> {
+    if dataframe.height() < 2 {
+        return Err(AnalysisError::InsufficientData(format!(
+            "{source_file_name} 只有 {} 筆資料，至少需要 2 筆才能計算統計量。",
+            dataframe.height()
+        )));
+    }
+    Ok(dataframe)
+}
+
+/// Excel exports UTF-8 with a byte-order mark, which would otherwise end up in the first header.
+fn strip_byte_order_mark(dataframe: &mut DataFrame) {
+    let Some(first) = dataframe
+        .get_column_names()
+        .first()
+        .map(|name| (*name).to_owned())
+    else {
+        return;
+    };
+    if let Some(stripped) = first.strip_prefix('\u{feff}') {
+        dataframe.rename(&first, stripped).unwrap();
+    }
+}
+
+/// Takes the data frame by value so it can be dropped once the columns are projected into the
+/// compact `processed_data`, before the correlation loops run.
+fn analyze(
+    orig_dataframe: DataFrame,
+    fields: &[Field],
+    overrides: &[Override],
+    options: &Options,
+) -> Result<Analysis, AnalysisError> {
+    let mut timings = Vec::new();
+    let (orig_dataframe, selection) = select_rows(orig_dataframe, options)?;
+    let (description, weighted_description) = timed(&mut timings, "敘述統計", || {
+        descriptions(&orig_dataframe, fields, options)
+    })?;
+    let (frequencies, crosstabs) = frequency_tables(&orig_dataframe, fields, options)?;
+    let (fields, columns, mut warnings) = numeric_columns(&orig_dataframe, fields, options)?;
+    let mcar = mcar(&columns, options);
+    let (processed_data, observed) = processed_columns(&columns, options);
+    let moments = moments(&fields, &columns, options);
+    let tie_proportions = tie_proportions(&fields, &columns, options);
+    let icc = intraclass_correlation(&fields, &columns, options)?;
+    let paired_t_test = paired_t_test(&fields, &columns, options)?;
+    let regression = regression(&fields, &columns, options)?;
+    let group_comparison = group_comparison(&orig_dataframe, &fields, &columns, options)?;
+    let normality = timed(&mut timings, "常態性檢定", || {
+        normality(&fields, &columns, options)
+    });
+    let response_rates = response_rates(&fields, &columns, options);
+    let covariance = covariance_matrix(&columns, &fields, options);
+    drop(columns);
+    write_audit_files(&processed_data, &observed, &fields, options)?;
+    let fields = fields.as_slice();
+    let data = (processed_data.as_slice(), observed.as_slice());
+    let weights = weights_and_rolling(orig_dataframe, data, fields, overrides, options)?;
+    let weights = weights.as_deref();
+    let ([pearson, spearman, kendall], [cross, point_biserial]) = correlations(
+        &processed_data,
+        &observed,
+        fields,
+        overrides,
+        options,
+        weights,
+        &mut timings,
+    )?;
+    let reliability = reliability(data, fields, options);
+
+    warnings.extend(data_warnings(data, fields, options));
+    let (factor, factor_warnings) = timed(&mut timings, "因子分析", || {
+        factor(data, fields, options)
+    });
+
+    let mut analysis = Analysis {
+        duplicates: selection.duplicates,
+        filter: selection.filter,
+        sample: selection.sample,
+        standardized: options.standardize,
+        detrend: options
+            .detrend
+            .clone()
+            .map(|column| (column, options.detrend_method)),
+        offline: options.offline,
+        scipy_version: (!options.offline).then(scipy_version).flatten(),
+        warnings,
+        weights: options.weights.clone(),
+        ordinal: ordinal_coefficient(options),
+        nominal: nominal_coefficient(options),
+        loading_cutoff: options.loading_cutoff,
+        group_loadings: options.group_loadings,
+        page_size: options.page_size.map(NonZeroUsize::get),
+        spearman_brown: options.spearman_brown,
+        correction: options.correction,
+        style: options.style,
+        table_style: options.table_style,
+        number_locale: options.number_locale,
+        precision: options.precision,
+        explain: options.explain,
+        caption: options.caption.clone().unwrap_or_default(),
+        legend: options.legend.clone().unwrap_or_default(),
+        moments,
+        tie_proportions,
+        description,
+        frequencies,
+        crosstabs,
+        weighted_description,
+        pearson,
+        spearman,
+        kendall,
+        covariance,
+        cross,
+        point_biserial,
+        reliability,
+        condition_number: condition_number(data, fields, options),
+        response_rates,
+        min_response_rate: options.min_response_rate,
+        mcar,
+        normality,
+        alpha: options.alpha,
+        icc,
+        paired_t_test,
+        regression,
+        group_comparison,
+        factor,
+        factor_warnings,
+        timings,
+    };
+    analysis.warnings.extend(result_warnings(&analysis));
+    Ok(analysis)
+}
+
+/// Little's MCAR test of the numeric columns, unless `--no-mcar` is given.
+fn mcar(columns: &[Column], options: &Options) -> Option<Result<LittleMcar, &'static str>> {
+    options
+        .includes(Section::Mcar)
+        .then(|| little_mcar(columns))
+}
+
+/// Detrends and standardizes the numeric columns, keeping which values were observed so the
+/// correlations can delete missing values pairwise.
+fn processed_columns(columns: &[Column], options: &Options) -> (Vec<Vec<f64>>, Vec<Vec<bool>>) {
+    let detrended = detrended(columns, options);
+    let observed = detrended
+        .iter()
+        .map(|column| column.iter().map(Option::is_some).collect())
+        .collect();
+    let processed_data = detrended
+        .iter()
+        .map(|column| processed_column(column, options))
+        .collect();
+    (processed_data, observed)
+}
+
+/// Computes the correlation matrices of `fields` the way `analyze` does, weighted by
+/// `--weights` when it is set. Fields that cannot be cast to numbers are left out.
+///
+/// # Errors
+///
+/// Returns [`AnalysisError::MissingColumn`] when a field or the `--weights` column is not in
+/// `dataframe`, [`AnalysisError::Cast`] when the weights are not all non-negative numbers,
+/// [`AnalysisError::Usage`] when `--rows` or `--cols` names a field that is not analyzed, and
+/// [`AnalysisError::Python`] when scipy is needed but cannot be imported.
+pub fn run_correlations(
+    dataframe: &DataFrame,
+    fields: &[Field],
+    options: &Options,
+) -> Result<CorrelationMatrices, AnalysisError> {
+    let (fields, columns, _) = numeric_columns(dataframe, fields, options)?;
+    let (processed_data, observed) = processed_columns(&columns, options);
+    let weights = options
+        .weights
+        .as_ref()
+        .map(|weight_field| weight_column(dataframe, weight_field))
+        .transpose()?;
+    let ([pearson, spearman, kendall], _) = correlations(
+        &processed_data,
+        &observed,
+        &fields,
+        &[],
+        options,
+        weights.as_deref(),
+        &mut Vec::new(),
+    )?;
+    Ok(CorrelationMatrices {
+        pearson,
+        spearman,
+        kendall,
+    })
+}
+
+/// Runs the factor analysis of the fields flagged with `factor`, whether or not the factor
+/// section is selected.
+///
+/// # Errors
+///
+/// Returns [`AnalysisError::MissingColumn`] when a field is not in `dataframe`, and
+/// [`AnalysisError::Python`] with the error of `factor_analyzer`, or of the memory and timeout
+/// limits in `options`.
+pub fn run_factor_analysis(
+    dataframe: &DataFrame,
+    fields: &[Field],
+    options: &Options,
+) -> Result<FactorSolution, AnalysisError> {
+    let (fields, columns, _) = numeric_columns(dataframe, fields, options)?;
+    let (processed_data, observed) = processed_columns(&columns, options);
+    Ok(fit_factor_analysis(
+        (&processed_data, &observed),
+        &fields,
+        options,
+    )?)
+}
+
+/// Warnings about the computed results rather than the input data.
+fn result_warnings(analysis: &Analysis) -> Vec<String> {
+    let mut warnings = failure_warnings(analysis);
+    warnings.extend(perfect_correlation_warnings(analysis));
+    warnings.extend(reverse_coding_warnings(&analysis.reliability));
+    for reliability in &analysis.reliability {
+        if reliability.alpha < ACCEPTABLE_ALPHA {
+            warnings.push(format!(
+                "構面 {} 的 Cronbach's α 為 {:.3}，低於一般可接受的 {ACCEPTABLE_ALPHA}，內部一致性可能不足",
+                reliability.group, reliability.alpha
+            ));
+        }
+    }
+    for response_rate in analysis.response_rates.iter().flatten() {
+        if response_rate.rate() < analysis.min_response_rate {
+            warnings.push(format!(
+                "{} 的回收率為 {:.1}%，低於 {}%，結果可能不可靠",
+                response_rate.field,
+                response_rate.rate() * 100.0,
+                analysis.min_response_rate * 100.0
+            ));
+        }
+    }
+    if let Some(Ok(FactorSolution {
+        adequacy: Some(adequacy),
+        ..
+    })) = &analysis.factor
+    {
+        if adequacy.kmo < KMO_LIMIT {
+            warnings.push(format!(
+                "KMO 為 {:.3}，低於 {KMO_LIMIT}，資料可能不適合進行因子分析，結果可能不可靠",
+                adequacy.kmo
+            ));
+        }
+    }
+    if let Some(condition_number) = analysis
+        .condition_number
+        .filter(|condition_number| *condition_number > CONDITION_NUMBER_LIMIT)
+    {
+        warnings.push(format!(
+            "因子分析變數之相關矩陣的條件數為 {condition_number:.2}，超過 {CONDITION_NUMBER_LIMIT}，可能有多重共線性"
+        ));
+    }
+    warnings
+}
+
+/// The conventional threshold below which a scale's internal consistency is unacceptable.
+const ACCEPTABLE_ALPHA: f64 = 0.7;
+
+/// Kaiser's threshold below which sampling adequacy is unacceptable.
+const KMO_LIMIT: f64 = 0.5;
+
+/// The `--id-column` is left out, and with `--only-regex` the columns the field file does not describe are left out as well.
+fn describe(dataframe: &DataFrame, fields: &[Field], options: &Options) -> DataFrame {
+    let description = described_columns(dataframe, fields, options)
+        .describe(Some(&options.percentiles))
+        .unwrap();
+    if options.ddof == 1 {
+        description
+    } else {
+        population_deviations(&description)
+    }
+}
+
+/// polars always divides by n − 1, so with `--ddof 0` the `std` row is rescaled by √((n − 1) / n),
+/// where n leaves out the nulls that polars counts in `count`.
+fn population_deviations(description: &DataFrame) -> DataFrame {
+    let statistics = description
+        .column("describe")
+        .unwrap()
+        .utf8()
+        .unwrap()
+        .into_no_null_iter()
+        .collect::<Vec<&str>>();
+    let row = |name: &str| statistics.iter().position(|statistic| *statistic == name);
+    let (Some(count_row), Some(null_row), Some(std_row)) =
+        (row("count"), row("null_count"), row("std"))
+    else {
+        return description.clone();
+    };
+    DataFrame::new(
+        description
+            .get_columns()
+            .iter()
+            .map(|column| {
+                column.f64().map_or_else(
+                    |_| column.clone(),
+                    |values| {
+                        let mut values = values.into_iter().collect::<Vec<Option<f64>>>();
+                        if let (Some(count), Some(nulls), Some(deviation)) =
+                            (values[count_row], values[null_row], values[std_row])
+                        {
+                            let observed = count - nulls;
+                            values[std_row] =
+                                Some(deviation * ((observed - 1.0) / observed).sqrt());
+                        }
+                        Series::new(column.name(), values)
+                    },
+                )
+            })
+            .collect(),
+    )
+    .unwrap()
+}
+
+/// The frequency table of every nominal field, with the descriptive statistics, and the
+/// `--crosstab` tables.
+fn frequency_tables(
+    dataframe: &DataFrame,
+    fields: &[Field],
+    options: &Options,
+) -> Result<(Vec<Frequency>, Vec<Crosstab>), AnalysisError> {
+    let frequencies = if options.includes(Section::Describe) {
+        fields
+            .iter()
+            .filter(|field| field.scale == Scale::Nominal)
+            .filter_map(|field| dataframe.column(&field.name).ok())
+            .map(frequency)
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let nominal_column = |name: &String| {
+        let column = fields
+            .iter()
+            .any(|field| &field.name == name && field.scale == Scale::Nominal)
+            .then(|| dataframe.column(name).ok())
+            .flatten();
+        column.ok_or_else(|| AnalysisError::Usage(format!("{name} is not a nominal field.")))
+    };
+    let crosstabs = options
+        .crosstab
+        .iter()
+        .map(|(rows, columns)| Ok(crosstab(nominal_column(rows)?, nominal_column(columns)?)))
+        .collect::<Result<_, AnalysisError>>()?;
+    Ok((frequencies, crosstabs))
+}
+
+fn frequency(column: &Series) -> Frequency {
+    let value_counts = column.drop_nulls().value_counts(true, true).unwrap();
+    let [values, counts] = value_counts.get_columns() else {
+        unreachable!()
+    };
+    let counts = counts.cast(&DataType::UInt64).unwrap();
+    let mut frequency = (0..values.len())
+        .map(|index| {
+            (
+                Some(values.str_value(index).unwrap().into_owned()),
+                usize::try_from(counts.u64().unwrap().get(index).unwrap()).unwrap(),
+            )
+        })
+        .collect::<Vec<(Option<String>, usize)>>();
+    frequency.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then_with(|| a.cmp(b)));
+    if column.null_count() > 0 {
+        frequency.push((None, column.null_count()));
+    }
+    Frequency {
+        field: column.name().to_owned(),
+        counts: frequency,
+    }
+}
+
+/// The categories of `rows` and `columns` are ordered as in their frequency tables.
+fn crosstab(rows: &Series, columns: &Series) -> Crosstab {
+    let levels = |column: &Series| {
+        frequency(column)
+            .counts
+            .into_iter()
+            .map(|(level, _)| level)
+            .collect::<Vec<Option<String>>>()
+    };
+    let (row_levels, column_levels) = (levels(rows), levels(columns));
+    let level = |column: &Series, missing: &BooleanChunked, row: usize| {
+        (missing.get(row) != Some(true)).then(|| column.str_value(row).unwrap().into_owned())
+    };
+    let (rows_missing, columns_missing) = (rows.is_null(), columns.is_null());
+    let mut counts = vec![vec![0; column_levels.len()]; row_levels.len()];
+    for row in 0..rows.len() {
+        let position = |levels: &[Option<String>], level: Option<String>| {
+            levels
+                .iter()
+                .position(|candidate| *candidate == level)
+                .unwrap()
+        };
+        let row_index = position(&row_levels, level(rows, &rows_missing, row));
+        let column_index = position(&column_levels, level(columns, &columns_missing, row));
+        counts[row_index][column_index] += 1;
+    }
+    Crosstab {
+        rows: rows.name().to_owned(),
+        columns: columns.name().to_owned(),
+        row_levels,
+        column_levels,
+        counts,
+    }
+}
+
+/// The unweighted description, and with `--weights` the weighted one next to it.
+fn descriptions(
+    dataframe: &DataFrame,
+    fields: &[Field],
+    options: &Options,
+) -> Result<(Option<DataFrame>, Option<DataFrame>), AnalysisError> {
+    if !options.includes(Section::Describe) {
+        return Ok((None, None));
+    }
+    let weighted_description = match &options.weights {
+        Some(weight_field) => Some(weighted_describe(
+            &described_columns(dataframe, fields, options),
+            &weight_column(dataframe, weight_field)?,
+            &options.percentiles,
+            options.ddof,
+        )),
+        None => None,
+    };
+    Ok((
+        Some(describe(dataframe, fields, options)),
+        weighted_description,
+    ))
+}
+
+fn described_columns(dataframe: &DataFrame, fields: &[Field], options: &Options) -> DataFrame {
+    let column_names = dataframe.get_column_names();
+    let mut order = canonical_order(&column_names, fields);
+    if options.only_regex.is_some() {
+        order.retain(|name| fields.iter().any(|field| &field.name == name));
+    }
+    order.retain(|name| options.id_column.as_ref() != Some(name));
+    dataframe.select(order).unwrap()
+}
+
+/// The rows of [`DataFrame::describe`], with every respondent counting as their weight: the
+/// counts are sums of weights, the standard deviation treats the weights as frequencies, and a
+/// quantile is the smallest value whose cumulative weight reaches it.
+fn weighted_describe(
+    dataframe: &DataFrame,
+    weights: &[f64],
+    percentiles: &[f64],
+    ddof: u8,
+) -> DataFrame {
+    let mut statistics = vec!["count", "null_count", "mean", "std", "min"];
+    let quantile_names = percentiles
+        .iter()
+        .map(|quantile| format!("{}%", quantile * 100.0))
+        .collect::<Vec<String>>();
+    statistics.extend(quantile_names.iter().map(String::as_str));
+    statistics.push("max");
+    let mut columns = vec![Series::new("describe", statistics)];
+    for column in dataframe.get_columns() {
+        let values = column
+            .cast(&DataType::Float64)
+            .unwrap()
+            .f64()
+            .unwrap()
+            .into_iter()
+            .collect::<Vec<Option<f64>>>();
+        let observed_weight = values
+            .iter()
+            .zip(weights)
+            .filter(|(value, _)| value.is_some())
+            .map(|(_, weight)| weight)
+            .sum::<f64>();
+        let mut column_statistics = vec![
+            Some(observed_weight),
+            Some(weights.iter().sum::<f64>() - observed_weight),
+        ];
+        column_statistics.extend(weighted_statistics(
+            column,
+            &values,
+            weights,
+            percentiles,
+            ddof,
+        ));
+        columns.push(Series::new(column.name(), column_statistics));
+    }
+    DataFrame::new(columns).unwrap()
+}
+
+fn weighted_statistics(
+    column: &Series,
+    values: &[Option<f64>],
+    weights: &[f64],
+    percentiles: &[f64],
+    ddof: u8,
+) -> Vec<Option<f64>> {
+    let mut observations = values
+        .iter()
+        .zip(weights)
+        .filter_map(|(value, weight)| value.map(|value| (value, *weight)))
+        .collect::<Vec<(f64, f64)>>();
+    let total = observations.iter().map(|(_, weight)| weight).sum::<f64>();
+    if !column.dtype().is_numeric() || total <= 0.0 {
+        return vec![None; percentiles.len() + 4];
+    }
+    observations.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+    let mean = observations
+        .iter()
+        .map(|(value, weight)| value * weight)
+        .sum::<f64>()
+        / total;
+    let variance = observations
+        .iter()
+        .map(|(value, weight)| weight * (value - mean).powi(2))
+        .sum::<f64>()
+        / (total - f64::from(ddof));
+    let quantile = |quantile: f64| {
+        let mut cumulative = 0.0;
+        observations.iter().find_map(|(value, weight)| {
+            cumulative += weight;
+            (cumulative >= quantile * total).then_some(*value)
+        })
+    };
+    let mut statistics = vec![
+        Some(mean),
+        Some(variance.sqrt()),
+        observations.first().map(|(value, _)| *value),
+    ];
+    statistics.extend(percentiles.iter().map(|&percentile| quantile(percentile)));
+    statistics.push(observations.last().map(|(value, _)| *value));
+    statistics
+}
+
+/// The correlation matrices, or the `--rows`/`--cols` block instead of them, with their
+/// significance applied.
+fn correlations(
+    processed_data: &[Vec<f64>],
+    observed: &[Vec<bool>],
+    fields: &[&Field],
+    overrides: &[Override],
+    options: &Options,
+    weights: Option<&[f64]>,
+    timings: &mut Vec<(String, Duration)>,
+) -> Result<Correlations, AnalysisError> {
+    let cross_correlation = !options.rows.is_empty();
+    let [mut pearson, mut spearman, mut kendall] = if cross_correlation {
+        [None, None, None]
+    } else {
+        correlation_matrices(
+            processed_data,
+            observed,
+            fields,
+            overrides,
+            options,
+            weights,
+            timings,
+        )?
+    };
+    let mut cross = cross_correlation
+        .then(|| {
+            cross_correlation_block(
+                processed_data,
+                observed,
+                fields,
+                overrides,
+                options,
+                weights,
+            )
+        })
+        .transpose()?;
+    let mut point_biserial = (!cross_correlation && options.includes(Section::PointBiserial))
+        .then(|| point_biserial_block(processed_data, observed, fields, options, weights))
+        .flatten();
+    apply_significance(
+        [
+            (Coefficient::Pearson, &mut pearson),
+            (ordinal_coefficient(options), &mut spearman),
+            (nominal_coefficient(options), &mut kendall),
+        ],
+        [&mut cross, &mut point_biserial],
+        options,
+    );
+    Ok(([pearson, spearman, kendall], [cross, point_biserial]))
+}
+
+fn correlation_matrices(
+    processed_data: &[Vec<f64>],
+    observed: &[Vec<bool>],
+    fields: &[&Field],
+    overrides: &[Override],
+    options: &Options,
+    weights: Option<&[f64]>,
+    timings: &mut Vec<(String, Duration)>,
+) -> Result<[Option<CorrelationMatrix>; 3], AnalysisError> {
+    let mut matrices = [None, None, None];
+    let coefficients = [
+        Coefficient::Pearson,
+        ordinal_coefficient(options),
+        nominal_coefficient(options),
+    ];
+    for (matrix, coefficient) in matrices.iter_mut().zip(coefficients) {
+        if !options.includes(coefficient.section()) {
+            continue;
+        }
+        *matrix = Some(timed(
+            timings,
+            &format!("{} 相關矩陣", coefficient.name()),
+            || {
+                correlation(
+                    processed_data,
+                    observed,
+                    fields,
+                    coefficient,
+                    overrides,
+                    options,
+                    weights,
+                )
+            },
+        )?);
+    }
+    Ok(matrices)
+}
+
+fn timed<T>(timings: &mut Vec<(String, Duration)>, step: &str, run: impl FnOnce() -> T) -> T {
+    info!("開始{step}");
+    let started = Instant::now();
+    let result = run();
+    let elapsed = started.elapsed();
+    info!("{step}完成（{elapsed:.2?}）");
+    timings.push((step.to_owned(), elapsed));
+    result
+}
+
+const fn ordinal_coefficient(options: &Options) -> Coefficient {
+    match options.ordinal_coefficient {
+        OrdinalCoefficient::Spearman => Coefficient::Spearman,
+        OrdinalCoefficient::Gamma => Coefficient::Gamma,
+    }
+}
+
+const fn nominal_coefficient(options: &Options) -> Coefficient {
+    match options.nominal_test {
+        NominalTest::Kendall => Coefficient::Kendall,
+        NominalTest::Chisq => Coefficient::ChiSquare,
+    }
+}
+
+fn apply_significance(
+    mut matrices: [(Coefficient, &mut Option<CorrelationMatrix>); 3],
+    mut blocks: [&mut Option<CrossCorrelation>; 2],
+    options: &Options,
+) {
+    if options.correction != Correction::None {
+        for (_, matrix) in &mut matrices {
+            if let Some(matrix) = matrix {
+                correct_matrix(matrix, options.correction);
+            }
+        }
+        for block in blocks.iter_mut().filter_map(|block| block.as_mut()) {
+            let values = block.cells.iter_mut().flatten().map(|(_, value)| value);
+            block.comparisons = Some(correct_p_values(values, options.correction));
+        }
+    }
+    let cross_values = blocks
+        .into_iter()
+        .flat_map(|block| block.iter_mut())
+        .flat_map(|block| block.cells.iter_mut().flatten())
+        .map(|(coefficient, value)| (*coefficient, value));
+    for (coefficient, value) in matrices
+        .into_iter()
+        .flat_map(|(coefficient, matrix)| {
+            matrix
+                .iter_mut()
+                .flat_map(|matrix| matrix.cells.iter_mut().flatten())
+                .map(move |value| (coefficient, value))
+        })
+        .chain(cross_values)
+    {
+        if let CorrelationValue::Valid(result) = value {
+            result.apply_significance_basis(
+                options.significance_basis(),
+                alpha(options, coefficient),
+            );
+            result.apply_bold_rule(options.bold_positive_only);
+            result.locale = options.number_locale;
+            result.precision = options.precision;
+        }
+    }
+}
+
+/// Corrects the unique pairs above the diagonal as one family and mirrors the adjusted p-values
+/// below it.
+fn correct_matrix(matrix: &mut CorrelationMatrix, correction: Correction) {
+    let upper_triangle = matrix
+        .cells
+        .iter_mut()
+        .enumerate()
+        .flat_map(|(row, values)| values.iter_mut().skip(row + 1));
+    matrix.comparisons = Some(correct_p_values(upper_triangle, correction));
+    for row in 0..matrix.cells.len() {
+        for column in 0..row {
+            let adjusted = match &matrix.cells[column][row] {
+                CorrelationValue::Valid(result) => result.adjusted_p_value,
+                _ => None,
+            };
+            if let CorrelationValue::Valid(result) = &mut matrix.cells[row][column] {
+                result.adjusted_p_value = adjusted;
+            }
+        }
+    }
+}
+
+/// Sets the adjusted p-value of every valid cell and returns how many were compared.
+fn correct_p_values<'a>(
+    values: impl Iterator<Item = &'a mut CorrelationValue>,
+    correction: Correction,
+) -> usize {
+    let mut results = values
+        .filter_map(|value| match value {
+            CorrelationValue::Valid(result) if !result.p_value.is_nan() => Some(result.as_mut()),
+            _ => None,
+        })
+        .collect::<Vec<&mut CorrelationResult>>();
+    let adjusted = adjusted_p_values(
+        &results
+            .iter()
+            .map(|result| result.p_value)
+            .collect::<Vec<f64>>(),
+        correction,
+    );
+    for (result, adjusted) in results.iter_mut().zip(adjusted) {
+        result.adjusted_p_value = Some(adjusted);
+    }
+    results.len()
+}
+
+/// Bonferroni multiplies every p-value by the number of comparisons; Holm multiplies the k-th
+/// smallest by `m - k + 1` and keeps the adjusted values monotonic. Both are capped at 1.
+fn adjusted_p_values(p_values: &[f64], correction: Correction) -> Vec<f64> {
+    let comparisons = p_values.len() as f64;
+    match correction {
+        Correction::None => p_values.to_vec(),
+        Correction::Bonferroni => p_values
+            .iter()
+            .map(|p_value| (p_value * comparisons).min(1.0))
+            .collect(),
+        Correction::Holm => {
+            let mut order = (0..p_values.len()).collect::<Vec<usize>>();
+            order.sort_by(|&a, &b| p_values[a].total_cmp(&p_values[b]));
+            let mut adjusted = vec![0.0; p_values.len()];
+            let mut running = 0.0_f64;
+            for (rank, index) in order.into_iter().enumerate() {
+                running = running.max((comparisons - rank as f64) * p_values[index]);
+                adjusted[index] = running.min(1.0);
+            }
+            adjusted
+        }
+    }
+}
+
+fn alpha(options: &Options, coefficient: Coefficient) -> f64 {
+    match coefficient {
+        Coefficient::Pearson | Coefficient::PointBiserial => options.alpha_pearson,
+        Coefficient::Kendall | Coefficient::ChiSquare => options.alpha_kendall,
+        Coefficient::Spearman | Coefficient::Gamma | Coefficient::Biserial => None,
+    }
+    .unwrap_or(options.alpha)
+}
+
+fn data_warnings(
+    data: (&[Vec<f64>], &[Vec<bool>]),
+    fields: &[&Field],
+    options: &Options,
+) -> Vec<String> {
+    let mut warnings = category_warnings(data, fields, options);
+    if options.significance_basis() == SignificanceBasis::Adjusted
+        && options.correction == Correction::None
+    {
+        warnings.push(String::from(
+            "尚未套用多重比較校正，顯著性仍以原始 p 值判斷。",
+        ));
+    }
+    if options.includes(Section::Factor) {
+        warnings.extend(factor_exclusion_warnings(data, fields));
+        warnings.extend(multicollinearity_warnings(data, fields));
+    }
+    warnings
+}
+
+fn category_warnings(
+    (processed_data, observed): (&[Vec<f64>], &[Vec<bool>]),
+    fields: &[&Field],
+    options: &Options,
+) -> Vec<String> {
+    let Some(max) = options.max_categories else {
+        return Vec::new();
+    };
+    fields
+        .iter()
+        .zip(processed_data.iter().zip(observed))
+        .filter(|(field, _)| field.scale == Scale::Nominal)
+        .map(|(field, (data, observed))| (field, levels(&observed_values(data, observed))))
+        .filter(|(_, levels)| *levels > max)
+        .map(|(field, levels)| {
+            format!(
+                "{} 有 {levels} 個類別，超過上限 {max}，其名目相關係數已略過",
+                field.name
+            )
+        })
+        .collect()
+}
+
+fn reverse_coding_warnings(reliability: &[Reliability]) -> Vec<String> {
+    reliability
+        .iter()
+        .flat_map(|reliability| {
+            reliability
+                .negative_items
+                .iter()
+                .map(|(name, correlation)| {
+                    format!(
+                        "{name} 與 {} 其餘題目總分的相關為 {correlation:.3}，可能需要反向計分",
+                        reliability.group
+                    )
+                })
+        })
+        .collect()
+}
+
+/// Pearson correlations whose absolute value exceeds this are treated as ±1, allowing for
+/// floating-point error.
+const PERFECT_CORRELATION: f64 = 0.999;
+
+fn perfect_correlation_warnings(analysis: &Analysis) -> Vec<String> {
+    analysis
+        .pearson
+        .iter()
+        .flat_map(CorrelationMatrix::upper_triangle)
+        .filter_map(|(name, other_name, value)| match value {
+            CorrelationValue::Valid(result) if result.r.abs() > PERFECT_CORRELATION => Some(format!(
+                "{name} 與 {other_name} 的 Pearson 相關為 {:.5}，可能是重複或由另一欄位推導的變數，建議在因子分析前刪除其中之一",
+                result.r
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+fn failure_warnings(analysis: &Analysis) -> Vec<String> {
+    let mut warnings = Vec::new();
+    for (name, other_name, coefficient, value) in analysis.correlation_pairs() {
+        if let CorrelationValue::Failed(error) = value {
+            warnings.push(format!(
+                "{name} × {other_name} 的 {coefficient} 計算失敗：{error}"
+            ));
+        }
+    }
+    match &analysis.factor {
+        Some(Err(error)) => warnings.push(format!("因子分析失敗：{error}")),
+        Some(Ok(solution)) => warnings.extend(
+            solution
+                .round_trip_issues
+                .iter()
+                .map(|issue| format!("polars 轉換為 pandas 後資料有變動：{issue}")),
+        ),
+        None => {}
+    }
+    for reliability in &analysis.reliability {
+        if let Some(Err(error)) = &reliability.omega {
+            warnings.push(format!(
+                "{} 的 McDonald's ω 計算失敗：{error}",
+                reliability.group
+            ));
+        }
+    }
+    warnings
+}
+
+/// The `--filter` expression, the rows it kept and the rows before filtering.
+type FilterCounts = (String, usize, usize);
+
+/// What [`select_rows`] did, for the notes at the top of the report.
+struct RowSelection {
+    duplicates: Option<usize>,
+    filter: Option<FilterCounts>,
+    sample: Option<(usize, usize)>,
+}
+
+/// Deduplicates, filters, samples and sorts the rows, in that order.
+fn select_rows(
+    dataframe: DataFrame,
+    options: &Options,
+) -> Result<(DataFrame, RowSelection), AnalysisError> {
+    let (dataframe, duplicates) = dedupe_rows(dataframe, options)?;
+    let (dataframe, filter) = filter_rows(dataframe, options)?;
+    let (dataframe, sample) = sample_rows(dataframe, options);
+    let dataframe = sort_by_date(dataframe, options)?;
+    preview(&dataframe, options);
+    let selection = RowSelection {
+        duplicates,
+        filter,
+        sample,
+    };
+    Ok((dataframe, selection))
+}
+
+fn dedupe_rows(
+    dataframe: DataFrame,
+    options: &Options,
+) -> Result<(DataFrame, Option<usize>), AnalysisError> {
+    if !options.dedupe_rows {
+        return Ok((dataframe, None));
+    }
+    let subset = options.id_column.clone().map(|id_column| vec![id_column]);
+    let deduplicated_dataframe = dataframe
+        .unique_stable(subset.as_deref(), UniqueKeepStrategy::First, None)
+        .map_err(|error| AnalysisError::Usage(format!("Unable to drop duplicate rows: {error}")))?;
+    let removed = dataframe.height() - deduplicated_dataframe.height();
+    Ok((deduplicated_dataframe, Some(removed)))
+}
+
+fn filter_rows(
+    dataframe: DataFrame,
+    options: &Options,
+) -> Result<(DataFrame, Option<FilterCounts>), AnalysisError> {
+    let Some(filter) = &options.filter else {
+        return Ok((dataframe, None));
+    };
+    let expr = parse_filter(filter)
+        .map_err(|error| AnalysisError::Usage(format!("Invalid filter `{filter}`: {error}")))?;
+    let height = dataframe.height();
+    let filtered_dataframe = dataframe.lazy().filter(expr).collect().map_err(|error| {
+        AnalysisError::Usage(format!("Unable to apply filter `{filter}`: {error}"))
+    })?;
+    let counts = (filter.clone(), filtered_dataframe.height(), height);
+    Ok((filtered_dataframe, Some(counts)))
+}
+
+fn sample_rows(dataframe: DataFrame, options: &Options) -> (DataFrame, Option<(usize, usize)>) {
+    match options.sample {
+        Some(size) if size < dataframe.height() => {
+            let sampled_dataframe = dataframe
+                .sample_n(size, false, true, Some(options.seed))
+                .unwrap();
+            (sampled_dataframe, Some((size, dataframe.height())))
+        }
+        _ => (dataframe, None),
+    }
+}
+
+/// `--rolling` windows are runs of consecutive rows, so the rows are put in date order first.
+fn sort_by_date(dataframe: DataFrame, options: &Options) -> Result<DataFrame, AnalysisError> {
+    let Some(column) = options
+        .rolling
+        .as_ref()
+        .map(|rolling| rolling.column.as_str())
+        .or(options.detrend.as_deref())
+    else {
+        return Ok(dataframe);
+    };
+    dataframe
+        .sort([column], false)
+        .map_err(|_| AnalysisError::MissingColumn(column.to_owned()))
+}
+
+/// With `--detrend` the columns, already in date order, are differenced or have their linear
+/// trend over the row order removed; missing values stay missing.
+fn detrended(columns: &[Column], options: &Options) -> Vec<Column> {
+    if options.detrend.is_none() {
+        return columns.to_vec();
+    }
+    columns
+        .iter()
+        .map(|column| match options.detrend_method {
+            DetrendMethod::Difference => column
+                .windows(2)
+                .map(|pair| {
+                    pair[0]
+                        .zip(pair[1])
+                        .map(|(previous, value)| value - previous)
+                })
+                .collect(),
+            DetrendMethod::Linear => {
+                let (rows, values): (Vec<f64>, Vec<f64>) = column
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(row, value)| value.map(|value| (row as f64, value)))
+                    .unzip();
+                let slope = covariance(&rows, &values) / variance(&rows);
+                let row_mean = rows.iter().sum::<f64>() / rows.len() as f64;
+                let value_mean = values.iter().sum::<f64>() / values.len() as f64;
+                column
+                    .iter()
+                    .enumerate()
+                    .map(|(row, value)| {
+                        value.map(|value| value - slope.mul_add(row as f64 - row_mean, value_mean))
+                    })
+                    .collect()
+            }
+        })
+        .collect()
+}
+
+fn preview(dataframe: &DataFrame, options: &Options) {
+    if let Some(rows) = options.preview {
+        eprintln!("{}", dataframe.head(Some(rows)));
+    }
+}
+
+/// Reads the `--weights` column and writes the `--rolling` windows, the last uses of the data
+/// frame before it is dropped.
+fn weights_and_rolling(
+    dataframe: DataFrame,
+    (processed_data, observed): (&[Vec<f64>], &[Vec<bool>]),
+    fields: &[&Field],
+    overrides: &[Override],
+    options: &Options,
+) -> Result<Option<Vec<f64>>, AnalysisError> {
+    let weights = options
+        .weights
+        .as_ref()
+        .map(|weight_field| weight_column(&dataframe, weight_field))
+        .transpose()?;
+    rolling(
+        &dataframe,
+        (processed_data, observed),
+        fields,
+        overrides,
+        options,
+        weights.as_deref(),
+    )?;
+    drop(dataframe);
+    Ok(weights)
+}
+
+/// Correlates every pair of the `--rolling-fields` within each window and writes the results
+/// to `<source>.rolling.csv`.
+fn rolling(
+    dataframe: &DataFrame,
+    (processed_data, observed): (&[Vec<f64>], &[Vec<bool>]),
+    fields: &[&Field],
+    overrides: &[Override],
+    options: &Options,
+    weights: Option<&[f64]>,
+) -> Result<(), AnalysisError> {
+    let Some(rolling) = &options.rolling else {
+        return Ok(());
+    };
+    let dates = dataframe
+        .column(&rolling.column)
+        .map_err(|_| AnalysisError::MissingColumn(rolling.column.clone()))?
+        .cast(&DataType::Utf8)
+        .map_err(|error| AnalysisError::Cast {
+            column: rolling.column.clone(),
+            message: format!("cannot be read as dates: {error}"),
+        })?;
+    let dates = dates
+        .utf8()
+        .into_iter()
+        .flatten()
+        .map(|date| date.unwrap_or_default().to_owned())
+        .collect::<Vec<String>>();
+    if rolling.window > dates.len() {
+        return Err(AnalysisError::Usage(format!(
+            "The rolling window of {} rows is longer than the {} rows analyzed.",
+            rolling.window,
+            dates.len()
+        )));
+    }
+    let indices = options
+        .rolling_fields
+        .iter()
+        .map(|name| field_index(fields, name))
+        .collect::<Result<Vec<usize>, AnalysisError>>()?;
+    let mut correlations = Vec::new();
+    for start in 0..=dates.len() - rolling.window {
+        let window = start..start + rolling.window;
+        for (position, &x) in indices.iter().enumerate() {
+            for &y in &indices[position + 1..] {
+                let coefficient = pair_coefficient(fields[x], fields[y], overrides, options);
+                let weights = weights.map(|weights| &weights[window.clone()]);
+                let value = pairwise_cell_value(
+                    (
+                        &processed_data[x][window.clone()],
+                        &observed[x][window.clone()],
+                    ),
+                    (
+                        &processed_data[y][window.clone()],
+                        &observed[y][window.clone()],
+                    ),
+                    coefficient,
+                    options,
+                    weights,
+                    |x, y| compute_coefficient(coefficient, x, y, weights, options),
+                );
+                correlations.push(RollingCorrelation {
+                    start: dates[window.start].clone(),
+                    end: dates[window.end - 1].clone(),
+                    var1: fields[x].name.clone(),
+                    var2: fields[y].name.clone(),
+                    coefficient,
+                    value,
+                });
+            }
+        }
+    }
+    write_rolling(
+        &format!("{}.rolling.csv", local_name(&options.source)),
+        &correlations,
+    )
+}
+
+fn factor(
+    data: (&[Vec<f64>], &[Vec<bool>]),
+    fields: &[&Field],
+    options: &Options,
+) -> (Option<Result<FactorSolution, PythonError>>, Vec<String>) {
+    if !options.includes(Section::Factor)
+        || options.offline
+        || !fields.iter().any(|field| is_factor_field(field))
+    {
+        (None, Vec::new())
+    } else if options.python_warnings {
+        let (solution, messages) =
+            capturing_warnings(|| fit_factor_analysis(data, fields, options));
+        (Some(solution), messages)
+    } else {
+        (Some(fit_factor_analysis(data, fields, options)), Vec::new())
+    }
+}
+
+fn polychoric_factor(fields: &[&Field], options: &Options) -> bool {
+    match options.factor_input {
+        FactorInput::Auto => {
+            let mut scales = fields
+                .iter()
+                .filter(|field| is_factor_field(field))
+                .map(|field| field.scale)
+                .peekable();
+            scales.peek().is_some() && scales.all(|scale| scale == Scale::Ordinal)
+        }
+        FactorInput::Data => false,
+        FactorInput::Polychoric => true,
+    }
+}
+
+/// The factor fields whose observed values vary, and their values in the rows that answered all
+/// of them: the factor analysis deletes missing values listwise.
+fn factor_fields<'a>(
+    (processed_data, observed): (&[Vec<f64>], &[Vec<bool>]),
+    fields: &[&'a Field],
+) -> (Vec<&'a Field>, Vec<Vec<f64>>) {
+    let (fields, columns): (Vec<&Field>, Vec<ObservedColumn>) = fields
+        .iter()
+        .zip(processed_data.iter().zip(observed))
+        .filter(|(field, (data, observed))| {
+            is_factor_field(field) && has_variance(&observed_values(data, observed))
+        })
+        .map(|(field, (data, observed))| (*field, (data.as_slice(), observed.as_slice())))
+        .unzip();
+    (fields, complete_cases(&columns))
+}
+
+/// Factor fields whose observed values do not vary, such as entirely missing items, would make
+/// the fit fail, so [`factor_fields`] skips them.
+fn factor_exclusion_warnings(
+    (processed_data, observed): (&[Vec<f64>], &[Vec<bool>]),
+    fields: &[&Field],
+) -> Vec<String> {
+    fields
+        .iter()
+        .zip(processed_data.iter().zip(observed))
+        .filter(|(field, (data, observed))| {
+            is_factor_field(field) && !has_variance(&observed_values(data, observed))
+        })
+        .map(|(field, _)| {
+            format!(
+                "{} 沒有變異（全為遺漏值或數值皆相同），已排除於因子分析之外",
+                field.name
+            )
+        })
+        .collect()
+}
+
+const fn is_factor_field(field: &Field) -> bool {
+    field.factor
+}
+
+/// Regressing each variable on the others gives `R²_j`, and `1 / (1 - R²_j)` is the j-th
+/// diagonal element of the inverse correlation matrix; `None` when that matrix is singular.
+fn variance_inflation_factors(columns: &[&[f64]]) -> Option<Vec<f64>> {
+    let standardized = columns
+        .iter()
+        .map(|column| {
+            let mean = column.iter().sum::<f64>() / column.len() as f64;
+            let deviation = variance(column).sqrt();
+            column
+                .iter()
+                .map(|value| (value - mean) / deviation)
+                .collect::<Vec<f64>>()
+        })
+        .collect::<Vec<_>>();
+    let correlation = standardized
+        .iter()
+        .map(|x| {
+            standardized
+                .iter()
+                .map(|y| x.iter().zip(y).map(|(x, y)| x * y).sum::<f64>() / (x.len() as f64 - 1.0))
+                .collect()
+        })
+        .collect::<Vec<Vec<f64>>>();
+    let inverse = invert(&correlation)?;
+    Some(
+        (0..columns.len())
+            .map(|index| inverse[index][index])
+            .collect(),
+    )
+}
+
+/// Condition numbers above this are commonly read as a sign of multicollinearity.
+const CONDITION_NUMBER_LIMIT: f64 = 30.0;
+
+/// With `--condition-number`, the ratio of the largest to the smallest eigenvalue of the factor
+/// fields' correlation matrix, infinite when the matrix is singular.
+fn condition_number(
+    data: (&[Vec<f64>], &[Vec<bool>]),
+    fields: &[&Field],
+    options: &Options,
+) -> Option<f64> {
+    if !options.condition_number {
+        return None;
+    }
+    let (_, columns) = factor_fields(data, fields);
+    let columns = columns.iter().map(Vec::as_slice).collect::<Vec<&[f64]>>();
+    if columns.len() < 2 {
+        return None;
+    }
+    let eigenvalues = symmetric_eigenvalues(&pearson_matrix(&columns));
+    let (largest, smallest) = (eigenvalues[0], eigenvalues[eigenvalues.len() - 1]);
+    Some(if smallest > 1e-12 {
+        largest / smallest
+    } else {
+        f64::INFINITY
+    })
+}
+
+fn multicollinearity_warnings(data: (&[Vec<f64>], &[Vec<bool>]), fields: &[&Field]) -> Vec<String> {
+    let (fields, columns) = factor_fields(data, fields);
+    let columns = columns.iter().map(Vec::as_slice).collect::<Vec<&[f64]>>();
+    if columns.len() < 2 {
+        return Vec::new();
+    }
+    variance_inflation_factors(&columns).map_or_else(
+        || {
+            vec![String::from(
+                "因子分析變數的相關矩陣為奇異矩陣，無法計算 VIF",
+            )]
+        },
+        |factors| {
+            fields
+                .iter()
+                .zip(factors)
+                .filter(|(_, factor)| *factor > 10.0)
+                .map(|(field, factor)| {
+                    format!("{} 的 VIF 為 {factor:.2}，可能有多重共線性", field.name)
+                })
+                .collect()
+        },
+    )
+}
+
+/// A p × p matrix of `f64` takes 8p² bytes, and `FactorAnalyzer` keeps several of them, so a very
+/// wide item set is refused before Python allocates anything.
+fn check_factor_memory(items: usize, options: &Options) -> Result<(), PythonError> {
+    let megabytes = (items as f64).powi(2) * 8.0 / 1_048_576.0;
+    if megabytes <= options.factor_memory_limit as f64 {
+        return Ok(());
+    }
+    Err(PythonError {
+        computation: String::from("factor analysis"),
+        message: format!(
+            "{items} 個題目的相關矩陣約需 {megabytes:.1} MB 記憶體，超過 --factor-memory-limit 的 {} MB，請減少因子分析的題目數",
+            options.factor_memory_limit
+        ),
+    })
+}
+
+fn fit_factor_analysis(
+    data: (&[Vec<f64>], &[Vec<bool>]),
+    fields: &[&Field],
+    options: &Options,
+) -> Result<FactorSolution, PythonError> {
+    let polychoric = polychoric_factor(fields, options);
+    let (factor_fields, columns) = factor_fields(data, fields);
+    let names = factor_fields
+        .iter()
+        .map(|field| field.name.as_str())
+        .collect::<Vec<&str>>();
+    let data = columns.iter().map(Vec::as_slice).collect::<Vec<&[f64]>>();
+    check_factor_memory(names.len(), options)?;
+    let columns = if polychoric {
+        polychoric_matrix(&data)
+    } else {
+        columns
+    };
+    let factor_analysis_dataframe = DataFrame::new(
+        names
+            .iter()
+            .zip(columns)
+            .map(|(name, column)| Series::new(name, column))
+            .collect::<Vec<Series>>(),
+    )
+    .unwrap();
+    let retries = options.python_retries;
+    let check_round_trip = options.round_trip_check;
+    let (rotation, n_factors) = (options.rotation.python_name(), options.n_factors);
+    let run = move || {
+        with_retries(retries, "factor analysis", || {
+            factor_analysis(
+                factor_analysis_dataframe.clone(),
+                polychoric,
+                check_round_trip,
+                rotation,
+                n_factors,
+            )
+        })
+    };
+    let solution = match options.factor_timeout {
+        Some(seconds) => with_timeout(Duration::from_secs(seconds), "factor analysis", run),
+        None => run(),
+    }?;
+    let column_names = solution.loadings.get_column_names();
+    let order = canonical_order(&column_names, fields.iter().copied());
+    let communalities = order
+        .iter()
+        .map(|name| {
+            let index = column_names.iter().position(|column| column == name);
+            solution.communalities[index.unwrap()]
+        })
+        .collect();
+    Ok(FactorSolution {
+        loadings: solution.loadings.select(&order).unwrap(),
+        communalities,
+        variance: solution.variance,
+        round_trip_issues: solution.round_trip_issues,
+        from_correlation_matrix: solution.from_correlation_matrix,
+        eigenvalues: solution.eigenvalues,
+        adequacy: solution.adequacy,
+    })
+}
+
+/// Orders columns by the field file, followed by the columns it does not describe in their
+/// original order, so every table in the report lines up.
+fn canonical_order<'a>(
+    column_names: &[&str],
+    fields: impl IntoIterator<Item = &'a Field>,
+) -> Vec<String> {
+    let field_names = fields
+        .into_iter()
+        .map(|field| field.name.as_str())
+        .collect::<Vec<&str>>();
+    let described = field_names
+        .iter()
+        .filter(|name| column_names.contains(name));
+    let undescribed = column_names
+        .iter()
+        .filter(|name| !field_names.contains(name));
+    described
+        .chain(undescribed)
+        .map(|name| (*name).to_owned())
+        .collect()
+}
+
+/// Fields with the same cleaned values in every row are reported, and all but the first are
+/// dropped with `--dedup-columns`.
+fn identical_columns<'a>(
+    fields: Vec<&'a Field>,
+    columns: Vec<Column>,
+    options: &Options,
+    warnings: &mut Vec<String>,
+) -> (Vec<&'a Field>, Vec<Column>) {
+    let mut kept: Vec<(&Field, Column)> = Vec::new();
+    for (field, column) in fields.into_iter().zip(columns) {
+        let Some((first, _)) = kept.iter().find(|(_, kept)| *kept == column) else {
+            kept.push((field, column));
+            continue;
+        };
+        let warning = if options.dedup_columns {
+            format!(
+                "{} 與 {} 的資料完全相同，已排除 {}",
+                first.name, field.name, field.name
+            )
+        } else {
+            format!(
+                "{} 與 {} 的資料完全相同，可加上 --dedup-columns 僅保留前者",
+                first.name, field.name
+            )
+        };
+        warnings.push(warning);
+        if !options.dedup_columns {
+            kept.push((field, column));
+        }
+    }
+    kept.into_iter().unzip()
+}
+
+/// Fields whose observed values are all the same are left out, or with
+/// `--include-zero-variance-as-note` kept with their missing values filled by that value, so
+/// every correlation with them is marked not applicable.
+fn zero_variance_columns<'a>(
+    fields: Vec<&'a Field>,
+    columns: Vec<Column>,
+    options: &Options,
+    warnings: &mut Vec<String>,
+) -> (Vec<&'a Field>, Vec<Column>) {
+    let mut kept = Vec::new();
+    for (field, column) in fields.into_iter().zip(columns) {
+        let mut observed = column.iter().flatten();
+        let Some(&value) = observed.next() else {
+            kept.push((field, column));
+            continue;
+        };
+        if !observed.all(|other| other - value == 0.0) {
+            kept.push((field, column));
+        } else if options.include_zero_variance_as_note {
+            warnings.push(format!(
+                "{} 的資料皆為 {value}（變異數為 0），相關係數皆標示為常數欄位",
+                field.name
+            ));
+            kept.push((field, vec![Some(value); column.len()]));
+        } else {
+            warnings.push(format!(
+                "{} 的資料皆為 {value}（變異數為 0），已排除於分析之外",
+                field.name
+            ));
+        }
+    }
+    kept.into_iter().unzip()
+}
+
+fn numeric_columns<'a>(
+    dataframe: &DataFrame,
+    fields: &'a [Field],
+    options: &Options,
+) -> Result<NumericColumns<'a>, AnalysisError> {
+    let mut warnings = Vec::new();
+    let columns = fields
+        .iter()
+        .map(|field| {
+            dataframe
+                .column(&field.name)
+                .map_err(|_| AnalysisError::MissingColumn(field.name.clone()))
+        })
+        .collect::<Result<Vec<&Series>, AnalysisError>>()?;
+    let (fields, processed_data) = fields
+        .par_iter()
+        .zip(columns.par_iter())
+        .map(|(field, column)| (field, numeric_column(column, field)))
+        .collect::<Vec<_>>()
+        .into_iter()
+        .filter_map(|(field, column)| match column {
+            Ok((data, offending_values)) => {
+                debug!(
+                    "{}：{} 筆有效資料",
+                    field.name,
+                    data.iter().flatten().count()
+                );
+                if !offending_values.is_empty() {
+                    warnings.push(format!(
+                        "{} 有無法轉換為數值的資料，已視為遺漏值：{}",
+                        field.name,
+                        offending_values.join("、")
+                    ));
+                }
+                Some((field, data))
+            }
+            Err(offending_values) => {
+                warnings.push(format!(
+                    "{} 無法轉換為數值，已排除於數值分析之外：{}",
+                    field.name,
+                    offending_values.join("、")
+                ));
+                None
+            }
+        })
+        .unzip();
+    let (fields, processed_data) =
+        identical_columns(fields, processed_data, options, &mut warnings);
+    let (fields, processed_data) =
+        zero_variance_columns(fields, processed_data, options, &mut warnings);
+    for warning in &warnings {
+        eprintln!("{warning}");
+    }
+    Ok((fields, processed_data, warnings))
+}
+
+/// Casts a field to `f64` after applying its cleaning rules. Text in a nominal field is
+/// label-encoded instead. A sample of values that could not be cast is returned alongside the
+/// data, or as the error when none of the values could be cast.
+fn numeric_column(column: &Series, field: &Field) -> Result<(Column, Vec<String>), Vec<String>> {
+    let data = if field.scale == Scale::Nominal && column.dtype() == &DataType::Utf8 {
+        label_encode(column.utf8().unwrap())
+    } else {
+        let Ok(cast) = column.cast(&DataType::Float64) else {
+            return Err(vec![column.dtype().to_string()]);
+        };
+        cast.f64().unwrap().into_iter().collect()
+    };
+    let mut offending_values = Vec::new();
+    if let Ok(text) = column.utf8() {
+        for (value, data) in text.into_iter().zip(&data) {
+            if let (Some(value), None) = (value, data) {
+                if !offending_values.iter().any(|offending| offending == value) {
+                    offending_values.push(value.to_owned());
+                }
+            }
+        }
+    }
+    if !offending_values.is_empty() && data.iter().all(Option::is_none) {
+        offending_values.truncate(5);
+        return Err(offending_values);
+    }
+    offending_values.truncate(5);
+    Ok((field.clean(data), offending_values))
+}
+
+fn label_encode(column: &Utf8Chunked) -> Vec<Option<f64>> {
+    let mut labels = column.into_iter().flatten().collect::<Vec<&str>>();
+    labels.sort_unstable();
+    labels.dedup();
+    column
+        .into_iter()
+        .map(|value| value.map(|value| labels.binary_search(&value).unwrap() as f64))
+        .collect()
+}
+
+fn weight_column(dataframe: &DataFrame, weight_field: &str) -> Result<Vec<f64>, AnalysisError> {
+    let column = dataframe
+        .column(weight_field)
+        .map_err(|_| AnalysisError::MissingColumn(weight_field.to_owned()))?;
+    let weights = column
+        .cast(&DataType::Float64)
+        .ok()
+        .and_then(|column| column.f64().ok()?.into_iter().collect::<Option<Vec<f64>>>());
+    match weights {
+        Some(weights) if weights.iter().all(|weight| *weight >= 0.0) => Ok(weights),
+        _ => Err(AnalysisError::Cast {
+            column: weight_field.to_owned(),
+            message: String::from("must not contain missing or negative weights"),
+        }),
+    }
+}
+
+fn set_env(table_style: TableStyle) {
+    env::set_var("POLARS_FMT_MAX_ROWS", u16::MAX.to_string());
+    env::set_var("POLARS_FMT_MAX_COLS", u16::MAX.to_string());
+    env::set_var("POLARS_FMT_STR_LEN", u16::MAX.to_string());
+    env::set_var(
+        "POLARS_FMT_TABLE_FORMATTING",
+        match table_style {
+            TableStyle::Ascii => "ASCII_FULL",
+            TableStyle::Markdown | TableStyle::Github | TableStyle::Csv => "ASCII_MARKDOWN",
+        },
+    );
+    env::set_var("POLARS_TABLE_WIDTH", u16::MAX.to_string());
+    env::set_var("POLARS_FMT_TABLE_HIDE_COLUMN_DATA_TYPES", 1.to_string());
+    env::set_var("POLARS_FMT_TABLE_HIDE_COLUMN_SEPARATOR", 1.to_string());
+    env::set_var(
+        "POLARS_FMT_TABLE_HIDE_DATAFRAME_SHAPE_INFORMATION",
+        1.to_string(),
+    );
+}
+
+fn correlation(
+    processed_data: &[Vec<f64>],
+    observed: &[Vec<bool>],
+    fields: &[&Field],
+    coefficient: Coefficient,
+    overrides: &[Override],
+    options: &Options,
+    weights: Option<&[f64]>,
+) -> Result<CorrelationMatrix, AnalysisError> {
+    if !options.offline && needs_python(coefficient, options) {
+        check_scipy()?;
+    }
+    let (ordinal, nominal) = (ordinal_coefficient(options), nominal_coefficient(options));
+    let columns = processed_data
+        .iter()
+        .zip(observed)
+        .zip(fields)
+        .filter(|(_, field)| {
+            coefficient_for(field.scale, field.scale, ordinal, nominal) == coefficient
+        })
+        .collect::<Vec<((&Vec<f64>, &Vec<bool>), &&Field)>>();
+    let weighted = coefficient == Coefficient::Pearson && weights.is_some();
+    let native = (is_native(coefficient, options) && !weighted).then(|| {
+        let data = columns.iter().map(|((data, _), _)| data.as_slice());
+        if coefficient == Coefficient::Spearman {
+            let ranks = data.map(average_ranks).collect::<Vec<Vec<f64>>>();
+            pearson_matrix(&ranks.iter().map(Vec::as_slice).collect::<Vec<&[f64]>>())
+        } else {
+            pearson_matrix(&data.collect::<Vec<&[f64]>>())
+        }
+    });
+    let cells_of_row = |row: usize| {
+        let (x, x_field) = &columns[row];
+        columns
+            .iter()
+            .enumerate()
+            .map(|(column, (y, y_field))| {
+                if column < row {
+                    // Mirrored from the upper triangle below.
+                    return CorrelationValue::NotValid;
+                }
+                if column == row {
+                    let (data, observed) = x;
+                    return if has_variance(&observed_values(data, observed)) {
+                        CorrelationValue::Diagonal
+                    } else {
+                        CorrelationValue::Constant
+                    };
+                }
+                let pair = pair_coefficient(x_field, y_field, overrides, options);
+                let (x, y) = (
+                    (x.0.as_slice(), x.1.as_slice()),
+                    (y.0.as_slice(), y.1.as_slice()),
+                );
+                let mut value =
+                    pairwise_cell_value(x, y, pair, options, weights, |x, y| match &native {
+                        Some(native) if pair == coefficient => {
+                            let r = native[row][column];
+                            Ok((r, pearson_p_value(r, x.len())))
+                        }
+                        _ => compute_coefficient(pair, x, y, weights, options),
+                    });
+                match &mut value {
+                    CorrelationValue::Valid(result) if pair != coefficient => {
+                        result.statistic = pair.name();
+                    }
+                    _ => {}
+                }
+                value
+            })
+            .collect::<Vec<CorrelationValue>>()
+    };
+    // Rows are computed in parallel; the scipy calls among them still take turns holding the
+    // GIL. Capturing Python warnings swaps a process-wide filter, so that stays sequential.
+    let mut cells: Vec<Vec<CorrelationValue>> = if options.python_warnings {
+        (0..columns.len()).map(cells_of_row).collect()
+    } else {
+        (0..columns.len())
+            .into_par_iter()
+            .map(cells_of_row)
+            .collect()
+    };
+    // Only the upper triangle is computed; the matrix is symmetric.
+    for row in 1..cells.len() {
+        let (above, below) = cells.split_at_mut(row);
+        for (value, upper) in below[0].iter_mut().zip(above.iter()) {
+            *value = upper[row].clone();
+        }
+    }
+    Ok(CorrelationMatrix {
+        names: columns
+            .iter()
+            .map(|(_, field)| field.name.clone())
+            .collect(),
+        cells,
+        comparisons: None,
+    })
+}
+
+/// The variance-covariance matrix of the interval and ratio fields, with `--emit-covariance`. It
+/// is computed on the values before `--standardize`, over the rows where both fields were
+/// answered.
+fn covariance_matrix(
+    columns: &[Column],
+    fields: &[&Field],
+    options: &Options,
+) -> Option<CovarianceMatrix> {
+    if !options.emit_covariance {
+        return None;
+    }
+    let detrended = detrended(columns, options);
+    let columns = detrended
+        .iter()
+        .zip(fields)
+        .filter(|(_, field)| matches!(field.scale, Scale::Interval | Scale::Ratio))
+        .collect::<Vec<(&Column, &&Field)>>();
+    Some(CovarianceMatrix {
+        names: columns
+            .iter()
+            .map(|(_, field)| field.name.clone())
+            .collect(),
+        cells: columns
+            .iter()
+            .map(|(x, _)| {
+                columns
+                    .iter()
+                    .map(|(y, _)| {
+                        let (x, y): (Vec<f64>, Vec<f64>) =
+                            x.iter().zip(*y).filter_map(|(x, y)| x.zip(*y)).unzip();
+                        covariance_with_ddof(&x, &y, options.ddof)
+                    })
+                    .collect()
+            })
+            .collect(),
+    })
+}
+
+fn cross_correlation_block(
+    processed_data: &[Vec<f64>],
+    observed: &[Vec<bool>],
+    fields: &[&Field],
+    overrides: &[Override],
+    options: &Options,
+    weights: Option<&[f64]>,
+) -> Result<CrossCorrelation, AnalysisError> {
+    let find = |names: &[String]| {
+        names
+            .iter()
+            .map(|name| field_index(fields, name))
+            .collect::<Result<Vec<usize>, AnalysisError>>()
+    };
+    let rows = find(&options.rows)?;
+    let columns = find(&options.cols)?;
+    Ok(CrossCorrelation {
+        row_names: options.rows.clone(),
+        column_names: options.cols.clone(),
+        cells: columns
+            .iter()
+            .map(|&column| {
+                rows.iter()
+                    .map(|&row| {
+                        let coefficient =
+                            pair_coefficient(fields[row], fields[column], overrides, options);
+                        let value = pairwise_cell_value(
+                            (&processed_data[row], &observed[row]),
+                            (&processed_data[column], &observed[column]),
+                            coefficient,
+                            options,
+                            weights,
+                            |x, y| compute_coefficient(coefficient, x, y, weights, options),
+                        );
+                        (coefficient, value)
+                    })
+                    .collect()
+            })
+            .collect(),
+        comparisons: None,
+    })
+}
+
+/// Point-biserial r (Pearson's r with a 0/1 variable) of every nominal field with exactly two
+/// observed levels against every ordinal, interval and ratio field, pairs that the matrices keep
+/// apart because their scales differ. `None` when either side is empty.
+fn point_biserial_block(
+    processed_data: &[Vec<f64>],
+    observed: &[Vec<bool>],
+    fields: &[&Field],
+    options: &Options,
+    weights: Option<&[f64]>,
+) -> Option<CrossCorrelation> {
+    let binary = (0..fields.len())
+        .filter(|&index| fields[index].scale == Scale::Nominal)
+        .filter(|&index| {
+            let kept = processed_data[index]
+                .iter()
+                .zip(&observed[index])
+                .filter_map(|(value, &observed)| observed.then_some(*value))
+                .collect::<Vec<f64>>();
+            is_dichotomous(&kept)
+        })
+        .collect::<Vec<usize>>();
+    let others = (0..fields.len())
+        .filter(|&index| fields[index].scale != Scale::Nominal)
+        .collect::<Vec<usize>>();
+    if binary.is_empty() || others.is_empty() {
+        return None;
+    }
+    let coefficient = Coefficient::PointBiserial;
+    let names = |indices: &[usize]| {
+        indices
+            .iter()
+            .map(|&index| fields[index].name.clone())
+            .collect()
+    };
+    Some(CrossCorrelation {
+        row_names: names(&binary),
+        column_names: names(&others),
+        cells: others
+            .iter()
+            .map(|&column| {
+                binary
+                    .iter()
+                    .map(|&row| {
+                        let value = pairwise_cell_value(
+                            (&processed_data[row], &observed[row]),
+                            (&processed_data[column], &observed[column]),
+                            coefficient,
+                            options,
+                            weights,
+                            |x, y| compute_coefficient(coefficient, x, y, weights, options),
+                        );
+                        (coefficient, value)
+                    })
+                    .collect()
+            })
+            .collect(),
+        comparisons: None,
+    })
+}
+
+/// An override naming the pair, in either order, wins over the scale-based dispatch.
+fn pair_coefficient(
+    a: &Field,
+    b: &Field,
+    overrides: &[Override],
+    options: &Options,
+) -> Coefficient {
+    overrides
+        .iter()
+        .find(
+            |Override {
+                 fields: (first, second),
+                 ..
+             }| {
+                (first == &a.name && second == &b.name) || (first == &b.name && second == &a.name)
+            },
+        )
+        .map_or_else(
+            || {
+                coefficient_for(
+                    a.scale,
+                    b.scale,
+                    ordinal_coefficient(options),
+                    nominal_coefficient(options),
+                )
+            },
+            |pair| pair.coefficient,
+        )
+}
+
+const fn needs_python(coefficient: Coefficient, options: &Options) -> bool {
+    match coefficient {
+        Coefficient::Pearson => !is_native(coefficient, options) && options.weights.is_none(),
+        Coefficient::Spearman => !is_native(coefficient, options),
+        Coefficient::Gamma | Coefficient::Biserial | Coefficient::PointBiserial => false,
+        Coefficient::Kendall => options.weights.is_none(),
+        Coefficient::ChiSquare => true,
+    }
+}
+
+/// Whether `coefficient` is computed in Rust rather than by scipy.
+const fn is_native(coefficient: Coefficient, options: &Options) -> bool {
+    let native_engine = matches!(options.engine, Engine::Native);
+    match coefficient {
+        Coefficient::Pearson => native_engine || options.native_pearson,
+        Coefficient::Spearman => native_engine,
+        Coefficient::Gamma
+        | Coefficient::Biserial
+        | Coefficient::PointBiserial
+        | Coefficient::Kendall
+        | Coefficient::ChiSquare => false,
+    }
+}
+
+/// Pairwise deletion: a cell is computed over only the rows where both fields were answered.
+/// Pairs without missing values go to `method` as they are; the others are computed from their
+/// kept rows, to which the precomputed native Pearson matrix does not apply.
+fn pairwise_cell_value(
+    (x, x_observed): (&[f64], &[bool]),
+    (y, y_observed): (&[f64], &[bool]),
+    coefficient: Coefficient,
+    options: &Options,
+    weights: Option<&[f64]>,
+    method: impl Fn(&[f64], &[f64]) -> Result<(f64, f64), PythonError>,
+) -> CorrelationValue {
+    let kept = (0..x.len())
+        .filter(|&row| x_observed[row] && y_observed[row])
+        .collect::<Vec<usize>>();
+    if kept.len() == x.len() {
+        return cell_value(x, y, coefficient, options, method);
+    }
+    let pick = |data: &[f64]| kept.iter().map(|&row| data[row]).collect::<Vec<f64>>();
+    let weights = weights.map(pick);
+    let mut value = cell_value(&pick(x), &pick(y), coefficient, options, |x, y| {
+        compute_coefficient(coefficient, x, y, weights.as_deref(), options)
+    });
+    if let CorrelationValue::Valid(result) = &mut value {
+        result.missing_rows = x.len() - kept.len();
+    }
+    value
+}
+
+fn cell_value(
+    x: &[f64],
+    y: &[f64],
+    coefficient: Coefficient,
+    options: &Options,
+    method: impl Fn(&[f64], &[f64]) -> Result<(f64, f64), PythonError>,
+) -> CorrelationValue {
+    if x.len() < 3 {
+        return CorrelationValue::NotValid;
+    }
+    if !has_variance(x) || !has_variance(y) {
+        return CorrelationValue::Constant;
+    }
+    let yule = coefficient == Coefficient::Kendall && is_dichotomous(x) && is_dichotomous(y);
+    if options.offline && !yule && needs_python(coefficient, options) && x != y {
+        return CorrelationValue::Skipped;
+    }
+    if matches!(coefficient, Coefficient::Kendall | Coefficient::ChiSquare)
+        && x != y
+        && options
+            .max_categories
+            .is_some_and(|max| levels(x) > max || levels(y) > max)
+    {
+        return CorrelationValue::TooManyCategories;
+    }
+    let fisher = yule && !options.offline && has_small_expected_count(two_by_two(x, y));
+    let method = |x: &[f64], y: &[f64]| {
+        if fisher {
+            let p_value = with_retries(options.python_retries, "fisher_exact", || {
+                fisher_exact(two_by_two(x, y))
+            })?;
+            Ok((yules_q(x, y), p_value))
+        } else if yule {
+            Ok(yules_q_test(x, y))
+        } else {
+            method(x, y)
+        }
+    };
+    let (mut value, messages) = if options.python_warnings {
+        capturing_warnings(|| correlation_value(x, y, options.min_n, method))
+    } else {
+        (correlation_value(x, y, options.min_n, method), Vec::new())
+    };
+    if let CorrelationValue::Valid(result) = &mut value {
+        result.python_warnings = messages;
+        if yule {
+            result.statistic = "Q";
+        }
+        if coefficient == Coefficient::ChiSquare {
+            result.statistic = "V";
+            result.chi_square = Some(chi_square_from_v(result.r, x, y));
+        }
+        result.exact_test = fisher;
+        if let (Coefficient::Pearson, Some(r0)) = (coefficient, options.test_against) {
+            result.reference = Some((r0, fisher_z_test(result.r, result.n, r0)));
+        }
+        if !yule
+            && matches!(
+                coefficient,
+                Coefficient::Pearson | Coefficient::Spearman | Coefficient::Kendall
+            )
+        {
+            let level = options.confidence_level;
+            (result.ci_low, result.ci_high) =
+                confidence_interval(result.r, result.n, coefficient, level).unzip();
+            result.confidence_level = Some(level);
+        }
+    }
+    if coefficient == Coefficient::Kendall && !yule {
+        if let CorrelationValue::Valid(result) = &mut value {
+            if options.kendall_detail {
+                result.pair_counts = Some(pair_counts(x, y, options.kendall_variant));
+            }
+            if options.weights.is_none() {
+                result.standard_error = Some(kendall_standard_error(x, y, options.kendall_variant));
+            }
+        }
+    }
+    if options.show_ties && coefficient == Coefficient::Kendall && !yule {
+        with_tied_pairs(value, x, y)
+    } else {
+        value
+    }
+}
+
+fn correlation_value(
+    x: &[f64],
+    y: &[f64],
+    min_n: Option<usize>,
+    method: impl Fn(&[f64], &[f64]) -> Result<(f64, f64), PythonError>,
+) -> CorrelationValue {
+    if x == y {
+        CorrelationValue::NotValid
+    } else if min_n.is_some_and(|min_n| x.len() < min_n) {
+        CorrelationValue::InsufficientSample
+    } else {
+        match method(x, y) {
+            Ok((r, p_value)) => {
+                CorrelationValue::Valid(Box::new(CorrelationResult::from((r, p_value, x.len()))))
+            }
+            Err(error) => CorrelationValue::Failed(error),
+        }
+    }
+}
+
+/// The items of each group are scored on the respondents who answered all of them, deleting
+/// missing values listwise.
+fn reliability(
+    (processed_data, observed): (&[Vec<f64>], &[Vec<bool>]),
+    fields: &[&Field],
+    options: &Options,
+) -> Vec<Reliability> {
+    if !options.includes(Section::Reliability) {
+        return Vec::new();
+    }
+    let mut groups = Vec::<(&str, Vec<&str>, Vec<ObservedColumn>)>::new();
+    for ((data, observed), field) in processed_data.iter().zip(observed).zip(fields) {
+        let Some(group) = &field.reliability_group else {
+            continue;
+        };
+        let item = (data.as_slice(), observed.as_slice());
+        match groups.iter_mut().find(|(name, _, _)| name == group) {
+            Some((_, names, items)) => {
+                names.push(&field.name);
+                items.push(item);
+            }
+            None => groups.push((group, vec![&field.name], vec![item])),
+        }
+    }
+    groups
+        .into_iter()
+        .filter(|(_, _, items)| items.len() > 1)
+        .map(|(group, names, items)| {
+            let complete = complete_cases(&items);
+            let items = complete.iter().map(Vec::as_slice).collect::<Vec<&[f64]>>();
+            Reliability {
+                group: group.to_owned(),
+                items: items.len(),
+                alpha: cronbach_alpha(&items),
+                negative_items: names
+                    .iter()
+                    .zip(corrected_item_total(&items))
+                    .filter(|(_, correlation)| *correlation < 0.0)
+                    .map(|(name, correlation)| ((*name).to_owned(), correlation))
+                    .collect(),
+                omega: (!options.offline).then(|| {
+                    with_retries(options.python_retries, "McDonald's omega", || {
+                        mcdonald_omega(&items)
+                    })
+                }),
+            }
+        })
+        .collect()
+}
+
+/// Missing values become 0, which after standardizing is the column mean. They only hold the
+/// place of the row: every analysis reads the observed mask and leaves them out.
+fn processed_column(column: &Column, options: &Options) -> Vec<f64> {
+    if !options.standardize {
+        return column.iter().map(|data| data.unwrap_or(0.0)).collect();
+    }
+    let observed = column.iter().flatten().copied().collect::<Vec<f64>>();
+    let mean = observed.iter().sum::<f64>() / observed.len() as f64;
+    let deviation = standard_deviation(&observed, options.ddof);
+    if deviation == 0.0 {
+        return vec![0.0; column.len()];
+    }
+    column
+        .iter()
+        .map(|data| data.map_or(0.0, |value| (value - mean) / deviation))
+        .collect()
+}
+
+fn write_audit_files(
+    processed_data: &[Vec<f64>],
+    observed: &[Vec<bool>],
+    fields: &[&Field],
+    options: &Options,
+) -> Result<(), AnalysisError> {
+    if let Some(file_name) = &options.emit_ranks {
+        write_ranks(file_name, (processed_data, observed), fields)?;
+    }
+    if let Some([pair, file_name]) = options.dump_pair.as_deref() {
+        let Some((var1, var2)) = pair.rsplit_once(':') else {
+            return Err(AnalysisError::Usage(format!(
+                "--dump-pair expects `VAR1:VAR2`, got `{pair}`."
+            )));
+        };
+        let (x, y) = (field_index(fields, var1)?, field_index(fields, var2)?);
+        let (x_values, y_values): (Vec<f64>, Vec<f64>) = processed_data[x]
+            .iter()
+            .zip(&processed_data[y])
+            .zip(observed[x].iter().zip(&observed[y]))
+            .filter(|(_, (&x_observed, &y_observed))| x_observed && y_observed)
+            .map(|(values, _)| values)
+            .unzip();
+        write_pair(file_name, (var1, &x_values), (var2, &y_values))?;
+    }
+    Ok(())
+}
+
+/// The mean and standard deviation of the observed values of every field, which only APA tables
+/// show.
+fn moments(
+    fields: &[&Field],
+    columns: &[Column],
+    options: &Options,
+) -> HashMap<String, (f64, f64)> {
+    if options.style != ReportStyle::Apa {
+        return HashMap::new();
+    }
+    fields
+        .iter()
+        .zip(columns)
+        .map(|(field, column)| {
+            let observed = column.iter().flatten().copied().collect::<Vec<f64>>();
+            let mean = observed.iter().sum::<f64>() / observed.len() as f64;
+            (
+                field.name.clone(),
+                (mean, standard_deviation(&observed, options.ddof)),
+            )
+        })
+        .collect()
+}
+
+/// Responses turned into missing values while cleaning, such as text in a numeric field, count as
+/// unanswered.
+fn response_rates(
+    fields: &[&Field],
+    columns: &[Column],
+    options: &Options,
+) -> Option<Vec<ResponseRate>> {
+    if !options.response_rate {
+        return None;
+    }
+    let response_rates = fields
+        .iter()
+        .zip(columns)
+        .map(|(field, column)| ResponseRate {
+            field: field.name.clone(),
+            answered: column.iter().flatten().count(),
+            total: column.len(),
+        })
+        .collect();
+    Some(response_rates)
+}
+
+/// With `--spearman-detail`, the number of distinct values of every non-nominal field and the
+/// share of its observed values that tie with another observation.
+fn tie_proportions(
+    fields: &[&Field],
+    columns: &[Column],
+    options: &Options,
+) -> Vec<(String, usize, f64)> {
+    if !options.spearman_detail {
+        return Vec::new();
+    }
+    fields
+        .iter()
+        .zip(columns)
+        .filter(|(field, _)| field.scale != Scale::Nominal)
+        .map(|(field, column)| {
+            let mut observed = column.iter().flatten().copied().collect::<Vec<f64>>();
+            observed.sort_by(f64::total_cmp);
+            let tied = (0..observed.len())
+                .filter(|&index| {
+                    (index > 0 && observed[index] - observed[index - 1] == 0.0)
+                        || (index + 1 < observed.len()
+                            && observed[index] - observed[index + 1] == 0.0)
+                })
+                .count();
+            let mut distinct = observed.clone();
+            distinct.dedup_by(|a, b| *a - *b == 0.0);
+            (
+                field.name.clone(),
+                distinct.len(),
+                tied as f64 / observed.len() as f64,
+            )
+        })
+        .collect()
+}
+
+/// The ICC of the `--icc` rater columns, which must be analyzed fields.
+fn intraclass_correlation(
+    fields: &[&Field],
+    columns: &[Column],
+    options: &Options,
+) -> Result<Option<Result<Icc, &'static str>>, AnalysisError> {
+    if options.icc.is_empty() {
+        return Ok(None);
+    }
+    let raters = options
+        .icc
+        .iter()
+        .map(|name| field_index(fields, name).map(|index| columns[index].clone()))
+        .collect::<Result<Vec<Column>, AnalysisError>>()?;
+    Ok(Some(icc(&raters)))
+}
+
+/// The position of `name` among the analyzed fields, for the options that name fields.
+fn field_index(fields: &[&Field], name: &str) -> Result<usize, AnalysisError> {
+    fields
+        .iter()
+        .position(|field| field.name == name)
+        .ok_or_else(|| AnalysisError::Usage(format!("{name} is not an analyzed field.")))
+}
+
+/// Fits `--regression Y ~ X1,X2` to the cleaned values of the named fields, which must all be
+/// analyzed, and therefore numeric, fields.
+fn regression(
+    fields: &[&Field],
+    columns: &[Column],
+    options: &Options,
+) -> Result<Option<Regression>, AnalysisError> {
+    let Some(model) = options.regression.as_deref() else {
+        return Ok(None);
+    };
+    let parsed = model.split_once('~').and_then(|(dependent, predictors)| {
+        let predictors = predictors
+            .split(',')
+            .map(|name| name.trim().to_owned())
+            .collect::<Vec<String>>();
+        let dependent = dependent.trim().to_owned();
+        (!dependent.is_empty() && predictors.iter().all(|name| !name.is_empty()))
+            .then_some((dependent, predictors))
+    });
+    let Some((dependent, predictors)) = parsed else {
+        return Err(AnalysisError::Usage(format!(
+            "--regression expects `Y ~ X1,X2`, got `{model}`."
+        )));
+    };
+    let model_columns = once(&dependent)
+        .chain(&predictors)
+        .map(|name| field_index(fields, name).map(|index| columns[index].clone()))
+        .collect::<Result<Vec<Column>, AnalysisError>>()?;
+    Ok(Some(Regression {
+        fit: ols(&model_columns),
+        dependent,
+        predictors,
+    }))
+}
+
+/// Splits the cleaned values of the metric by the raw labels of the group field, ordered
+/// numerically when every label is a number. Rows missing either are left out.
+fn group_comparison(
+    dataframe: &DataFrame,
+    fields: &[&Field],
+    columns: &[Column],
+    options: &Options,
+) -> Result<Option<GroupComparison>, AnalysisError> {
+    let Some(comparison) = options.group_compare.as_deref() else {
+        return Ok(None);
+    };
+    let Some((metric, group)) = comparison
+        .split_once(" by ")
+        .map(|(metric, group)| (metric.trim(), group.trim()))
+    else {
+        return Err(AnalysisError::Usage(format!(
+            "--group-compare expects `METRIC by GROUP`, got `{comparison}`."
+        )));
+    };
+    let index = field_index(fields, metric)?;
+    let labels = dataframe
+        .column(group)
+        .map_err(|_| AnalysisError::MissingColumn(group.to_owned()))?;
+    let missing = labels.is_null();
+    let mut samples = BTreeMap::<String, Vec<f64>>::new();
+    for (row, value) in columns[index].iter().enumerate() {
+        if let (Some(value), false) = (value, missing.get(row) == Some(true)) {
+            let label = labels.str_value(row).unwrap().into_owned();
+            samples.entry(label).or_default().push(*value);
+        }
+    }
+    let mut samples = samples.into_iter().collect::<Vec<(String, Vec<f64>)>>();
+    if samples
+        .iter()
+        .all(|(label, _)| label.parse::<f64>().is_ok())
+    {
+        samples.sort_by(|(a, _), (b, _)| {
+            a.parse::<f64>()
+                .unwrap()
+                .total_cmp(&b.parse::<f64>().unwrap())
+        });
+    }
+    let groups = samples
+        .iter()
+        .map(|(label, values)| {
+            let mean = values.iter().sum::<f64>() / values.len() as f64;
+            let deviation = standard_deviation(values, 1);
+            (label.clone(), values.len(), mean, deviation)
+        })
+        .collect();
+    let samples = samples
+        .into_iter()
+        .map(|(_, values)| values)
+        .collect::<Vec<Vec<f64>>>();
+    Ok(Some(GroupComparison {
+        metric: metric.to_owned(),
+        group: group.to_owned(),
+        groups,
+        test: group_test(&samples, options),
+    }))
+}
+
+/// scipy's `ttest_ind` or `f_oneway` and `levene`; offline, the same statistics natively.
+fn group_test(samples: &[Vec<f64>], options: &Options) -> Result<GroupTest, String> {
+    if samples.len() < 2 {
+        return Err(String::from("有效資料的組別少於 2 組"));
+    }
+    if samples.iter().any(|values| values.len() < 2) {
+        return Err(String::from("每組至少需要 2 筆資料"));
+    }
+    let anova = one_way_anova(samples)?;
+    let two_groups = samples.len() == 2;
+    let native = || {
+        let mut statistic = anova.f;
+        if two_groups {
+            let mean = |values: &[f64]| values.iter().sum::<f64>() / values.len() as f64;
+            statistic = statistic
+                .sqrt()
+                .copysign(mean(&samples[0]) - mean(&samples[1]));
+        }
+        let levene = levene(samples)
+            .ok()
+            .map(|levene| (levene.f, levene.p_value));
+        (statistic, anova.p_value, levene)
+    };
+    let (statistic, p_value, levene) = if options.offline {
+        native()
+    } else {
+        let retries = options.python_retries;
+        let (statistic, p_value) = if two_groups {
+            with_retries(retries, "ttest_ind", || python::ttest_ind(samples))
+        } else {
+            with_retries(retries, "f_oneway", || python::f_oneway(samples))
+        }
+        .map_err(|error| error.to_string())?;
+        let levene = with_retries(retries, "levene", || python::levene(samples)).ok();
+        (statistic, p_value, levene)
+    };
+    Ok(GroupTest {
+        statistic_name: if two_groups { "t" } else { "F" },
+        statistic,
+        degrees_of_freedom: if two_groups {
+            vec![anova.degrees_of_freedom.1]
+        } else {
+            vec![anova.degrees_of_freedom.0, anova.degrees_of_freedom.1]
+        },
+        p_value,
+        levene,
+        eta_squared: anova.eta_squared,
+        omega_squared: anova.omega_squared,
+    })
+}
+
+/// Above this many observations scipy warns that the p-value of the Shapiro-Wilk test may not be
+/// accurate.
+const SHAPIRO_MAX_N: usize = 5000;
+
+/// The Shapiro-Wilk test of every ordinal, interval and ratio field, on its observed values.
+fn normality(fields: &[&Field], columns: &[Column], options: &Options) -> Option<Vec<Normality>> {
+    options.includes(Section::Normality).then(|| {
+        fields
+            .iter()
+            .zip(columns)
+            .filter(|(field, _)| field.scale != Scale::Nominal)
+            .map(|(field, column)| {
+                let data = column.iter().flatten().copied().collect::<Vec<f64>>();
+                Normality {
+                    field: field.name.clone(),
+                    n: data.len(),
+                    test: shapiro_wilk(&data, options),
+                }
+            })
+            .collect()
+    })
+}
+
+fn shapiro_wilk(data: &[f64], options: &Options) -> Result<(f64, f64), String> {
+    if data.len() > SHAPIRO_MAX_N {
+        Err(format!(
+            "樣本超過 {SHAPIRO_MAX_N} 筆，檢定結果不可靠，已略過"
+        ))
+    } else if data.len() < 3 {
+        Err(String::from("有效樣本少於 3 筆"))
+    } else if !has_variance(data) {
+        Err(String::from("常數欄位"))
+    } else if options.offline {
+        Err(String::from("離線模式不執行"))
+    } else {
+        with_retries(options.python_retries, "shapiro", || shapiro(data))
+            .map_err(|error| error.to_string())
+    }
+}
+
+/// The paired-samples t-test of `--paired-ttest PRE:POST` over the rows answering both fields,
+/// in the fields' original units. scipy computes t and p; offline they come from statrs.
+fn paired_t_test(
+    fields: &[&Field],
+    columns: &[Column],
+    options: &Options,
+) -> Result<Option<Result<PairedTTest, String>>, AnalysisError> {
+    let Some(pair) = options.paired_ttest.as_deref() else {
+        return Ok(None);
+    };
+    let Some((pre, post)) = pair.rsplit_once(':') else {
+        return Err(AnalysisError::Usage(format!(
+            "--paired-ttest expects `PRE:POST`, got `{pair}`."
+        )));
+    };
+    let (pre_index, post_index) = (field_index(fields, pre)?, field_index(fields, post)?);
+    let (before, after): (Vec<f64>, Vec<f64>) = columns[pre_index]
+        .iter()
+        .zip(&columns[post_index])
+        .filter_map(|(&before, &after)| Some((before?, after?)))
+        .unzip();
+    let pairs = before.len();
+    if pairs < 2 {
+        return Ok(Some(Err(String::from("兩欄皆有回答的資料少於 2 筆"))));
+    }
+    let differences = after
+        .iter()
+        .zip(&before)
+        .map(|(after, before)| after - before)
+        .collect::<Vec<f64>>();
+    let n = pairs as f64;
+    let mean_difference = differences.iter().sum::<f64>() / n;
+    let standard_error = standard_deviation(&differences, 1) / n.sqrt();
+    if standard_error == 0.0 {
+        return Ok(Some(Err(String::from("差異沒有變異，無法檢定"))));
+    }
+    let degrees_of_freedom = n - 1.0;
+    let distribution = StudentsT::new(0.0, 1.0, degrees_of_freedom).unwrap();
+    // POST goes first so that t has the sign of the mean change reported here.
+    let (t, p_value) = if options.offline {
+        let t = mean_difference / standard_error;
+        (t, 2.0 * (1.0 - distribution.cdf(t.abs())))
+    } else {
+        match with_retries(options.python_retries, "ttest_rel", || {
+            python::paired_t_test(&after, &before)
+        }) {
+            Ok(result) => result,
+            Err(error) => return Ok(Some(Err(error.to_string()))),
+        }
+    };
+    let margin = distribution.inverse_cdf(0.975) * standard_error;
+    Ok(Some(Ok(PairedTTest {
+        pre: pre.to_owned(),
+        post: post.to_owned(),
+        pairs,
+        t,
+        degrees_of_freedom,
+        p_value,
+        mean_difference,
+        interval: (mean_difference - margin, mean_difference + margin),
+    })))
+}
+
+/// Each item's correlation with the total of the other items in its group.
+fn corrected_item_total(items: &[&[f64]]) -> Vec<f64> {
+    let totals = (0..items[0].len())
+        .map(|row| items.iter().map(|item| item[row]).sum())
+        .collect::<Vec<f64>>();
+    items
+        .iter()
+        .map(|item| {
+            let rest = totals
+                .iter()
+                .zip(*item)
+                .map(|(total, value)| total - value)
+                .collect::<Vec<f64>>();
+            pearson_r(item, &rest)
+        })
+        .collect()
+}
+
+fn pearson_r(x: &[f64], y: &[f64]) -> f64 {
+    covariance(x, y) / (variance(x) * variance(y)).sqrt()
+}
+
+fn covariance(x: &[f64], y: &[f64]) -> f64 {
+    covariance_with_ddof(x, y, 1)
+}
+
+/// Divides the sum of cross products by `n - ddof`; Pearson's r is the same either way.
+fn covariance_with_ddof(x: &[f64], y: &[f64], ddof: u8) -> f64 {
+    let n = x.len() as f64;
+    let (x_mean, y_mean) = (x.iter().sum::<f64>() / n, y.iter().sum::<f64>() / n);
+    x.iter()
+        .zip(y)
+        .map(|(x, y)| (x - x_mean) * (y - y_mean))
+        .sum::<f64>()
+        / (n - f64::from(ddof))
+}
+
+fn standard_deviation(data: &[f64], ddof: u8) -> f64 {
+    covariance_with_ddof(data, data, ddof).sqrt()
+}
+
+fn cronbach_alpha(items: &[&[f64]]) -> f64 {
+    let k = items.len() as f64;
+    let totals = (0..items[0].len())
+        .map(|row| items.iter().map(|item| item[row]).sum())
+        .collect::<Vec<f64>>();
+    let item_variance = items.iter().map(|item| variance(item)).sum::<f64>();
+    k / (k - 1.0) * (1.0 - item_variance / variance(&totals))
+}
+
+fn variance(data: &[f64]) -> f64 {
+    let n = data.len() as f64;
+    let mean = data.iter().sum::<f64>() / n;
+    data.iter().map(|value| (value - mean).powi(2)).sum::<f64>() / (n - 1.0)
+}
+
+fn with_tied_pairs(mut value: CorrelationValue, x: &[f64], y: &[f64]) -> CorrelationValue {
+    if let CorrelationValue::Valid(result) = &mut value {
+        let mut tied_pairs = 0;
+        for i in 0..x.len() {
+            for j in (i + 1)..x.len() {
+                if x[i] - x[j] == 0.0 || y[i] - y[j] == 0.0 {
+                    tied_pairs += 1;
+                }
+            }
+        }
+        result.tied_pairs = Some(tied_pairs);
+    }
+    value
+}
+
+/// Counts are unweighted, and the native tau uses the same variant as the cell so it can be
+/// checked against scipy's.
+fn pair_counts(x: &[f64], y: &[f64], variant: KendallVariant) -> PairCounts {
+    let mut counts = PairCounts {
+        concordant: 0,
+        discordant: 0,
+        x_ties: 0,
+        y_ties: 0,
+        joint_ties: 0,
+        tau: weighted_kendall(x, y, &vec![1.0; x.len()], variant).0,
+    };
+    for i in 0..x.len() {
+        for j in (i + 1)..x.len() {
+            let (x_difference, y_difference) = (x[i] - x[j], y[i] - y[j]);
+            match (x_difference == 0.0, y_difference == 0.0) {
+                (true, true) => counts.joint_ties += 1,
+                (true, false) => counts.x_ties += 1,
+                (false, true) => counts.y_ties += 1,
+                (false, false) if x_difference.signum() == y_difference.signum() => {
+                    counts.concordant += 1;
+                }
+                (false, false) => counts.discordant += 1,
+            }
+        }
+    }
+    counts
+}
+
+fn compute_coefficient(
+    coefficient: Coefficient,
+    x: &[f64],
+    y: &[f64],
+    weights: Option<&[f64]>,
+    options: &Options,
+) -> Result<(f64, f64), PythonError> {
+    let retries = options.python_retries;
+    match (coefficient, weights) {
+        (Coefficient::Pearson | Coefficient::PointBiserial, Some(weights)) => {
+            Ok(weighted_pearson(x, y, weights))
+        }
+        (Coefficient::PointBiserial, None) => {
+            let r = pearson_r(x, y);
+            Ok((r, pearson_p_value(r, x.len())))
+        }
+        (Coefficient::Pearson, _) if is_native(coefficient, options) => {
+            let r = pearson_r(x, y);
+            Ok((r, pearson_p_value(r, x.len())))
+        }
+        (Coefficient::Spearman, _) if is_native(coefficient, options) => {
+            let r = pearson_r(&average_ranks(x), &average_ranks(y));
+            Ok((r, pearson_p_value(r, x.len())))
+        }
+        (Coefficient::Pearson, _) => with_retries(retries, "pearsonr", || pearson(x, y)),
+        (Coefficient::Spearman, _) => with_retries(retries, "spearmanr", || spearman(x, y)),
+        (Coefficient::Gamma, _) => Ok(gamma_test(x, y)),
+        (Coefficient::Biserial, _) => biserial_test(x, y).ok_or_else(|| PythonError {
+            computation: String::from("biserial"),
+            message: String::from("exactly one of the two fields must be dichotomous"),
+        }),
+        (Coefficient::Kendall, Some(weights)) => {
+            Ok(weighted_kendall(x, y, weights, options.kendall_variant))
+        }
+        (Coefficient::Kendall, None) => with_retries(retries, "kendalltau", || {
+            kendall(x, y, options.kendall_variant.name())
+        }),
+        (Coefficient::ChiSquare, _) => with_retries(retries, "chi2_contingency", || {
+            chi2_contingency(contingency_table(x, y))
+        })
+        .map(|(statistic, p_value)| (cramers_v(statistic, x, y), p_value)),
+    }
+}
+
+/// Two-sided p-value of `r = r0` from the Fisher transformation, whose standard error is
+/// `1 / sqrt(n - 3)`.
+fn fisher_z_test(r: f64, n: usize, r0: f64) -> f64 {
+    let z = (r.atanh() - r0.atanh()) * (n as f64 - 3.0).sqrt();
+    2.0 * Normal::standard().sf(z.abs())
+}
+
+/// The largest |r| transformed to z, so a perfect correlation still has a finite interval.
+const MAX_FISHER_R: f64 = 1.0 - 1e-12;
+
+/// The Fisher z interval of `r`. The variance of z is `1 / (n - 3)` for Pearson, and the
+/// approximations of Bonett & Wright (2000) for Spearman and Kendall. `None` when `n` leaves no
+/// degrees of freedom for the variance.
+fn confidence_interval(
+    r: f64,
+    n: usize,
+    coefficient: Coefficient,
+    level: f64,
+) -> Option<(f64, f64)> {
+    let n = n as f64;
+    let variance = match coefficient {
+        Coefficient::Pearson => 1.0 / (n - 3.0),
+        Coefficient::Spearman => 1.06 / (n - 3.0),
+        Coefficient::Kendall => 0.437 / (n - 4.0),
+        Coefficient::Gamma
+        | Coefficient::Biserial
+        | Coefficient::PointBiserial
+        | Coefficient::ChiSquare => return None,
+    };
+    if variance <= 0.0 || variance.is_infinite() || r.is_nan() {
+        return None;
+    }
+    let z = r.clamp(-MAX_FISHER_R, MAX_FISHER_R).atanh();
+    let margin = Normal::standard().inverse_cdf(f64::midpoint(1.0, level)) * variance.sqrt();
+    Some(((z - margin).tanh(), (z + margin).tanh()))
+}
+
+/// Biserial correlation of the dichotomous field with the continuous one, assuming the
+/// dichotomy splits an underlying normal variable:
+/// `r_b = (M₁ - M₀) / s · pq / φ(z)`, where `z` cuts off the proportion `p` of the upper group.
+/// Weights are not applied. `r_b` is a rescaled point-biserial `r_pb`, so the p-value is the
+/// usual t test of `r_pb` with `n - 2` degrees of freedom. `None` unless exactly one field is
+/// dichotomous.
+fn biserial_test(x: &[f64], y: &[f64]) -> Option<(f64, f64)> {
+    let (dichotomy, continuous) = match (is_dichotomous(x), is_dichotomous(y)) {
+        (true, false) => (x, y),
+        (false, true) => (y, x),
+        _ => return None,
+    };
+    let low = dichotomy.iter().copied().fold(f64::INFINITY, f64::min);
+    let (mut upper, mut lower) = (Vec::new(), Vec::new());
+    for (group, value) in dichotomy.iter().zip(continuous) {
+        if group - low == 0.0 {
+            lower.push(*value);
+        } else {
+            upper.push(*value);
+        }
+    }
+    let n = continuous.len() as f64;
+    let mean = |data: &[f64]| data.iter().sum::<f64>() / data.len() as f64;
+    let deviation = (variance(continuous) * (n - 1.0) / n).sqrt();
+    let upper_share = upper.len() as f64 / n;
+    let split = upper_share * (1.0 - upper_share);
+    let normal = Normal::standard();
+    let ordinate = normal.pdf(normal.inverse_cdf(1.0 - upper_share));
+    let difference = (mean(&upper) - mean(&lower)) / deviation;
+    let point_biserial = difference * split.sqrt();
+    Some((
+        difference * split / ordinate,
+        pearson_p_value(point_biserial, continuous.len()),
+    ))
+}
+
+/// Two-sided p-value of a Pearson r from `t = r √((n - 2) / (1 - r²))` with `n - 2` degrees of
+/// freedom, as scipy's `pearsonr` computes it.
+fn pearson_p_value(r: f64, n: usize) -> f64 {
+    let degrees_of_freedom = n as f64 - 2.0;
+    let t = r * (degrees_of_freedom / r.mul_add(-r, 1.0)).sqrt();
+    2.0 * StudentsT::new(0.0, 1.0, degrees_of_freedom)
+        .unwrap()
+        .sf(t.abs())
+}
+
+/// Every Pearson r at once: the columns are z-scored in one pass, after which each r is the
+/// cross product of two z-score columns divided by `n - 1`.
+fn pearson_matrix(columns: &[&[f64]]) -> Vec<Vec<f64>> {
+    let standardized = columns
+        .par_iter()
+        .map(|column| {
+            let mean = column.iter().sum::<f64>() / column.len() as f64;
+            let deviation = variance(column).sqrt();
+            column
+                .iter()
+                .map(|value| (value - mean) / deviation)
+                .collect::<Vec<f64>>()
+        })
+        .collect::<Vec<_>>();
+    standardized
+        .par_iter()
+        .map(|x| {
+            standardized
+                .iter()
+                .map(|y| x.iter().zip(y).map(|(x, y)| x * y).sum::<f64>() / (x.len() as f64 - 1.0))
+                .collect()
+        })
+        .collect()
+}
+
+/// Ranks from 1, with tied values sharing the mean of their ranks as scipy's `rankdata` does.
+fn average_ranks(data: &[f64]) -> Vec<f64> {
+    let mut order = (0..data.len()).collect::<Vec<usize>>();
+    order.sort_by(|&a, &b| data[a].total_cmp(&data[b]));
+    let mut ranks = vec![0.0; data.len()];
+    let mut start = 0;
+    while start < order.len() {
+        let end = (start..order.len())
+            .find(|&index| data[order[index]] - data[order[start]] != 0.0)
+            .unwrap_or(order.len());
+        let rank = (start + end + 1) as f64 / 2.0;
+        for &index in &order[start..end] {
+            ranks[index] = rank;
+        }
+        start = end;
+    }
+    ranks
+}
+
+/// The values of `data` that were observed rather than filled in.
+fn observed_values(data: &[f64], observed: &[bool]) -> Vec<f64> {
+    data.iter()
+        .zip(observed)
+        .filter_map(|(value, &observed)| observed.then_some(*value))
+        .collect()
+}
+
+/// Listwise deletion: the values of the rows observed in every column.
+fn complete_cases(columns: &[ObservedColumn]) -> Vec<Vec<f64>> {
+    let rows = columns.first().map_or(0, |(data, _)| data.len());
+    let complete = (0..rows)
+        .filter(|&row| columns.iter().all(|(_, observed)| observed[row]))
+        .collect::<Vec<usize>>();
+    columns
+        .iter()
+        .map(|(data, _)| complete.iter().map(|&row| data[row]).collect())
+        .collect()
+}
+
+fn has_variance(data: &[f64]) -> bool {
+    data.windows(2).any(|pair| pair[0] - pair[1] != 0.0)
+}
+
+fn is_dichotomous(data: &[f64]) -> bool {
+    levels(data) == 2
+}
+
+fn levels(data: &[f64]) -> usize {
+    let mut levels = data.to_vec();
+    levels.sort_by(f64::total_cmp);
+    levels.dedup();
+    levels.len()
+}
+
+/// The crosstab of `x` by `y`, with a row for each level of `x` and a column for each level of
+/// `y`, in order of value.
+fn contingency_table(x: &[f64], y: &[f64]) -> Vec<Vec<f64>> {
+    let sorted_levels = |data: &[f64]| {
+        let mut levels = data.to_vec();
+        levels.sort_by(f64::total_cmp);
+        levels.dedup();
+        levels
+    };
+    let (rows, columns) = (sorted_levels(x), sorted_levels(y));
+    let index = |levels: &[f64], value: &f64| {
+        levels
+            .binary_search_by(|level| level.total_cmp(value))
+            .unwrap()
+    };
+    let mut counts = vec![vec![0.0; columns.len()]; rows.len()];
+    for (x, y) in x.iter().zip(y) {
+        counts[index(&rows, x)][index(&columns, y)] += 1.0;
+    }
+    counts
+}
+
+/// Cramér's V, `sqrt(χ² / (n · (min(r, c) − 1)))`, for an `r`×`c` crosstab of `x` by `y`.
+fn cramers_v(statistic: f64, x: &[f64], y: &[f64]) -> f64 {
+    let smaller = levels(x).min(levels(y)) - 1;
+    (statistic / (x.len() as f64 * smaller as f64)).sqrt()
+}
+
+/// The chi-square statistic and degrees of freedom `(r − 1)(c − 1)` that give Cramér's V `v`.
+fn chi_square_from_v(v: f64, x: &[f64], y: &[f64]) -> (f64, usize) {
+    let (rows, columns) = (levels(x), levels(y));
+    let statistic = v.powi(2) * x.len() as f64 * (rows.min(columns) - 1) as f64;
+    (statistic, (rows - 1) * (columns - 1))
+}
+
+/// The 2×2 cell counts `[[a, b], [c, d]]`, with rows and columns ordered by value.
+fn two_by_two(x: &[f64], y: &[f64]) -> [[f64; 2]; 2] {
+    let low = |data: &[f64]| data.iter().copied().fold(f64::INFINITY, f64::min);
+    let (x_low, y_low) = (low(x), low(y));
+    let mut counts = [[0.0; 2]; 2];
+    for (x, y) in x.iter().zip(y) {
+        counts[usize::from(x - x_low != 0.0)][usize::from(y - y_low != 0.0)] += 1.0;
+    }
+    counts
+}
+
+/// The products of the diagonal and off-diagonal cells, `ad` and `bc`.
+fn cross_products(counts: [[f64; 2]; 2]) -> (f64, f64) {
+    (counts[0][0] * counts[1][1], counts[0][1] * counts[1][0])
+}
+
+/// Chi-square style approximations are unreliable once an expected count drops below 5.
+fn has_small_expected_count(counts: [[f64; 2]; 2]) -> bool {
+    let total = counts.iter().flatten().sum::<f64>();
+    (0..2).any(|row| {
+        (0..2).any(|column| {
+            (counts[row][0] + counts[row][1]) * (counts[0][column] + counts[1][column]) / total
+                < 5.0
+        })
+    })
+}
+
+/// Yule's Q, `(ad - bc) / (ad + bc)`. An empty cell makes Q exactly ±1.
+fn yules_q(x: &[f64], y: &[f64]) -> f64 {
+    let (concordant, discordant) = cross_products(two_by_two(x, y));
+    if concordant == 0.0 {
+        -1.0
+    } else if discordant == 0.0 {
+        1.0
+    } else {
+        (concordant - discordant) / (concordant + discordant)
+    }
+}
+
+/// Weights are not applied to Yule's Q. The p-value uses the normal approximation with the
+/// standard error `(1 - Q²) / 2 · √(1/a + 1/b + 1/c + 1/d)`, adding 0.5 to every cell when one
+/// is empty so the standard error stays finite.
+fn yules_q_test(x: &[f64], y: &[f64]) -> (f64, f64) {
+    let counts = two_by_two(x, y);
+    let correction = if counts.iter().flatten().any(|&count| count == 0.0) {
+        0.5
+    } else {
+        0.0
+    };
+    let counts = counts.map(|row| row.map(|count| count + correction));
+    let (concordant, discordant) = cross_products(counts);
+    let corrected = (concordant - discordant) / (concordant + discordant);
+    let standard_error = corrected.mul_add(-corrected, 1.0) / 2.0
+        * counts
+            .iter()
+            .flatten()
+            .map(|count| 1.0 / count)
+            .sum::<f64>()
+            .sqrt();
+    let p_value = 2.0 * (1.0 - Normal::standard().cdf((corrected / standard_error).abs()));
+    (yules_q(x, y), p_value)
+}
+
+fn concordance(x: &[f64], y: &[f64]) -> (f64, f64) {
+    let (mut concordant, mut discordant) = (0.0, 0.0);
+    for i in 0..x.len() {
+        for j in (i + 1)..x.len() {
+            let (x_difference, y_difference) = (x[i] - x[j], y[i] - y[j]);
+            if x_difference == 0.0 || y_difference == 0.0 {
+                continue;
+            }
+            if x_difference.signum() == y_difference.signum() {
+                concordant += 1.0;
+            } else {
+                discordant += 1.0;
+            }
+        }
+    }
+    (concordant, discordant)
+}
+
+/// Goodman and Kruskal's gamma, `(C - D) / (C + D)` over the concordant and discordant pairs;
+/// pairs tied on either variable are ignored.
+fn gamma(x: &[f64], y: &[f64]) -> f64 {
+    let (concordant, discordant) = concordance(x, y);
+    (concordant - discordant) / (concordant + discordant)
+}
+
+/// Weights are not applied to gamma. The p-value uses the normal approximation
+/// `z = G √((C + D) / (n (1 - G²)))`.
+fn gamma_test(x: &[f64], y: &[f64]) -> (f64, f64) {
+    let gamma = gamma(x, y);
+    let (concordant, discordant) = concordance(x, y);
+    let z =
+        gamma * ((concordant + discordant) / (x.len() as f64 * gamma.mul_add(-gamma, 1.0))).sqrt();
+    let p_value = 2.0 * (1.0 - Normal::standard().cdf(z.abs()));
+    (gamma, p_value)
+}
+
+/// Weighted Pearson r: the weighted covariance over the product of the weighted standard
+/// deviations. The p-value uses Kish's effective sample size `(Σw)² / Σw²` in place of n.
+fn weighted_pearson(x: &[f64], y: &[f64], weights: &[f64]) -> (f64, f64) {
+    let total = weights.iter().sum::<f64>();
+    let centered = |data: &[f64]| {
+        let mean = data.iter().zip(weights).map(|(a, w)| a * w).sum::<f64>() / total;
+        data.iter().map(|value| value - mean).collect::<Vec<f64>>()
+    };
+    let (x, y) = (centered(x), centered(y));
+    let moment = |a: &[f64], b: &[f64]| {
+        a.iter()
+            .zip(b)
+            .zip(weights)
+            .map(|((a, b), weight)| weight * a * b)
+            .sum::<f64>()
+    };
+    let r = moment(&x, &y) / (moment(&x, &x) * moment(&y, &y)).sqrt();
+    let effective_size = total.powi(2) / weights.iter().map(|weight| weight * weight).sum::<f64>();
+    let degrees_of_freedom = effective_size - 2.0;
+    let t = r * (degrees_of_freedom / r.mul_add(-r, 1.0)).sqrt();
+    let p_value = StudentsT::new(0.0, 1.0, degrees_of_freedom)
+        .map_or(f64::NAN, |distribution| 2.0 * distribution.sf(t.abs()));
+    (r, p_value)
+}
+
+/// Weighted tau-b or tau-c: every pair of respondents counts with the product of their weights.
+/// scipy has no equivalent, so the p-value is a normal approximation that substitutes
+/// Kish's effective sample size `(Σw)² / Σw²` for n in the usual variance of tau.
+fn weighted_kendall(x: &[f64], y: &[f64], weights: &[f64], variant: KendallVariant) -> (f64, f64) {
+    let (mut concordant, mut discordant, mut x_ties, mut y_ties) = (0.0, 0.0, 0.0, 0.0);
+    for i in 0..x.len() {
+        for j in (i + 1)..x.len() {
+            let weight = weights[i] * weights[j];
+            let (x_difference, y_difference) = (x[i] - x[j], y[i] - y[j]);
+            if x_difference == 0.0 && y_difference == 0.0 {
+                continue;
+            }
+            if x_difference == 0.0 {
+                x_ties += weight;
+            } else if y_difference == 0.0 {
+                y_ties += weight;
+            } else if x_difference.signum() == y_difference.signum() {
+                concordant += weight;
+            } else {
+                discordant += weight;
+            }
+        }
+    }
+    let tau = match variant {
+        KendallVariant::B => {
+            (concordant - discordant)
+                / ((concordant + discordant + x_ties) * (concordant + discordant + y_ties)).sqrt()
+        }
+        KendallVariant::C => {
+            let distinct = |data: &[f64]| {
+                let mut data = data.to_vec();
+                data.sort_by(f64::total_cmp);
+                data.dedup();
+                data.len() as f64
+            };
+            let m = distinct(x).min(distinct(y));
+            2.0 * m * (concordant - discordant) / (weights.iter().sum::<f64>().powi(2) * (m - 1.0))
+        }
+    };
+    let n = weights.iter().sum::<f64>().powi(2)
+        / weights.iter().map(|weight| weight * weight).sum::<f64>();
+    let z = 3.0 * tau * (n * (n - 1.0)).sqrt() / (2.0 * 2.0f64.mul_add(n, 5.0)).sqrt();
+    let p_value = 2.0 * (1.0 - Normal::standard().cdf(z.abs()));
+    (tau, p_value)
+}
+
+/// The square root of the tie-corrected variance of Kendall's S (the one scipy's asymptotic
+/// p-value uses), scaled by the same denominator as tau. Without ties and for tau-b this is
+/// `√(2(2n + 5) / (9n(n − 1)))`.
+fn kendall_standard_error(x: &[f64], y: &[f64], variant: KendallVariant) -> f64 {
+    let tie_sums = |data: &[f64]| {
+        let mut data = data.to_vec();
+        data.sort_by(f64::total_cmp);
+        data.chunk_by(|a, b| a - b == 0.0)
+            .map(|group| group.len() as f64)
+            .fold((0.0, 0.0, 0.0), |(pairs, triples, variance), t| {
+                (
+                    t.mul_add(t - 1.0, pairs),
+                    (t * (t - 1.0)).mul_add(t - 2.0, triples),
+                    (t * (t - 1.0)).mul_add(2.0f64.mul_add(t, 5.0), variance),
+                )
+            })
+    };
+    let n = x.len() as f64;
+    let (x_pairs, x_triples, x_variance) = tie_sums(x);
+    let (y_pairs, y_triples, y_variance) = tie_sums(y);
+    let variance = (n * (n - 1.0)).mul_add(2.0f64.mul_add(n, 5.0), -x_variance - y_variance) / 18.0
+        + x_triples * y_triples / (9.0 * n * (n - 1.0) * (n - 2.0))
+        + x_pairs * y_pairs / (2.0 * n * (n - 1.0));
+    let denominator = match variant {
+        KendallVariant::B => {
+            let pairs = n * (n - 1.0) / 2.0;
+            ((pairs - x_pairs / 2.0) * (pairs - y_pairs / 2.0)).sqrt()
+        }
+        KendallVariant::C => {
+            let m = levels(x).min(levels(y)) as f64;
+            n * n * (m - 1.0) / (2.0 * m)
+        }
+    };
+    variance.sqrt() / denominator
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use proptest::{collection::vec, prelude::*};
+
+    fn likert_pairs() -> impl Strategy<Value = (Vec<f64>, Vec<f64>, Vec<f64>)> {
+        (3_usize..40).prop_flat_map(|n| {
+            (
+                vec((1_u8..=7).prop_map(f64::from), n),
+                vec((1_u8..=7).prop_map(f64::from), n),
+                vec(0.5..3.0, n),
+            )
+        })
+    }
+
+    fn assert_bounded((r, p_value): (f64, f64)) -> Result<(), TestCaseError> {
+        prop_assert!((-1.0..=1.0).contains(&r), "r = {r}");
+        prop_assert!((0.0..=1.0).contains(&p_value), "p = {p_value}");
+        Ok(())
+    }
+
+    fn assert_symmetric(forward: (f64, f64), backward: (f64, f64)) -> Result<(), TestCaseError> {
+        prop_assert!((forward.0 - backward.0).abs() < 1e-12);
+        prop_assert!((forward.1 - backward.1).abs() < 1e-12);
+        Ok(())
+    }
+
+    proptest! {
+        #[test]
+        fn gamma_is_bounded_and_symmetric((x, y, _) in likert_pairs()) {
+            let (concordant, discordant) = concordance(&x, &y);
+            prop_assume!(concordant + discordant > 0.0);
+            assert_bounded(gamma_test(&x, &y))?;
+            assert_symmetric(gamma_test(&x, &y), gamma_test(&y, &x))?;
+        }
+
+        #[test]
+        fn gamma_of_a_variable_with_itself_is_one((x, _, _) in likert_pairs()) {
+            prop_assume!(!x.iter().all(|value| value - x[0] == 0.0));
+            prop_assert!((gamma(&x, &x) - 1.0).abs() < 1e-12);
+        }
+
+        #[test]
+        fn weighted_kendall_is_bounded_and_symmetric((x, y, weights) in likert_pairs()) {
+            prop_assume!(!x.iter().all(|value| value - x[0] == 0.0));
+            prop_assume!(!y.iter().all(|value| value - y[0] == 0.0));
+            for variant in [KendallVariant::B, KendallVariant::C] {
+                let forward = weighted_kendall(&x, &y, &weights, variant);
+                assert_bounded(forward)?;
+                assert_symmetric(forward, weighted_kendall(&y, &x, &weights, variant))?;
+            }
+            let (tau, _) = weighted_kendall(&x, &x, &weights, KendallVariant::B);
+            prop_assert!((tau - 1.0).abs() < 1e-12);
+        }
+
+        #[test]
+        fn yules_q_is_bounded_and_symmetric(
+            (x, y) in (4_usize..40).prop_flat_map(|n| {
+                (vec((0_u8..=1).prop_map(f64::from), n), vec((0_u8..=1).prop_map(f64::from), n))
+            })
+        ) {
+            prop_assume!(is_dichotomous(&x) && is_dichotomous(&y));
+            assert_bounded(yules_q_test(&x, &y))?;
+            assert_symmetric(yules_q_test(&x, &y), yules_q_test(&y, &x))?;
+            prop_assert!((yules_q(&x, &x) - 1.0).abs() < 1e-12);
+        }
+
+        #[test]
+        fn pearson_matrix_matches_pairwise_pearson((x, y, z) in likert_pairs()) {
+            for column in [&x, &y, &z] {
+                prop_assume!(!column.iter().all(|value| value - column[0] == 0.0));
+            }
+            let columns = [x.as_slice(), y.as_slice(), z.as_slice()];
+            let matrix = pearson_matrix(&columns);
+            for (row, a) in columns.iter().enumerate() {
+                for (column, b) in columns.iter().enumerate() {
+                    prop_assert!((matrix[row][column] - pearson_r(a, b)).abs() < 1e-9);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn eigenvalues_of_a_symmetric_matrix() {
+        let eigenvalues = symmetric_eigenvalues(&vec![
+            vec![2.0, 1.0, 0.0],
+            vec![1.0, 2.0, 0.0],
+            vec![0.0, 0.0, 5.0],
+        ]);
+        for (eigenvalue, expected) in eigenvalues.into_iter().zip([5.0, 3.0, 1.0]) {
+            assert!((eigenvalue - expected).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn kendall_standard_error_without_ties() {
+        let x = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+        let y = [2.0, 1.0, 4.0, 3.0, 6.0, 5.0];
+        let n = 6.0_f64;
+        let expected = (2.0 * 2.0f64.mul_add(n, 5.0) / (9.0 * n * (n - 1.0))).sqrt();
+        assert!((kendall_standard_error(&x, &y, KendallVariant::B) - expected).abs() < 1e-12);
+    }
+
+    #[test]
+    fn icc_matches_shrout_and_fleiss() {
+        // Shrout & Fleiss (1979), six targets rated by four judges.
+        let ratings = [
+            [9.0, 2.0, 5.0, 8.0],
+            [6.0, 1.0, 3.0, 2.0],
+            [8.0, 4.0, 6.0, 8.0],
+            [7.0, 1.0, 2.0, 6.0],
+            [10.0, 5.0, 6.0, 9.0],
+            [6.0, 2.0, 4.0, 7.0],
+        ];
+        let columns = (0..4)
+            .map(|judge| ratings.iter().map(|row| Some(row[judge])).collect())
+            .collect::<Vec<Column>>();
+        let Ok(result) = icc(&columns) else {
+            panic!("ICC failed")
+        };
+        assert!((result.single.value - 0.290).abs() < 1e-3);
+        assert!((result.average.value - 0.620).abs() < 1e-3);
+        assert!((result.single.lower - 0.019).abs() < 1e-3);
+        assert!((result.single.upper - 0.761).abs() < 1e-3);
+    }
+
+    #[test]
+    fn missing_values_are_deleted_pairwise() {
+        let Command::Analyze(options) = Cli::parse_from([
+            "tool",
+            "analyze",
+            "data.csv",
+            "fields.json",
+            "--native-pearson",
+        ])
+        .command
+        else {
+            unreachable!()
+        };
+        // Filling the missing x with 0 would pull r below 1.
+        let x = vec![1.0, 2.0, 0.0, 4.0, 5.0];
+        let y = vec![2.0, 4.0, 6.0, 8.0, 10.0];
+        let value = pairwise_cell_value(
+            (&x, &[true, true, false, true, true]),
+            (&y, &[true; 5]),
+            Coefficient::Pearson,
+            &options,
+            None,
+            |_, _| unreachable!(),
+        );
+        let CorrelationValue::Valid(result) = value else {
+            panic!("expected a valid correlation");
+        };
+        assert_eq!((result.n, result.missing_rows), (4, 1));
+        assert!((result.r - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn reliability_deletes_missing_values_listwise() {
+        let Command::Analyze(options) =
+            Cli::parse_from(["tool", "analyze", "data.csv", "fields.json", "--offline"]).command
+        else {
+            unreachable!()
+        };
+        let fields: Vec<Field> = from_str(
+            r#"[{"name": "a", "scale": "Interval", "reliability_group": "g"},
+                {"name": "b", "scale": "Interval", "reliability_group": "g"}]"#,
+        )
+        .unwrap();
+        let fields = fields.iter().collect::<Vec<&Field>>();
+        // A filled-in 0 for the missing b would make it disagree with a.
+        let data = vec![vec![1.0, 2.0, 3.0, 4.0, 5.0], vec![1.0, 2.0, 0.0, 4.0, 5.0]];
+        let observed = vec![vec![true; 5], vec![true, true, false, true, true]];
+        let reliability = reliability((&data, &observed), &fields, &options);
+        let complete = [1.0, 2.0, 4.0, 5.0];
+        assert!((reliability[0].alpha - cronbach_alpha(&[&complete, &complete])).abs() < 1e-12);
+        assert!((reliability[0].alpha - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn covariance_skips_missing_values_and_ignores_standardize() {
+        let fields: Vec<Field> =
+            from_str(r#"[{"name": "x", "scale": "Interval"}, {"name": "y", "scale": "Ratio"}]"#)
+                .unwrap();
+        let fields = fields.iter().collect::<Vec<&Field>>();
+        let columns = vec![
+            vec![Some(1.0), Some(2.0), None, Some(4.0)],
+            vec![Some(2.0), Some(4.0), Some(9.0), Some(8.0)],
+        ];
+        let covariance = |standardize: &[&str]| {
+            let arguments = [
+                "tool",
+                "analyze",
+                "data.csv",
+                "fields.json",
+                "--emit-covariance",
+            ];
+            let Command::Analyze(options) =
+                Cli::parse_from(arguments.iter().chain(standardize)).command
+            else {
+                unreachable!()
+            };
+            covariance_matrix(&columns, &fields, &options)
+                .unwrap()
+                .cells
+        };
+        let cells = covariance(&[]);
+        // x is missing in the third row, so only the other three pair up.
+        assert!(
+            (cells[0][1] - covariance_with_ddof(&[1.0, 2.0, 4.0], &[2.0, 4.0, 8.0], 1)).abs()
+                < 1e-12
+        );
+        assert!((cells[1][1] - variance(&[2.0, 4.0, 9.0, 8.0])).abs() < 1e-12);
+        assert_eq!(covariance(&["--standardize"]), cells);
+    }
+
+    #[test]
+    fn the_weaker_scale_decides_the_coefficient() {
+        let (ordinal, nominal) = (Coefficient::Spearman, Coefficient::ChiSquare);
+        let scales = [
+            Scale::Nominal,
+            Scale::Ordinal,
+            Scale::Interval,
+            Scale::Ratio,
+        ];
+        for a in scales {
+            for b in scales {
+                let expected = match (a, b) {
+                    (Scale::Nominal, _) | (_, Scale::Nominal) => nominal,
+                    (Scale::Ordinal, _) | (_, Scale::Ordinal) => ordinal,
+                    _ => Coefficient::Pearson,
+                };
+                assert_eq!(
+                    coefficient_for(a, b, ordinal, nominal),
+                    expected,
+                    "{a:?} × {b:?}"
+                );
+            }
+        }
+        assert_eq!(
+            coefficient_for(Scale::Ordinal, Scale::Interval, ordinal, nominal),
+            Coefficient::Spearman
+        );
+        assert_eq!(
+            coefficient_for(Scale::Ratio, Scale::Nominal, ordinal, nominal),
+            Coefficient::ChiSquare
+        );
+        let Command::Analyze(options) =
+            Cli::parse_from(["tool", "analyze", "data.csv", "fields.json"]).command
+        else {
+            unreachable!()
+        };
+        assert_eq!(nominal_coefficient(&options), Coefficient::ChiSquare);
+    }
+
+    #[test]
+    fn constant_pairs_never_reach_scipy() {
+        let Command::Analyze(options) =
+            Cli::parse_from(["tool", "analyze", "data.csv", "fields.json"]).command
+        else {
+            unreachable!()
+        };
+        // y only varies in the row that x is missing, so the kept rows are constant.
+        let x = vec![1.0, 2.0, 3.0, 0.0, 5.0];
+        let y = vec![3.0, 3.0, 3.0, 4.0, 3.0];
+        let value = pairwise_cell_value(
+            (&x, &[true, true, true, false, true]),
+            (&y, &[true; 5]),
+            Coefficient::Pearson,
+            &options,
+            None,
+            |_, _| unreachable!(),
+        );
+        assert!(matches!(value, CorrelationValue::Constant));
+        assert_eq!(value.to_string(), "常數欄位");
+    }
+
+    #[test]
+    fn big5_headers_round_trip() {
+        let Command::Validate { format, .. } = Cli::parse_from([
+            "tool",
+            "validate",
+            "big5.csv",
+            "fields.json",
+            "--encoding",
+            "big5",
+            "--delimiter",
+            ";",
+        ])
+        .command
+        else {
+            unreachable!()
+        };
+        let source = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/big5.csv");
+        let dataframe = load_dataframe(source, &format, FETCH_TIMEOUT, &[]).unwrap();
+        assert_eq!(dataframe.get_column_names(), ["滿意度", "年齡區間", "職業"]);
+        assert_eq!(dataframe.height(), 3);
+        assert_eq!(
+            dataframe.column("職業").unwrap().utf8().unwrap().get(1),
+            Some("上班族")
+        );
+    }
+
+    #[test]
+    fn typos_suggest_the_nearest_column() {
+        let columns = ["Q1 滿意度", "Q2 推薦意願", "年齡"];
+        assert_eq!(closest_name("Q1 滿意度 ", &columns), Some("Q1 滿意度"));
+        assert_eq!(closest_name("Q2 推廌意願", &columns), Some("Q2 推薦意願"));
+        assert_eq!(closest_name("性別", &columns), None);
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+    }
+
+    #[test]
+    fn file_reports_come_with_a_json_sidecar() {
+        let formats = |arguments: &[&str]| {
+            let Command::Analyze(options) = Cli::parse_from(
+                ["tool", "analyze", "data.csv", "fields.json"]
+                    .iter()
+                    .chain(arguments),
+            )
+            .command
+            else {
+                unreachable!()
+            };
+            options.report_formats()
+        };
+        assert_eq!(formats(&[]), [OutputFormat::Md, OutputFormat::Json]);
+        assert_eq!(
+            formats(&["--format", "json,md"]),
+            [OutputFormat::Json, OutputFormat::Md]
+        );
+        assert_eq!(formats(&["--output", "-"]), [OutputFormat::Md]);
+    }
+
+    #[test]
+    fn compare_reports_go_next_to_the_compare_file() {
+        let Command::Analyze(options) = Cli::parse_from([
+            "tool",
+            "analyze",
+            "data/2023.csv",
+            "fields.json",
+            "--compare",
+            "data/2024.csv",
+        ])
+        .command
+        else {
+            unreachable!()
+        };
+        let options = options.resolve();
+        assert_eq!(
+            options.report_path(OutputFormat::Json),
+            Path::new("data/2023.csv.compare.json")
+        );
+        assert_eq!(
+            options.compare_report_path("data/2024.csv", OutputFormat::Json),
+            Path::new("data/2024.csv.json")
+        );
+        assert_eq!(
+            options.matrix_path(Some("data/2024.csv"), "pearson"),
+            Path::new("data/2024.csv.pearson.csv")
+        );
+    }
+
+    #[test]
+    fn point_biserial_pairs_binary_nominal_fields_with_the_others() {
+        let Command::Analyze(options) =
+            Cli::parse_from(["tool", "analyze", "data.csv", "fields.json"]).command
+        else {
+            unreachable!()
+        };
+        let fields: Vec<Field> = from_str(
+            r#"[{"name": "gender", "scale": "Nominal"}, {"name": "region", "scale": "Nominal"},
+                {"name": "satisfaction", "scale": "Ordinal"}]"#,
+        )
+        .unwrap();
+        let fields = fields.iter().collect::<Vec<&Field>>();
+        let data = vec![
+            vec![0.0, 0.0, 1.0, 1.0, 0.0, 1.0],
+            vec![1.0, 2.0, 3.0, 1.0, 2.0, 3.0],
+            vec![2.0, 3.0, 4.0, 5.0, 1.0, 4.0],
+        ];
+        let observed = vec![vec![true; 6]; 3];
+        let block = point_biserial_block(&data, &observed, &fields, &options, None).unwrap();
+        assert_eq!(block.row_names, ["gender"]);
+        assert_eq!(block.column_names, ["satisfaction"]);
+        let CorrelationValue::Valid(result) = &block.cells[0][0].1 else {
+            panic!("expected a valid point-biserial r");
+        };
+        assert!((result.r - pearson_r(&data[0], &data[2])).abs() < 1e-12);
+    }
+
+    #[test]
+    fn weighted_pearson_counts_weights_as_repeated_rows() {
+        let (x, y) = ([1.0, 2.0, 3.0, 4.0, 5.0], [2.0, 1.0, 4.0, 3.0, 5.0]);
+        let (r, p_value) = weighted_pearson(&x, &y, &[1.0; 5]);
+        assert!((r - pearson_r(&x, &y)).abs() < 1e-12);
+        assert!((p_value - pearson_p_value(r, 5)).abs() < 1e-12);
+        let (repeated_x, repeated_y) = (
+            [1.0, 1.0, 2.0, 3.0, 4.0, 5.0],
+            [2.0, 2.0, 1.0, 4.0, 3.0, 5.0],
+        );
+        let (r, _) = weighted_pearson(&x, &y, &[2.0, 1.0, 1.0, 1.0, 1.0]);
+        assert!((r - pearson_r(&repeated_x, &repeated_y)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn dry_run_marks_missing_and_derived_fields() {
+        let dataframe = DataFrame::new(vec![Series::new("a", [Some(1_i64), None])]).unwrap();
+        let fields: Vec<Field> = from_str(
+            r#"[{"name": "a", "scale": "Interval"}, {"name": "b", "scale": "Ordinal"},
+                {"name": "s", "scale": "Ratio", "sum_of": ["a"]}]"#,
+        )
+        .unwrap();
+        let inventory = field_inventory(&dataframe, &fields);
+        let column = |name| {
+            inventory
+                .column(name)
+                .unwrap()
+                .utf8()
+                .unwrap()
+                .into_no_null_iter()
+                .map(str::to_owned)
+                .collect::<Vec<String>>()
+        };
+        assert_eq!(column("存在"), ["是", "否", "衍生（1 題加總）"]);
+        assert_eq!(column("非空值數"), ["1", "-", "-"]);
+    }
+
+    #[test]
+    fn repeated_names_are_reported_once() {
+        let names = ["性別", "年齡", "性別", "性別", "收入"];
+        assert_eq!(duplicate_names(names), ["性別"]);
+        let columns = [
+            "a",
+            "b",
+            "a_duplicated_0",
+            "a_duplicated_1",
+            "c_duplicated_0",
+        ];
+        assert_eq!(duplicated_headers(&columns), ["a"]);
+    }
+
+    #[test]
+    fn fisher_interval_of_r() {
+        let (low, high) = confidence_interval(0.5, 28, Coefficient::Pearson, 0.95).unwrap();
+        assert!((low - 0.156_03).abs() < 1e-4 && (high - 0.735_82).abs() < 1e-4);
+        let (low, high) = confidence_interval(1.0, 10, Coefficient::Pearson, 0.95).unwrap();
+        assert!(low.is_finite() && low < 1.0 && high <= 1.0);
+        assert!(confidence_interval(0.5, 3, Coefficient::Pearson, 0.95).is_none());
+        assert!(confidence_interval(0.5, 4, Coefficient::Kendall, 0.95).is_none());
+    }
+
+    #[test]
+    fn cramers_v_of_a_crosstab() {
+        let cells = [
+            (1.0, 1.0, 20),
+            (1.0, 2.0, 10),
+            (2.0, 1.0, 10),
+            (2.0, 2.0, 20),
+        ];
+        let (x, y): (Vec<f64>, Vec<f64>) = cells
+            .iter()
+            .flat_map(|&(x, y, count)| std::iter::repeat_n((x, y), count))
+            .unzip();
+        assert_eq!(
+            contingency_table(&x, &y),
+            vec![vec![20.0, 10.0], vec![10.0, 20.0]]
+        );
+        let v = cramers_v(20.0 / 3.0, &x, &y);
+        assert!((v - 1.0 / 3.0).abs() < 1e-12);
+        let (statistic, df) = chi_square_from_v(v, &x, &y);
+        assert!((statistic - 20.0 / 3.0).abs() < 1e-12);
+        assert_eq!(df, 1);
+    }
+
+    #[test]
+    fn holm_is_between_raw_and_bonferroni() {
+        let p_values = [0.01, 0.04, 0.03, 0.005];
+        let close = |actual: Vec<f64>, expected: [f64; 4]| {
+            actual
+                .iter()
+                .zip(expected)
+                .all(|(actual, expected)| (actual - expected).abs() < 1e-12)
+        };
+        assert!(close(
+            adjusted_p_values(&p_values, Correction::Bonferroni),
+            [0.04, 0.16, 0.12, 0.02]
+        ));
+        assert!(close(
+            adjusted_p_values(&p_values, Correction::Holm),
+            [0.03, 0.06, 0.06, 0.02]
+        ));
+        assert!(close(
+            adjusted_p_values(&[0.5, 0.9, 0.4, 0.3], Correction::Bonferroni),
+            [1.0; 4]
+        ));
+    }
+
+    #[test]
+    fn weights_count_as_repeated_respondents() {
+        // Weighting 1, 2, 3 by 1, 1, 2 describes the same sample as 1, 2, 3, 3.
+        let values = [Some(1.0), Some(2.0), Some(3.0), None];
+        let column = Series::new("x", values);
+        let percentiles = [0.05, 0.25, 0.5, 0.75, 0.95];
+        let statistics =
+            weighted_statistics(&column, &values, &[1.0, 1.0, 2.0, 5.0], &percentiles, 1);
+        let expected = [
+            2.25,
+            (2.75_f64 / 3.0).sqrt(),
+            1.0,
+            1.0,
+            1.0,
+            2.0,
+            3.0,
+            3.0,
+            3.0,
+        ];
+        for (statistic, expected) in statistics.into_iter().zip(expected) {
+            assert!((statistic.unwrap() - expected).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn percentiles_must_lie_strictly_between_zero_and_one() {
+        let parse = |percentiles: &str| {
+            Cli::try_parse_from([
+                "tool",
+                "analyze",
+                "data.csv",
+                "fields.json",
+                "--percentiles",
+                percentiles,
+            ])
+        };
+        let Command::Analyze(options) = parse("0.1,0.9").unwrap().command else {
+            unreachable!()
+        };
+        assert_eq!(options.percentiles, [0.1, 0.9]);
+        assert!(parse("0.25,1").is_err());
+        assert!(parse("0").is_err());
+        assert!(parse("half").is_err());
+    }
+
+    #[test]
+    fn missing_values_are_their_own_category() {
+        let gender = Series::new("gender", [Some("F"), None, Some("M"), Some("F")]);
+        let region = Series::new("region", [Some(1), Some(2), None, Some(2)]);
+        let frequency = frequency(&gender);
+        assert_eq!(
+            frequency.counts,
+            [
+                (Some(String::from("F")), 2),
+                (Some(String::from("M")), 1),
+                (None, 1)
+            ]
+        );
+        assert_eq!(frequency.total(), 4);
+        let crosstab = crosstab(&gender, &region);
+        assert_eq!(
+            crosstab.column_levels,
+            [Some(String::from("2")), Some(String::from("1")), None]
+        );
+        assert_eq!(crosstab.counts, [[1, 1, 0], [0, 0, 1], [1, 0, 0]]);
+    }
+
+    #[test]
+    fn shapiro_wilk_skips_large_samples() {
+        let Command::Analyze(options) =
+            Cli::parse_from(["tool", "analyze", "data.csv", "fields.json", "--offline"]).command
+        else {
+            unreachable!()
+        };
+        let data = (0..=SHAPIRO_MAX_N)
+            .map(|value| value as f64)
+            .collect::<Vec<f64>>();
+        assert!(shapiro_wilk(&data, &options).unwrap_err().contains("5000"));
+        assert_eq!(
+            shapiro_wilk(&data[..10], &options),
+            Err(String::from("離線模式不執行"))
+        );
+        assert_eq!(
+            shapiro_wilk(&[2.0; 10], &options),
+            Err(String::from("常數欄位"))
+        );
+    }
+
+    #[test]
+    fn gamma_counts_concordant_and_discordant_pairs() {
+        // Pairs (1,2) (1,3) (1,4) (2,4) (3,4) are concordant and (2,3) is discordant.
+        assert!((gamma(&[1.0, 2.0, 3.0, 4.0], &[1.0, 3.0, 2.0, 4.0]) - 4.0 / 6.0).abs() < 1e-12);
+        assert!((gamma(&[1.0, 2.0, 3.0], &[3.0, 2.0, 1.0]) + 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn gamma_ignores_tied_pairs() {
+        // Of the ten pairs, four are tied on x or y, four are concordant and two are discordant.
+        let x = [1.0, 1.0, 2.0, 2.0, 3.0];
+        let y = [1.0, 2.0, 1.0, 3.0, 2.0];
+        assert_eq!(concordance(&x, &y), (4.0, 2.0));
+        assert!((gamma(&x, &y) - 1.0 / 3.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn pair_counts_split_every_pair() {
+        // Of the ten pairs, two are tied on x only, two on y only, four are concordant and two are
+        // discordant.
+        let counts = pair_counts(
+            &[1.0, 1.0, 2.0, 2.0, 3.0],
+            &[1.0, 2.0, 1.0, 3.0, 2.0],
+            KendallVariant::B,
+        );
+        assert_eq!(
+            (
+                counts.concordant,
+                counts.discordant,
+                counts.x_ties,
+                counts.y_ties,
+                counts.joint_ties
+            ),
+            (4, 2, 2, 2, 0)
+        );
+        assert!((counts.tau - 0.25).abs() < 1e-12);
+    }
+
+    #[test]
+    fn biserial_rescales_point_biserial() {
+        // The groups split evenly, so r_b = r_pb · √(pq) / φ(0) with r_pb = 2 / √1.25 · 0.5.
+        let (biserial, p_value) =
+            biserial_test(&[0.0, 0.0, 1.0, 1.0], &[1.0, 2.0, 3.0, 4.0]).unwrap();
+        let point_biserial = 1.0 / 1.25_f64.sqrt();
+        let expected = point_biserial * 0.5 / Normal::standard().pdf(0.0);
+        assert!((biserial - expected).abs() < 1e-12);
+        assert!((0.0..=1.0).contains(&p_value));
+        assert_eq!(
+            biserial_test(&[1.0, 2.0, 3.0, 4.0], &[0.0, 0.0, 1.0, 1.0]),
+            Some((biserial, p_value))
+        );
+        assert!(biserial_test(&[1.0, 2.0, 3.0], &[3.0, 1.0, 2.0]).is_none());
+    }
+}
@@ -0,0 +1,44 @@
+use log::{LevelFilter, Log, Metadata, Record};
+use std::{sync::OnceLock, time::Instant};
+
+/// Writes every record to stderr with the time since logging started.
+struct StderrLogger {
+    started: OnceLock<Instant>,
+}
+
+static LOGGER: StderrLogger = StderrLogger {
+    started: OnceLock::new(),
+};
+
+impl Log for StderrLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if self.enabled(record.metadata()) {
+            let elapsed = self.started.get_or_init(Instant::now).elapsed();
+            eprintln!(
+                "[{:>8.2}s {:<5}] {}",
+                elapsed.as_secs_f64(),
+                record.level(),
+                record.args()
+            );
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Logs warnings only by default, each step with `-v`, and every field as well with `-vv`.
+pub fn init(verbose: u8) {
+    let level = match verbose {
+        0 => LevelFilter::Warn,
+        1 => LevelFilter::Info,
+        _ => LevelFilter::Debug,
+    };
+    LOGGER.started.get_or_init(Instant::now);
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(level);
+    }
+}
@@ -0,0 +1,298 @@
+use statrs::distribution::{ChiSquared, ContinuousCDF};
+use std::collections::BTreeMap;
+
+pub struct LittleMcar {
+    pub statistic: f64,
+    pub degrees_of_freedom: usize,
+    pub p_value: f64,
+    pub patterns: usize,
+}
+
+type Matrix = Vec<Vec<f64>>;
+
+/// Little's (1988) test: the means and covariance are estimated by EM under multivariate
+/// normality, then each missingness pattern's observed means are compared against them.
+pub fn little_mcar(columns: &[Vec<Option<f64>>]) -> Result<LittleMcar, &'static str> {
+    let variables = columns.len();
+    let rows = (0..columns.first().map_or(0, Vec::len))
+        .map(|row| columns.iter().map(|column| column[row]).collect::<Vec<_>>())
+        .filter(|row| row.iter().any(Option::is_some))
+        .collect::<Vec<_>>();
+    if rows.iter().all(|row| row.iter().all(Option::is_some)) {
+        return Err("沒有遺漏值，不需檢定");
+    }
+    let (mean, covariance) = expectation_maximization(&rows, variables).ok_or(SINGULAR)?;
+
+    let mut patterns = BTreeMap::<Vec<bool>, Vec<&Vec<Option<f64>>>>::new();
+    for row in &rows {
+        patterns
+            .entry(row.iter().map(Option::is_some).collect())
+            .or_default()
+            .push(row);
+    }
+    let mut statistic = 0.0;
+    let mut observed_total = 0;
+    for (pattern, members) in &patterns {
+        let observed = observed_indices(pattern);
+        observed_total += observed.len();
+        let inverse = invert(&submatrix(&covariance, &observed, &observed)).ok_or(SINGULAR)?;
+        let difference = observed
+            .iter()
+            .map(|&variable| {
+                members
+                    .iter()
+                    .map(|row| row[variable].unwrap_or_default())
+                    .sum::<f64>()
+                    / members.len() as f64
+                    - mean[variable]
+            })
+            .collect::<Vec<f64>>();
+        statistic += members.len() as f64 * quadratic_form(&inverse, &difference);
+    }
+    let degrees_of_freedom = observed_total.saturating_sub(variables);
+    if degrees_of_freedom == 0 {
+        return Err("遺漏型態不足，無法檢定");
+    }
+    let p_value = ChiSquared::new(degrees_of_freedom as f64)
+        .unwrap()
+        .sf(statistic);
+    Ok(LittleMcar {
+        statistic,
+        degrees_of_freedom,
+        p_value,
+        patterns: patterns.len(),
+    })
+}
+
+const SINGULAR: &str = "共變異數矩陣為奇異矩陣，無法檢定";
+
+fn expectation_maximization(
+    rows: &[Vec<Option<f64>>],
+    variables: usize,
+) -> Option<(Vec<f64>, Matrix)> {
+    let mut mean = (0..variables)
+        .map(|variable| {
+            let observed = rows.iter().filter_map(|row| row[variable]);
+            let (sum, count) =
+                observed.fold((0.0, 0.0), |(sum, count), value| (sum + value, count + 1.0));
+            sum / count
+        })
+        .collect::<Vec<f64>>();
+    let mut covariance = vec![vec![0.0; variables]; variables];
+    for (variable, row_of_covariance) in covariance.iter_mut().enumerate() {
+        let observed = rows
+            .iter()
+            .filter_map(|row| row[variable])
+            .collect::<Vec<f64>>();
+        row_of_covariance[variable] = observed
+            .iter()
+            .map(|value| (value - mean[variable]).powi(2))
+            .sum::<f64>()
+            / observed.len() as f64;
+    }
+
+    for _ in 0..1000 {
+        let mut sum = vec![0.0; variables];
+        let mut cross_product = vec![vec![0.0; variables]; variables];
+        for row in rows {
+            let pattern = row.iter().map(Option::is_some).collect::<Vec<bool>>();
+            let observed = observed_indices(&pattern);
+            let missing = (0..variables)
+                .filter(|&variable| !pattern[variable])
+                .collect::<Vec<usize>>();
+            let mut expected = row
+                .iter()
+                .map(|value| value.unwrap_or_default())
+                .collect::<Vec<f64>>();
+            let mut conditional = vec![vec![0.0; variables]; variables];
+            if !missing.is_empty() {
+                let inverse = invert(&submatrix(&covariance, &observed, &observed))?;
+                let regression = multiply(&submatrix(&covariance, &missing, &observed), &inverse);
+                for (index, &variable) in missing.iter().enumerate() {
+                    expected[variable] = mean[variable]
+                        + observed
+                            .iter()
+                            .enumerate()
+                            .map(|(other_index, &other)| {
+                                regression[index][other_index] * (expected[other] - mean[other])
+                            })
+                            .sum::<f64>();
+                }
+                let explained = multiply(&regression, &submatrix(&covariance, &observed, &missing));
+                for (index, &variable) in missing.iter().enumerate() {
+                    for (other_index, &other) in missing.iter().enumerate() {
+                        conditional[variable][other] =
+                            covariance[variable][other] - explained[index][other_index];
+                    }
+                }
+            }
+            for variable in 0..variables {
+                sum[variable] += expected[variable];
+                for other in 0..variables {
+                    cross_product[variable][other] +=
+                        expected[variable].mul_add(expected[other], conditional[variable][other]);
+                }
+            }
+        }
+        let count = rows.len() as f64;
+        let new_mean = sum.iter().map(|sum| sum / count).collect::<Vec<f64>>();
+        let new_covariance = (0..variables)
+            .map(|variable| {
+                (0..variables)
+                    .map(|other| {
+                        new_mean[variable]
+                            .mul_add(-new_mean[other], cross_product[variable][other] / count)
+                    })
+                    .collect()
+            })
+            .collect::<Matrix>();
+        let change = new_mean
+            .iter()
+            .zip(&mean)
+            .map(|(new, old)| (new - old).abs())
+            .chain(
+                new_covariance
+                    .iter()
+                    .flatten()
+                    .zip(covariance.iter().flatten())
+                    .map(|(new, old)| (new - old).abs()),
+            )
+            .fold(0.0, f64::max);
+        mean = new_mean;
+        covariance = new_covariance;
+        if change < 1e-8 {
+            break;
+        }
+    }
+    Some((mean, covariance))
+}
+
+fn observed_indices(pattern: &[bool]) -> Vec<usize> {
+    (0..pattern.len()).filter(|&index| pattern[index]).collect()
+}
+
+fn submatrix(matrix: &Matrix, rows: &[usize], columns: &[usize]) -> Matrix {
+    rows.iter()
+        .map(|&row| columns.iter().map(|&column| matrix[row][column]).collect())
+        .collect()
+}
+
+fn multiply(left: &Matrix, right: &Matrix) -> Matrix {
+    left.iter()
+        .map(|row| {
+            (0..right.first().map_or(0, Vec::len))
+                .map(|column| {
+                    row.iter()
+                        .zip(right)
+                        .map(|(value, right_row)| value * right_row[column])
+                        .sum()
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn quadratic_form(matrix: &Matrix, vector: &[f64]) -> f64 {
+    matrix
+        .iter()
+        .zip(vector)
+        .map(|(row, x)| {
+            x * row
+                .iter()
+                .zip(vector)
+                .map(|(value, y)| value * y)
+                .sum::<f64>()
+        })
+        .sum()
+}
+
+/// Gauss-Jordan elimination with partial pivoting; `None` when the matrix is singular.
+pub fn invert(matrix: &Matrix) -> Option<Matrix> {
+    let size = matrix.len();
+    let mut augmented = matrix
+        .iter()
+        .enumerate()
+        .map(|(index, row)| {
+            let mut row = row.clone();
+            row.extend((0..size).map(|column| if column == index { 1.0 } else { 0.0 }));
+            row
+        })
+        .collect::<Matrix>();
+    for column in 0..size {
+        let pivot = (column..size).max_by(|&a, &b| {
+            augmented[a][column]
+                .abs()
+                .total_cmp(&augmented[b][column].abs())
+        })?;
+        if augmented[pivot][column].abs() < 1e-12 {
+            return None;
+        }
+        augmented.swap(column, pivot);
+        let divisor = augmented[column][column];
+        for value in &mut augmented[column] {
+            *value /= divisor;
+        }
+        for row in 0..size {
+            if row != column {
+                let factor = augmented[row][column];
+                if factor != 0.0 {
+                    let pivot_row = augmented[column].clone();
+                    for (value, pivot_value) in augmented[row].iter_mut().zip(pivot_row) {
+                        *value -= factor * pivot_value;
+                    }
+                }
+            }
+        }
+    }
+    Some(
+        augmented
+            .into_iter()
+            .map(|row| row[size..].to_vec())
+            .collect(),
+    )
+}
+
+/// Cyclic Jacobi rotations; the eigenvalues of a symmetric matrix in descending order.
+pub fn symmetric_eigenvalues(matrix: &Matrix) -> Vec<f64> {
+    let size = matrix.len();
+    let mut matrix = matrix.clone();
+    for _ in 0..100 {
+        let off_diagonal = (0..size)
+            .flat_map(|row| {
+                (0..size)
+                    .filter(move |column| *column != row)
+                    .map(move |column| (row, column))
+            })
+            .map(|(row, column)| matrix[row][column].powi(2))
+            .sum::<f64>();
+        if off_diagonal < 1e-20 {
+            break;
+        }
+        for first in 0..size {
+            for second in first + 1..size {
+                if matrix[first][second] == 0.0 {
+                    continue;
+                }
+                let theta =
+                    (matrix[second][second] - matrix[first][first]) / (2.0 * matrix[first][second]);
+                let tangent = theta.signum() / (theta.abs() + theta.hypot(1.0));
+                let cos = 1.0 / tangent.hypot(1.0);
+                let sin = tangent * cos;
+                for row in &mut matrix {
+                    let (a, b) = (row[first], row[second]);
+                    row[first] = cos * a - sin * b;
+                    row[second] = sin * a + cos * b;
+                }
+                let (upper, lower) = matrix.split_at_mut(second);
+                for (a, b) in upper[first].iter_mut().zip(&mut lower[0]) {
+                    (*a, *b) = (cos * *a - sin * *b, sin * *a + cos * *b);
+                }
+            }
+        }
+    }
+    let mut eigenvalues = (0..size)
+        .map(|index| matrix[index][index])
+        .collect::<Vec<f64>>();
+    eigenvalues.sort_by(|a, b| b.total_cmp(a));
+    eigenvalues
+}
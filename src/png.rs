@@ -0,0 +1,171 @@
+use flate2::{write::ZlibEncoder, Compression};
+use std::io::Write;
+
+pub type Rgb = [u8; 3];
+
+/// The rows of each glyph of the 3 × 5 pixel font, three bits each with the leftmost pixel in
+/// the highest bit. Only what the heatmap writes is covered: capitals, digits, `-` and `.`.
+const GLYPHS: [(char, [u8; 5]); 38] = [
+    ('0', [0b111, 0b101, 0b101, 0b101, 0b111]),
+    ('1', [0b010, 0b110, 0b010, 0b010, 0b111]),
+    ('2', [0b111, 0b001, 0b111, 0b100, 0b111]),
+    ('3', [0b111, 0b001, 0b111, 0b001, 0b111]),
+    ('4', [0b101, 0b101, 0b111, 0b001, 0b001]),
+    ('5', [0b111, 0b100, 0b111, 0b001, 0b111]),
+    ('6', [0b111, 0b100, 0b111, 0b101, 0b111]),
+    ('7', [0b111, 0b001, 0b001, 0b001, 0b001]),
+    ('8', [0b111, 0b101, 0b111, 0b101, 0b111]),
+    ('9', [0b111, 0b101, 0b111, 0b001, 0b111]),
+    ('-', [0b000, 0b000, 0b111, 0b000, 0b000]),
+    ('.', [0b000, 0b000, 0b000, 0b000, 0b010]),
+    ('A', [0b010, 0b101, 0b111, 0b101, 0b101]),
+    ('B', [0b110, 0b101, 0b110, 0b101, 0b110]),
+    ('C', [0b011, 0b100, 0b100, 0b100, 0b011]),
+    ('D', [0b110, 0b101, 0b101, 0b101, 0b110]),
+    ('E', [0b111, 0b100, 0b110, 0b100, 0b111]),
+    ('F', [0b111, 0b100, 0b110, 0b100, 0b100]),
+    ('G', [0b011, 0b100, 0b101, 0b101, 0b011]),
+    ('H', [0b101, 0b101, 0b111, 0b101, 0b101]),
+    ('I', [0b111, 0b010, 0b010, 0b010, 0b111]),
+    ('J', [0b001, 0b001, 0b001, 0b101, 0b010]),
+    ('K', [0b101, 0b101, 0b110, 0b101, 0b101]),
+    ('L', [0b100, 0b100, 0b100, 0b100, 0b111]),
+    ('M', [0b101, 0b111, 0b111, 0b101, 0b101]),
+    ('N', [0b110, 0b101, 0b101, 0b101, 0b101]),
+    ('O', [0b010, 0b101, 0b101, 0b101, 0b010]),
+    ('P', [0b110, 0b101, 0b110, 0b100, 0b100]),
+    ('Q', [0b010, 0b101, 0b101, 0b110, 0b011]),
+    ('R', [0b110, 0b101, 0b110, 0b101, 0b101]),
+    ('S', [0b011, 0b100, 0b010, 0b001, 0b110]),
+    ('T', [0b111, 0b010, 0b010, 0b010, 0b010]),
+    ('U', [0b101, 0b101, 0b101, 0b101, 0b111]),
+    ('V', [0b101, 0b101, 0b101, 0b101, 0b010]),
+    ('W', [0b101, 0b101, 0b111, 0b111, 0b101]),
+    ('X', [0b101, 0b101, 0b010, 0b101, 0b101]),
+    ('Y', [0b101, 0b101, 0b010, 0b010, 0b010]),
+    ('Z', [0b111, 0b001, 0b010, 0b100, 0b111]),
+];
+
+/// Font pixels from the start of one glyph to the next.
+const GLYPH_ADVANCE: usize = 4;
+pub const GLYPH_HEIGHT: usize = 5;
+
+/// An RGB image drawn with filled rectangles and the 3 × 5 pixel font.
+pub struct Canvas {
+    width: usize,
+    height: usize,
+    pixels: Vec<Rgb>,
+}
+
+impl Canvas {
+    pub fn new(width: usize, height: usize, background: Rgb) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![background; width * height],
+        }
+    }
+
+    /// Fills a rectangle, clipped to the canvas.
+    pub fn fill_rect(
+        &mut self,
+        (x, y): (usize, usize),
+        (width, height): (usize, usize),
+        color: Rgb,
+    ) {
+        for row in y..(y + height).min(self.height) {
+            for column in x..(x + width).min(self.width) {
+                self.pixels[row * self.width + column] = color;
+            }
+        }
+    }
+
+    /// Draws `text` with its top-left corner at `(x, y)`, every font pixel `scale` pixels wide.
+    /// Lowercase letters are drawn as capitals; characters the font lacks are left blank.
+    pub fn text(&mut self, (x, y): (usize, usize), text: &str, scale: usize, color: Rgb) {
+        for (index, character) in text.chars().enumerate() {
+            let character = character.to_ascii_uppercase();
+            let Some((_, rows)) = GLYPHS.iter().find(|(glyph, _)| *glyph == character) else {
+                continue;
+            };
+            let left = x + index * GLYPH_ADVANCE * scale;
+            for (row, bits) in rows.iter().enumerate() {
+                for column in 0..3 {
+                    if bits & (0b100 >> column) != 0 {
+                        self.fill_rect(
+                            (left + column * scale, y + row * scale),
+                            (scale, scale),
+                            color,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// How many pixels wide [`Canvas::text`] draws `text`.
+    pub fn text_width(text: &str, scale: usize) -> usize {
+        (text.chars().count() * GLYPH_ADVANCE).saturating_sub(1) * scale
+    }
+
+    /// The image as an 8-bit RGB PNG, every scanline unfiltered.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        for row in self.pixels.chunks(self.width) {
+            // Writing into a Vec cannot fail.
+            encoder.write_all(&[0]).unwrap();
+            encoder.write_all(&row.concat()).unwrap();
+        }
+        let mut header = Vec::new();
+        header.extend(u32::try_from(self.width).unwrap().to_be_bytes());
+        header.extend(u32::try_from(self.height).unwrap().to_be_bytes());
+        // Bit depth 8, truecolor, deflate, no filter method variants, no interlacing.
+        header.extend([8, 2, 0, 0, 0]);
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        chunk(&mut png, *b"IHDR", &header);
+        chunk(&mut png, *b"IDAT", &encoder.finish().unwrap());
+        chunk(&mut png, *b"IEND", &[]);
+        png
+    }
+}
+
+fn chunk(png: &mut Vec<u8>, kind: [u8; 4], data: &[u8]) {
+    png.extend(u32::try_from(data.len()).unwrap().to_be_bytes());
+    png.extend(kind);
+    png.extend(data);
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(&kind);
+    hasher.update(data);
+    png.extend(hasher.finalize().to_be_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Canvas;
+    use flate2::read::ZlibDecoder;
+    use std::io::Read;
+
+    #[test]
+    fn encode_writes_unfiltered_rgb_scanlines() {
+        let mut canvas = Canvas::new(3, 2, [255, 255, 255]);
+        canvas.fill_rect((1, 1), (5, 5), [178, 24, 43]);
+        let png = canvas.encode();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(&png[12..16], b"IHDR");
+        assert_eq!(&png[16..24], &[0, 0, 0, 3, 0, 0, 0, 2]);
+        let length = u32::from_be_bytes(png[33..37].try_into().unwrap()) as usize;
+        assert_eq!(&png[37..41], b"IDAT");
+        let mut pixels = Vec::new();
+        ZlibDecoder::new(&png[41..41 + length])
+            .read_to_end(&mut pixels)
+            .unwrap();
+        assert_eq!(
+            pixels,
+            [
+                0, 255, 255, 255, 255, 255, 255, 255, 255, 255, //
+                0, 255, 255, 255, 178, 24, 43, 178, 24, 43,
+            ]
+        );
+        assert_eq!(&png[png.len() - 12..], b"\0\0\0\0IEND\xaeB`\x82");
+    }
+}
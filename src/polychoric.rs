@@ -0,0 +1,133 @@
+use statrs::distribution::{ContinuousCDF, Normal};
+use std::f64::consts::PI;
+
+type Matrix = Vec<Vec<f64>>;
+
+/// The polychoric correlation matrix of ordinal columns, each pair estimated separately by the
+/// two-step method: thresholds from the marginal proportions, then the correlation that
+/// maximizes the likelihood of the contingency table.
+pub fn polychoric_matrix(columns: &[&[f64]]) -> Matrix {
+    let thresholds = columns
+        .iter()
+        .map(|column| thresholds(column))
+        .collect::<Vec<_>>();
+    let mut matrix = vec![vec![1.0; columns.len()]; columns.len()];
+    for x in 0..columns.len() {
+        for y in x + 1..columns.len() {
+            let rho = polychoric((columns[x], &thresholds[x]), (columns[y], &thresholds[y]));
+            matrix[x][y] = rho;
+            matrix[y][x] = rho;
+        }
+    }
+    matrix
+}
+
+/// The sorted categories of a column and the normal quantiles of their cumulative proportions,
+/// with infinite outer thresholds.
+fn thresholds(column: &[f64]) -> (Vec<f64>, Vec<f64>) {
+    let mut categories = column.to_vec();
+    categories.sort_by(f64::total_cmp);
+    categories.dedup();
+    let normal = Normal::standard();
+    let mut cuts = vec![f64::NEG_INFINITY];
+    for category in &categories[..categories.len().saturating_sub(1)] {
+        let below = column.iter().filter(|value| *value <= category).count();
+        cuts.push(normal.inverse_cdf(below as f64 / column.len() as f64));
+    }
+    cuts.push(f64::INFINITY);
+    (categories, cuts)
+}
+
+fn polychoric(x: (&[f64], &(Vec<f64>, Vec<f64>)), y: (&[f64], &(Vec<f64>, Vec<f64>))) -> f64 {
+    let (x_data, (x_categories, x_cuts)) = x;
+    let (y_data, (y_categories, y_cuts)) = y;
+    let mut counts = vec![vec![0.0; y_categories.len()]; x_categories.len()];
+    for (x_value, y_value) in x_data.iter().zip(y_data) {
+        let row = x_categories.partition_point(|category| category < x_value);
+        let column = y_categories.partition_point(|category| category < y_value);
+        counts[row][column] += 1.0;
+    }
+    let log_likelihood = |rho: f64| {
+        let mut total = 0.0;
+        for (row, row_counts) in counts.iter().enumerate() {
+            for (column, &count) in row_counts.iter().enumerate() {
+                if count > 0.0 {
+                    let probability =
+                        bivariate_normal_cdf(x_cuts[row + 1], y_cuts[column + 1], rho)
+                            - bivariate_normal_cdf(x_cuts[row], y_cuts[column + 1], rho)
+                            - bivariate_normal_cdf(x_cuts[row + 1], y_cuts[column], rho)
+                            + bivariate_normal_cdf(x_cuts[row], y_cuts[column], rho);
+                    total += count * probability.max(f64::MIN_POSITIVE).ln();
+                }
+            }
+        }
+        total
+    };
+    golden_section_maximum(log_likelihood, -0.999, 0.999)
+}
+
+fn golden_section_maximum(function: impl Fn(f64) -> f64, mut low: f64, mut high: f64) -> f64 {
+    let ratio = (5.0_f64.sqrt() - 1.0) / 2.0;
+    let mut left = ratio.mul_add(-(high - low), high);
+    let mut right = ratio.mul_add(high - low, low);
+    let (mut left_value, mut right_value) = (function(left), function(right));
+    for _ in 0..40 {
+        if left_value > right_value {
+            high = right;
+            right = left;
+            right_value = left_value;
+            left = ratio.mul_add(-(high - low), high);
+            left_value = function(left);
+        } else {
+            low = left;
+            left = right;
+            left_value = right_value;
+            right = ratio.mul_add(high - low, low);
+            right_value = function(right);
+        }
+    }
+    f64::midpoint(low, high)
+}
+
+const GAUSS_LEGENDRE: [(f64, f64); 10] = [
+    (-0.973_906_528_517_171_7, 0.066_671_344_308_688_14),
+    (-0.865_063_366_688_984_5, 0.149_451_349_150_580_6),
+    (-0.679_409_568_299_024_4, 0.219_086_362_515_982_04),
+    (-0.433_395_394_129_247_2, 0.269_266_719_309_996_35),
+    (-0.148_874_338_981_631_2, 0.295_524_224_714_752_87),
+    (0.148_874_338_981_631_2, 0.295_524_224_714_752_87),
+    (0.433_395_394_129_247_2, 0.269_266_719_309_996_35),
+    (0.679_409_568_299_024_4, 0.219_086_362_515_982_04),
+    (0.865_063_366_688_984_5, 0.149_451_349_150_580_6),
+    (0.973_906_528_517_171_7, 0.066_671_344_308_688_14),
+];
+
+/// `P(X ≤ h, Y ≤ k)` for a standard bivariate normal with correlation `rho`, by integrating
+/// Sheppard's formula over `θ = asin r` so the integrand stays bounded as `|rho|` nears one.
+fn bivariate_normal_cdf(h: f64, k: f64, rho: f64) -> f64 {
+    let normal = Normal::standard();
+    if h == f64::NEG_INFINITY || k == f64::NEG_INFINITY {
+        return 0.0;
+    }
+    if h == f64::INFINITY {
+        return normal.cdf(k);
+    }
+    if k == f64::INFINITY {
+        return normal.cdf(h);
+    }
+    let end = rho.asin();
+    let pieces = 4;
+    let width = end / f64::from(pieces);
+    let mut integral = 0.0;
+    for piece in 0..pieces {
+        let middle = width * (f64::from(piece) + 0.5);
+        for (node, weight) in GAUSS_LEGENDRE {
+            let theta = (width / 2.0).mul_add(node, middle);
+            let (sine, cosine) = theta.sin_cos();
+            let exponent =
+                (2.0 * h * k).mul_add(-sine, h.mul_add(h, k * k)) / (2.0 * cosine * cosine);
+            integral += weight * width / 2.0 * (-exponent).exp();
+        }
+    }
+    normal.cdf(h).mul_add(normal.cdf(k), integral / (2.0 * PI))
+}
@@ -0,0 +1,427 @@
+use crate::error::AnalysisError;
+use log::warn;
+use polars::prelude::DataFrame;
+use pyo3::{
+    exceptions::PyKeyError,
+    sync::GILOnceCell,
+    types::{PyBytes, PyDict, PyList, PyModule, PyTuple},
+    IntoPy, Py, PyAny, PyResult, Python,
+};
+use pyo3_polars::PyDataFrame;
+use std::{
+    fmt::Display,
+    sync::mpsc::channel,
+    thread::{sleep, spawn},
+    time::Duration,
+};
+
+#[derive(Clone, Debug)]
+pub struct PythonError {
+    pub computation: String,
+    pub message: String,
+}
+
+impl Display for PythonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.computation, self.message)
+    }
+}
+
+pub fn mcdonald_omega(items: &[&[f64]]) -> PyResult<f64> {
+    Python::with_gil(|py| {
+        let locals = PyDict::new(py);
+        locals.set_item(
+            "items",
+            items
+                .iter()
+                .map(|item| item.to_vec())
+                .collect::<Vec<Vec<f64>>>(),
+        )?;
+        py.run(
+            r"
+from factor_analyzer import FactorAnalyzer
+import numpy
+fa = FactorAnalyzer(n_factors=1, rotation=None)
+fa.fit(numpy.array(items).T)
+loading_sum = fa.loadings_[:, 0].sum()
+omega = loading_sum ** 2 / (loading_sum ** 2 + fa.get_uniquenesses().sum())
+        ",
+            None,
+            Some(locals),
+        )?;
+        python_local(locals, "omega")?.extract::<f64>()
+    })
+}
+
+/// Kaiser-Meyer-Olkin's measure of sampling adequacy and Bartlett's test of sphericity.
+pub struct Adequacy {
+    pub kmo: f64,
+    pub chi_square: f64,
+    pub degrees_of_freedom: f64,
+    pub p_value: f64,
+}
+
+pub struct FactorSolution {
+    pub loadings: DataFrame,
+    pub communalities: Vec<f64>,
+    pub variance: Vec<Vec<f64>>,
+    pub round_trip_issues: Vec<String>,
+    pub from_correlation_matrix: bool,
+    /// Eigenvalues of the correlation matrix, for choosing the number of factors.
+    pub eigenvalues: Vec<f64>,
+    /// Only computed from the data, not from a correlation matrix.
+    pub adequacy: Option<Adequacy>,
+}
+
+/// With `is_corr_matrix`, `dataframe` holds a correlation matrix instead of the data. With
+/// `check_round_trip`, the pandas frame handed to `factor_analyzer` is compared against the
+/// polars original for shape, column names and non-null counts.
+pub fn factor_analysis(
+    dataframe: DataFrame,
+    is_corr_matrix: bool,
+    check_round_trip: bool,
+    rotation: Option<&str>,
+    n_factors: u16,
+) -> Result<FactorSolution, AnalysisError> {
+    let solution = Python::with_gil(|py| -> PyResult<FactorSolution> {
+        let locals = PyDict::new(py);
+        locals.set_item("dataframe", PyDataFrame(dataframe).into_py(py))?;
+        locals.set_item("is_corr_matrix", is_corr_matrix)?;
+        locals.set_item("check_round_trip", check_round_trip)?;
+        locals.set_item("rotation", rotation)?;
+        locals.set_item("n_factors", n_factors)?;
+        py.run(
+            r#"
+from factor_analyzer import FactorAnalyzer
+import polars
+fa = FactorAnalyzer(n_factors=n_factors, rotation=rotation, is_corr_matrix=is_corr_matrix)
+converted = dataframe.to_pandas(use_pyarrow_extension_array=True)
+issues = []
+if check_round_trip:
+    if converted.shape != dataframe.shape:
+        issues.append(f"shape {dataframe.shape} became {converted.shape}")
+    if converted.columns.tolist() != dataframe.columns:
+        issues.append("column names changed")
+    for name in dataframe.columns:
+        if name in converted and converted[name].count() != dataframe.height - dataframe[name].null_count():
+            issues.append(f"{name} non-null count {dataframe.height - dataframe[name].null_count()} became {converted[name].count()}")
+adequacy = None
+if not is_corr_matrix:
+    from factor_analyzer.factor_analyzer import calculate_bartlett_sphericity, calculate_kmo
+    chi_square, bartlett_p_value = calculate_bartlett_sphericity(converted)
+    columns = converted.shape[1]
+    adequacy = (float(calculate_kmo(converted)[1]), float(chi_square), columns * (columns - 1) / 2, float(bartlett_p_value))
+fa.fit(converted)
+result = polars.DataFrame(data=fa.loadings_,schema=converted.columns.tolist())
+communalities = fa.get_communalities().tolist()
+variance = [row.tolist() for row in fa.get_factor_variance()]
+eigenvalues = fa.get_eigenvalues()[0].tolist()
+        "#,
+            None,
+            Some(locals),
+        )?;
+        Ok(FactorSolution {
+            loadings: python_local(locals, "result")?
+                .extract::<PyDataFrame>()?
+                .into(),
+            communalities: python_local(locals, "communalities")?.extract()?,
+            variance: python_local(locals, "variance")?.extract()?,
+            eigenvalues: python_local(locals, "eigenvalues")?.extract()?,
+            round_trip_issues: python_local(locals, "issues")?.extract()?,
+            from_correlation_matrix: is_corr_matrix,
+            adequacy: python_local(locals, "adequacy")?
+                .extract::<Option<(f64, f64, f64, f64)>>()?
+                .map(|(kmo, chi_square, degrees_of_freedom, p_value)| Adequacy {
+                    kmo,
+                    chi_square,
+                    degrees_of_freedom,
+                    p_value,
+                }),
+        })
+    })?;
+    Ok(solution)
+}
+
+fn python_local<'py>(locals: &'py PyDict, name: &str) -> PyResult<&'py PyAny> {
+    locals
+        .get_item(name)
+        .ok_or_else(|| PyKeyError::new_err(name.to_owned()))
+}
+
+/// Newer scipy returns result objects with `statistic` and `pvalue` (and sometimes more, such as
+/// a confidence interval) where older releases returned a plain pair.
+fn statistic_and_p_value(result: &PyAny) -> PyResult<(f64, f64)> {
+    if result.hasattr("statistic")? && result.hasattr("pvalue")? {
+        Ok((
+            result.getattr("statistic")?.extract()?,
+            result.getattr("pvalue")?.extract()?,
+        ))
+    } else {
+        result.extract()
+    }
+}
+
+/// `scipy.stats`, imported on first use and reused by every later test instead of being looked
+/// up again for each pair.
+fn scipy_stats(py: Python<'_>) -> PyResult<&PyModule> {
+    static SCIPY_STATS: GILOnceCell<Py<PyModule>> = GILOnceCell::new();
+    SCIPY_STATS
+        .get_or_try_init(py, || PyModule::import(py, "scipy.stats").map(Into::into))
+        .map(|module| module.as_ref(py))
+}
+
+/// Imports `scipy.stats`, so that a missing scipy fails a whole matrix up front rather than each
+/// of its pairs.
+pub fn check_scipy() -> Result<(), AnalysisError> {
+    Python::with_gil(|py| scipy_stats(py).map(|_| ()))?;
+    Ok(())
+}
+
+/// Calls `scipy.stats.<function>` with every argument that affects the result spelled out, so a
+/// changed default cannot silently change the numbers. The Python lists are built straight from
+/// the borrowed slices.
+fn scipy_test(
+    function: &str,
+    x: &[f64],
+    y: &[f64],
+    arguments: &[(&str, &str)],
+) -> PyResult<(f64, f64)> {
+    Python::with_gil(|py| {
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("alternative", "two-sided")?;
+        for (name, value) in arguments {
+            kwargs.set_item(name, value)?;
+        }
+        statistic_and_p_value(
+            scipy_stats(py)?
+                .getattr(function)?
+                .call((PyList::new(py, x), PyList::new(py, y)), Some(kwargs))?,
+        )
+    })
+}
+
+pub fn pearson(x: &[f64], y: &[f64]) -> Result<(f64, f64), AnalysisError> {
+    Ok(scipy_test("pearsonr", x, y, &[])?)
+}
+
+pub fn spearman(x: &[f64], y: &[f64]) -> PyResult<(f64, f64)> {
+    scipy_test("spearmanr", x, y, &[("nan_policy", "propagate")])
+}
+
+pub fn kendall(x: &[f64], y: &[f64], variant: &str) -> Result<(f64, f64), AnalysisError> {
+    Ok(scipy_test(
+        "kendalltau",
+        x,
+        y,
+        &[
+            ("variant", variant),
+            ("method", "auto"),
+            ("nan_policy", "propagate"),
+        ],
+    )?)
+}
+
+/// Calls `scipy.stats.<function>` with one sample per group.
+fn scipy_group_test(
+    function: &str,
+    groups: &[Vec<f64>],
+    arguments: &[(&str, &str)],
+) -> PyResult<(f64, f64)> {
+    Python::with_gil(|py| {
+        let kwargs = PyDict::new(py);
+        for (name, value) in arguments {
+            kwargs.set_item(name, value)?;
+        }
+        let samples = PyTuple::new(py, groups.iter().map(|group| PyList::new(py, group)));
+        statistic_and_p_value(
+            scipy_stats(py)?
+                .getattr(function)?
+                .call(samples, Some(kwargs))?,
+        )
+    })
+}
+
+/// Student's t-test of two independent groups; scipy assumes equal variances by default.
+pub fn ttest_ind(groups: &[Vec<f64>]) -> PyResult<(f64, f64)> {
+    scipy_group_test("ttest_ind", groups, &[("alternative", "two-sided")])
+}
+
+pub fn f_oneway(groups: &[Vec<f64>]) -> PyResult<(f64, f64)> {
+    scipy_group_test("f_oneway", groups, &[])
+}
+
+pub fn levene(groups: &[Vec<f64>]) -> PyResult<(f64, f64)> {
+    scipy_group_test("levene", groups, &[("center", "median")])
+}
+
+/// The Shapiro-Wilk W statistic and p-value of `x`.
+pub fn shapiro(x: &[f64]) -> PyResult<(f64, f64)> {
+    Python::with_gil(|py| {
+        statistic_and_p_value(
+            scipy_stats(py)?
+                .getattr("shapiro")?
+                .call1((PyList::new(py, x),))?,
+        )
+    })
+}
+
+/// Tests whether the mean of `x − y` differs from zero.
+pub fn paired_t_test(x: &[f64], y: &[f64]) -> PyResult<(f64, f64)> {
+    scipy_test("ttest_rel", x, y, &[("nan_policy", "propagate")])
+}
+
+/// The installed scipy version, recorded in reports for reproducibility.
+/// Decodes text with one of Python's codecs, which cover the legacy encodings such as Big5 that
+/// survey platforms still export.
+pub fn decode(bytes: &[u8], encoding: &str) -> PyResult<String> {
+    Python::with_gil(|py| {
+        PyBytes::new(py, bytes)
+            .call_method1("decode", (encoding,))?
+            .extract()
+    })
+}
+
+pub fn scipy_version() -> Option<String> {
+    Python::with_gil(|py| {
+        PyModule::import(py, "scipy")
+            .and_then(|scipy| scipy.getattr("__version__")?.extract())
+            .ok()
+    })
+}
+
+/// Records every Python warning emitted while `call` runs instead of letting it go to stderr.
+pub fn capturing_warnings<T>(call: impl FnOnce() -> T) -> (T, Vec<String>) {
+    let context = Python::with_gil(|py| -> PyResult<Py<PyDict>> {
+        let locals = PyDict::new(py);
+        py.run(
+            r#"
+import warnings
+context = warnings.catch_warnings(record=True)
+record = context.__enter__()
+warnings.simplefilter("always")
+            "#,
+            None,
+            Some(locals),
+        )?;
+        Ok(locals.into())
+    });
+    let value = call();
+    let messages = context.and_then(|locals| {
+        Python::with_gil(|py| {
+            let locals = locals.as_ref(py);
+            py.run(
+                r"
+messages = [str(warning.message) for warning in record]
+context.__exit__(None, None, None)
+                ",
+                None,
+                Some(locals),
+            )?;
+            python_local(locals, "messages")?.extract()
+        })
+    });
+    (value, messages.unwrap_or_default())
+}
+
+/// The two-sided p-value of Fisher's exact test on a 2×2 table of counts.
+pub fn fisher_exact(table: [[f64; 2]; 2]) -> PyResult<f64> {
+    Python::with_gil(|py| {
+        let (_, p_value) = statistic_and_p_value(
+            scipy_stats(py)?
+                .getattr("fisher_exact")?
+                .call1((table.map(Vec::from).to_vec(),))?,
+        )?;
+        Ok(p_value)
+    })
+}
+
+/// The chi-square statistic and p-value of the test of independence on a crosstab, without the
+/// Yates correction so the statistic stays consistent with Cramér's V.
+pub fn chi2_contingency(table: Vec<Vec<f64>>) -> PyResult<(f64, f64)> {
+    Python::with_gil(|py| {
+        let kwargs = PyDict::new(py);
+        kwargs.set_item("correction", false)?;
+        let result = scipy_stats(py)?
+            .getattr("chi2_contingency")?
+            .call((table,), Some(kwargs))?;
+        Ok((
+            result.get_item(0)?.extract()?,
+            result.get_item(1)?.extract()?,
+        ))
+    })
+}
+
+pub fn importable(module: &str) -> bool {
+    Python::with_gil(|py| PyModule::import(py, module).is_ok())
+}
+
+/// Runs `call` on its own thread and gives up after `timeout`. The GIL is released between
+/// bytecodes, so on timeout a `TimeoutError` is raised asynchronously in the worker's Python
+/// thread; native code that never returns to the interpreter keeps running in the background.
+pub fn with_timeout<T: Send + 'static>(
+    timeout: Duration,
+    computation: &str,
+    call: impl FnOnce() -> Result<T, PythonError> + Send + 'static,
+) -> Result<T, PythonError> {
+    let (ident_sender, ident_receiver) = channel();
+    let (result_sender, result_receiver) = channel();
+    spawn(move || {
+        let ident = Python::with_gil(|py| {
+            PyModule::import(py, "threading")?
+                .getattr("get_ident")?
+                .call0()?
+                .extract::<u64>()
+        });
+        let _ = ident_sender.send(ident.ok());
+        let _ = result_sender.send(call());
+    });
+    let ident = ident_receiver.recv().ok().flatten();
+    if let Ok(result) = result_receiver.recv_timeout(timeout) {
+        return result;
+    }
+    if let Some(ident) = ident {
+        let _ = Python::with_gil(|py| {
+            let locals = PyDict::new(py);
+            locals.set_item("ident", ident)?;
+            py.run(
+                r"
+import ctypes
+ctypes.pythonapi.PyThreadState_SetAsyncExc(ctypes.c_ulong(ident), ctypes.py_object(TimeoutError))
+                ",
+                None,
+                Some(locals),
+            )
+        });
+    }
+    Err(PythonError {
+        computation: computation.to_owned(),
+        message: format!("timed out after {} s", timeout.as_secs()),
+    })
+}
+
+/// Retries a failing Python computation with exponential backoff starting at 100 ms.
+pub fn with_retries<T, E: Display>(
+    retries: usize,
+    computation: &str,
+    mut call: impl FnMut() -> Result<T, E>,
+) -> Result<T, PythonError> {
+    let mut delay = Duration::from_millis(100);
+    let mut attempt = 0;
+    loop {
+        match call() {
+            Ok(value) => return Ok(value),
+            Err(error) if attempt < retries => {
+                warn!("{computation} failed, retrying in {delay:?}: {error}");
+                sleep(delay);
+                delay *= 2;
+                attempt += 1;
+            }
+            Err(error) => {
+                return Err(PythonError {
+                    computation: computation.to_owned(),
+                    message: error.to_string(),
+                })
+            }
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use crate::mcar::invert;
+use statrs::distribution::{ContinuousCDF, FisherSnedecor, StudentsT};
+
+pub struct Coefficient {
+    pub estimate: f64,
+    pub standard_error: f64,
+    pub t: f64,
+    pub p_value: f64,
+}
+
+pub struct Ols {
+    pub n: usize,
+    /// The intercept, then one coefficient per predictor.
+    pub coefficients: Vec<Coefficient>,
+    pub r_squared: f64,
+    pub adjusted_r_squared: f64,
+    pub f: f64,
+    pub degrees_of_freedom: (f64, f64),
+    pub p_value: f64,
+}
+
+/// Ordinary least squares of the first column on the others with an intercept, solved through
+/// the normal equations `(XᵀX)β = Xᵀy`. Rows with a missing value in any column are dropped.
+pub fn ols(columns: &[Vec<Option<f64>>]) -> Result<Ols, &'static str> {
+    let rows = (0..columns[0].len())
+        .filter_map(|row| columns.iter().map(|column| column[row]).collect())
+        .collect::<Vec<Vec<f64>>>();
+    let (n, predictors) = (rows.len(), columns.len() - 1);
+    if n <= predictors + 1 {
+        return Err("完整資料的筆數不足以估計所有係數");
+    }
+    let design = rows
+        .iter()
+        .map(|row| {
+            let mut design_row = vec![1.0];
+            design_row.extend_from_slice(&row[1..]);
+            design_row
+        })
+        .collect::<Vec<Vec<f64>>>();
+    let size = predictors + 1;
+    let cross_products = (0..size)
+        .map(|a| {
+            (0..size)
+                .map(|b| design.iter().map(|row| row[a] * row[b]).sum())
+                .collect()
+        })
+        .collect::<Vec<Vec<f64>>>();
+    let Some(inverse) = invert(&cross_products) else {
+        return Err("預測變數共線，無法估計");
+    };
+    let moments = (0..size)
+        .map(|a| design.iter().zip(&rows).map(|(x, row)| x[a] * row[0]).sum())
+        .collect::<Vec<f64>>();
+    let beta = inverse
+        .iter()
+        .map(|row| row.iter().zip(&moments).map(|(a, b)| a * b).sum())
+        .collect::<Vec<f64>>();
+
+    let mean = rows.iter().map(|row| row[0]).sum::<f64>() / n as f64;
+    let residual = design
+        .iter()
+        .zip(&rows)
+        .map(|(x, row)| {
+            let fitted = x.iter().zip(&beta).map(|(x, b)| x * b).sum::<f64>();
+            (row[0] - fitted).powi(2)
+        })
+        .sum::<f64>();
+    let total = rows.iter().map(|row| (row[0] - mean).powi(2)).sum::<f64>();
+    if total == 0.0 {
+        return Err("依變數沒有變異，無法估計");
+    }
+    let degrees_of_freedom = (predictors as f64, (n - size) as f64);
+    let variance = residual / degrees_of_freedom.1;
+    let distribution = StudentsT::new(0.0, 1.0, degrees_of_freedom.1).unwrap();
+    let coefficients = beta
+        .iter()
+        .enumerate()
+        .map(|(index, &estimate)| {
+            let standard_error = (variance * inverse[index][index]).sqrt();
+            let t = estimate / standard_error;
+            Coefficient {
+                estimate,
+                standard_error,
+                t,
+                p_value: 2.0 * distribution.sf(t.abs()),
+            }
+        })
+        .collect();
+
+    let r_squared = 1.0 - residual / total;
+    let adjusted_r_squared = 1.0 - (1.0 - r_squared) * (n as f64 - 1.0) / degrees_of_freedom.1;
+    let f = (r_squared / degrees_of_freedom.0) / ((1.0 - r_squared) / degrees_of_freedom.1);
+    let p_value = FisherSnedecor::new(degrees_of_freedom.0, degrees_of_freedom.1)
+        .map_or(f64::NAN, |distribution| distribution.sf(f));
+    Ok(Ols {
+        n,
+        coefficients,
+        r_squared,
+        adjusted_r_squared,
+        f,
+        degrees_of_freedom,
+        p_value,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ols;
+
+    #[test]
+    fn recovers_an_exact_line() {
+        // y = 1 + 2·x₁ − x₂ plus residuals that sum to zero and are orthogonal to x₁ and x₂.
+        let x1 = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+        let x2 = [2.0, 1.0, 4.0, 3.0, 6.0, 5.0];
+        let noise = [0.1, -0.1, 0.0, 0.0, -0.1, 0.1];
+        let y = x1
+            .iter()
+            .zip(&x2)
+            .zip(noise)
+            .map(|((x1, x2), noise)| Some(2.0f64.mul_add(*x1, 1.0) - x2 + noise))
+            .collect();
+        let columns = vec![y, x1.map(Some).to_vec(), x2.map(Some).to_vec()];
+        let fit = ols(&columns).unwrap();
+        assert_eq!(fit.n, 6);
+        assert_eq!(fit.degrees_of_freedom, (2.0, 3.0));
+        for (coefficient, expected) in fit.coefficients.iter().zip([1.0, 2.0, -1.0]) {
+            assert!((coefficient.estimate - expected).abs() < 1e-9);
+        }
+        assert!(fit.r_squared > 0.99 && fit.adjusted_r_squared < fit.r_squared);
+        assert!(fit.coefficients[1].p_value < 0.01);
+        let collinear = vec![columns[0].clone(), columns[1].clone(), columns[1].clone()];
+        assert!(ols(&collinear).is_err());
+    }
+}
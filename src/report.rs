@@ -0,0 +1,2357 @@
+use crate::{
+    cli::{DetrendMethod, NumberLocale, CSV_MATRICES},
+    error::AnalysisError,
+    icc::Icc,
+    json::{JsonCorrelation, JsonReport},
+    mcar::LittleMcar,
+    png::{Canvas, Rgb, GLYPH_HEIGHT},
+    python::{FactorSolution, PythonError},
+    regression, Analysis, Coefficient, CorrelationMatrix, CorrelationResult, CorrelationValue,
+    Crosstab, Field, Frequency, GroupComparison, Normality, PairedTTest, Regression, Reliability,
+    ReportStyle, ResponseRate, RollingCorrelation, Scale, TableStyle, SHAPIRO_MAX_N,
+};
+use polars::prelude::*;
+use std::{
+    collections::BTreeMap,
+    fmt::{Display, Write as _},
+    fs::File,
+    io,
+    iter::once,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+impl Display for CorrelationValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NotValid => write!(f, "不適用"),
+            Self::Diagonal => write!(f, "1"),
+            Self::Constant => write!(f, "常數欄位"),
+            Self::InsufficientSample => write!(f, "樣本不足"),
+            Self::TooManyCategories => write!(f, "類別過多"),
+            Self::Skipped => write!(f, "已略過"),
+            Self::Failed(_) => write!(f, "計算失敗"),
+            Self::Valid(result) => write!(f, "{result}"),
+        }
+    }
+}
+
+impl Display for CorrelationResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let number = |value: f64| format_number(value, self.precision, self.locale);
+        if self.bold {
+            write!(
+                f,
+                "**{}: {}** <br> **p value: {}**",
+                self.statistic,
+                number(self.r),
+                number(self.p_value)
+            )?;
+            if let Some(adjusted_p_value) = self.adjusted_p_value {
+                write!(f, " <br> **adjusted p: {}**", number(adjusted_p_value))?;
+            }
+        } else {
+            write!(
+                f,
+                "{}: {}<br>p value: {}",
+                self.statistic,
+                number(self.r),
+                number(self.p_value)
+            )?;
+            if let Some(adjusted_p_value) = self.adjusted_p_value {
+                write!(f, "<br>adjusted p: {}", number(adjusted_p_value))?;
+            }
+        }
+        if let Some(level) = self.confidence_level {
+            write!(f, "<br>{}% CI: ", level * 100.0)?;
+            match (self.ci_low, self.ci_high) {
+                (Some(low), Some(high)) => write!(f, "[{}, {}]", number(low), number(high))?,
+                _ => write!(f, "N/A")?,
+            }
+        }
+        if self.exact_test {
+            write!(f, "<br>(Fisher's exact test)")?;
+        }
+        if let Some((statistic, df)) = self.chi_square {
+            write!(f, "<br>χ²({df}): {}", number(statistic))?;
+        }
+        if let Some((r0, p_value)) = self.reference {
+            write!(f, "<br>p value (r = {r0}): {}", number(p_value))?;
+        }
+        if let Some(standard_error) = self.standard_error {
+            write!(f, "<br>SE: {}", number(standard_error))?;
+        }
+        write!(f, "<br>N: {}", self.n)?;
+        if self.missing_rows > 0 {
+            write!(f, "（排除 {} 筆遺漏）", self.missing_rows)?;
+        }
+        if let Some(tied_pairs) = self.tied_pairs {
+            write!(f, "<br>tied pairs: {tied_pairs}")?;
+        }
+        Ok(())
+    }
+}
+
+pub fn render_analysis(analysis: &Analysis, out: &mut impl io::Write) -> io::Result<()> {
+    for note in row_selection_notes("本報告", analysis) {
+        out.write_all(note.as_bytes())?;
+    }
+    if !analysis.warnings.is_empty() {
+        out.write_all(warning_section("警告", &analysis.warnings).as_bytes())?;
+    }
+    let style = analysis.table_style;
+    if let Some(description) = &analysis.description {
+        description_sections(
+            out,
+            analysis,
+            description,
+            analysis.weighted_description.as_ref(),
+        )?;
+    }
+    frequency_sections(out, analysis)?;
+    for (coefficient, rows) in analysis.matrices() {
+        if let Some(rows) = rows {
+            let title = matrix_title(analysis, coefficient, rows.comparisons);
+            out.write_all(
+                match analysis.style {
+                    ReportStyle::Default => correlation_section(&title, rows, analysis),
+                    ReportStyle::Apa => apa_correlation_section(&title, rows, analysis),
+                }
+                .as_bytes(),
+            )?;
+            if coefficient == Coefficient::Spearman {
+                out.write_all(tie_notes(&title, rows, analysis).as_bytes())?;
+            }
+            explain(out, analysis, correlation_explanation(coefficient))?;
+        }
+    }
+    if let Some(covariance) = &analysis.covariance {
+        let covariance = matrix_table(
+            &covariance.names,
+            &covariance.names,
+            &covariance.cells,
+            |&value| number(value, analysis),
+        );
+        out.write_all(table_section("變異數－共變異數矩陣", &covariance, style).as_bytes())?;
+        explain(out, analysis, COVARIANCE_EXPLANATION)?;
+    }
+    block_sections(out, analysis)?;
+    if !analysis.reliability.is_empty() {
+        let reliability = reliability_table(&analysis.reliability, analysis.spearman_brown);
+        out.write_all(table_section("信度分析", &reliability, style).as_bytes())?;
+        explain(out, analysis, RELIABILITY_EXPLANATION)?;
+    }
+    diagnostic_sections(out, analysis, "")?;
+    out.write_all(adequacy_section("因子分析適切性", analysis).as_bytes())?;
+    if let Some(factor_analysis) = &analysis.factor {
+        out.write_all(
+            factor_analysis_section(
+                if matches!(factor_analysis, Ok(solution) if solution.from_correlation_matrix) {
+                    "因子分析（多分格相關矩陣）"
+                } else {
+                    "因子分析"
+                },
+                factor_analysis,
+                analysis,
+            )
+            .as_bytes(),
+        )?;
+        explain(out, analysis, FACTOR_EXPLANATION)?;
+    }
+    let python_warnings = analysis.python_warnings();
+    if !python_warnings.is_empty() {
+        out.write_all(warning_section("Python 警告", &python_warnings).as_bytes())?;
+    }
+    Ok(())
+}
+
+/// The `--rows` cross-correlation block and the point-biserial table, which both pair one set
+/// of fields with another instead of every field with every other.
+fn block_sections(out: &mut impl io::Write, analysis: &Analysis) -> io::Result<()> {
+    let style = analysis.table_style;
+    if let Some(cross) = &analysis.cross {
+        let block = matrix_table(
+            &cross.row_names,
+            &cross.column_names,
+            &cross.cells,
+            |(coefficient, value)| format!("{}<br>{value}", coefficient.name()),
+        );
+        let title = corrected_title(String::from("交叉相關"), analysis, cross.comparisons);
+        out.write_all(table_section(&title, &block, style).as_bytes())?;
+        let pairs = cross
+            .column_names
+            .iter()
+            .zip(&cross.cells)
+            .flat_map(|(column, values)| {
+                cross
+                    .row_names
+                    .iter()
+                    .zip(values)
+                    .map(move |(row, (_, value))| (row.as_str(), column.as_str(), value))
+            });
+        out.write_all(pair_count_notes("交叉相關", pairs, analysis).as_bytes())?;
+        explain(out, analysis, CROSS_EXPLANATION)?;
+    }
+    if let Some(point_biserial) = &analysis.point_biserial {
+        let block = matrix_table(
+            &point_biserial.row_names,
+            &point_biserial.column_names,
+            &point_biserial.cells,
+            |(_, value)| value.to_string(),
+        );
+        let title = corrected_title(
+            String::from("點二系列相關（Point-biserial）"),
+            analysis,
+            point_biserial.comparisons,
+        );
+        out.write_all(table_section(&title, &block, style).as_bytes())?;
+        explain(out, analysis, POINT_BISERIAL_EXPLANATION)?;
+    }
+    Ok(())
+}
+
+/// `value` with `precision` decimals, grouped by thousands and with the decimal mark of `locale`.
+pub fn format_number(value: f64, precision: usize, locale: NumberLocale) -> String {
+    let text = format!("{value:.precision$}");
+    if locale == NumberLocale::Plain || !value.is_finite() {
+        return text;
+    }
+    let (group, decimal) = locale.separators();
+    let (sign, digits) = text
+        .strip_prefix('-')
+        .map_or(("", text.as_str()), |digits| ("-", digits));
+    let (integer, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+    let mut grouped = String::new();
+    for (index, digit) in integer.chars().enumerate() {
+        if index > 0 && (integer.len() - index) % 3 == 0 {
+            grouped.push_str(group);
+        }
+        grouped.push(digit);
+    }
+    if fraction.is_empty() {
+        format!("{sign}{grouped}")
+    } else {
+        format!("{sign}{grouped}{decimal}{fraction}")
+    }
+}
+
+/// Writes the floating-point columns with [`format_number`] at the report's precision.
+fn localize(dataframe: &DataFrame, analysis: &Analysis) -> DataFrame {
+    DataFrame::new(
+        dataframe
+            .get_columns()
+            .iter()
+            .map(|column| {
+                column.f64().map_or_else(
+                    |_| column.clone(),
+                    |values| {
+                        Series::new(
+                            column.name(),
+                            values
+                                .into_iter()
+                                .map(|value| value.map(|value| number(value, analysis)))
+                                .collect::<Vec<Option<String>>>(),
+                        )
+                    },
+                )
+            })
+            .collect::<Vec<Series>>(),
+    )
+    .unwrap()
+}
+
+const DESCRIBE_EXPLANATION: &str = "count 為有效樣本數，mean 與 std 為平均數與標準差，由 min、百分位數至 max 可看出資料的分布與偏態。";
+const FREQUENCY_EXPLANATION: &str = "次數分配列出名目欄位每個類別的人數與占全部樣本的百分比，(缺失) 為未作答的人數；交叉表列出兩個欄位各類別組合的人數，可先確認各類別的樣本是否足夠再解讀相關結果。";
+const COVARIANCE_EXPLANATION: &str = "對角線為各欄位的變異數，其餘為兩個欄位的共變異數；正負號代表兩者共同變動的方向，但大小受單位影響，不宜直接比較。";
+const CROSS_EXPLANATION: &str =
+    "每格列出兩組欄位之間所使用的相關係數及其結果，解讀方式與相關矩陣相同。";
+const POINT_BISERIAL_EXPLANATION: &str = "點二系列相關即二分名目變數（兩個類別）與另一個變數的 Pearson 相關，列為二分變數、欄為順序、等距與比率欄位；正值代表代碼較大的類別在該欄位的平均較高。顯著的結果以粗體標示。";
+const RELIABILITY_EXPLANATION: &str = "Cronbach's α 與 McDonald's ω 衡量同一構面題目的內部一致性，一般以 0.7 以上為可接受、0.8 以上為良好。";
+const MCAR_EXPLANATION: &str = "虛無假設為資料完全隨機遺漏；p value 小於 0.05 時代表遺漏並非完全隨機，直接刪除遺漏值可能造成偏誤。";
+const FACTOR_EXPLANATION: &str = "因子負荷量的絕對值越大，代表該題越能反映該因子；共同性為各題可被所有因子解釋的變異比例，解釋變異量則為各因子解釋的變異比例。";
+
+const fn correlation_explanation(coefficient: Coefficient) -> &'static str {
+    match coefficient {
+        Coefficient::Pearson | Coefficient::Biserial | Coefficient::PointBiserial => "Pearson 相關係數衡量兩個連續變數的線性關聯，介於 −1 與 1 之間，越接近兩端代表關聯越強，正負號代表方向；顯著的結果以粗體標示。",
+        Coefficient::Spearman => "Spearman 等級相關以名次計算兩個變數的單調關聯，介於 −1 與 1 之間，越接近兩端代表關聯越強，正負號代表方向；顯著的結果以粗體標示。",
+        Coefficient::Gamma => "Goodman-Kruskal gamma 比較一致與不一致的配對數，不計同分的配對，介於 −1 與 1 之間，越接近兩端代表順序關聯越強；顯著的結果以粗體標示。",
+        Coefficient::ChiSquare => "卡方獨立性檢定不假設類別有順序，檢驗兩個名目變數是否獨立；Cramér's V 為效果量，介於 0 與 1 之間，越大代表關聯越強，格內並列出 χ² 統計量與自由度。顯著的結果以粗體標示。",
+        Coefficient::Kendall => "Kendall tau 比較兩個變數的排序是否一致，介於 −1 與 1 之間，越接近兩端代表關聯越強；兩個欄位皆為二分變數時為 Yule's Q。顯著的結果以粗體標示。",
+    }
+}
+
+/// Writes `explanation` as a quoted note when `--explain` is given.
+fn explain(out: &mut impl io::Write, analysis: &Analysis, explanation: &str) -> io::Result<()> {
+    if analysis.explain {
+        out.write_all(format!("> 說明：{explanation}\n\n").as_bytes())?;
+    }
+    Ok(())
+}
+
+/// `value` at the `--precision` of the report, in its `--number-locale`.
+fn number(value: f64, analysis: &Analysis) -> String {
+    format_number(value, analysis.precision, analysis.number_locale)
+}
+
+fn table_section(title: &str, dataframe: &DataFrame, style: TableStyle) -> String {
+    format!("## {title} \n\n{}\n\n", table(dataframe, style))
+}
+
+fn mcar_section(
+    title: &str,
+    mcar: &Result<LittleMcar, &'static str>,
+    analysis: &Analysis,
+) -> String {
+    match mcar {
+        Ok(mcar) => format!(
+            "## {title} \n\nLittle's MCAR 檢定（{} 種遺漏型態）：χ² = {}，自由度 = {}，p value = {}\n\n",
+            mcar.patterns,
+            number(mcar.statistic, analysis),
+            mcar.degrees_of_freedom,
+            number(mcar.p_value, analysis)
+        ),
+        Err(reason) => format!("## {title} \n\nLittle's MCAR 檢定：{reason}\n\n"),
+    }
+}
+
+/// The missing-data test, ICC and condition number, with `suffix` appended to their titles.
+fn diagnostic_sections(
+    out: &mut impl io::Write,
+    analysis: &Analysis,
+    suffix: &str,
+) -> io::Result<()> {
+    if let Some(response_rates) = &analysis.response_rates {
+        let title = format!("回收率{suffix}");
+        out.write_all(response_rate_section(&title, response_rates, analysis).as_bytes())?;
+    }
+    if let Some(mcar) = &analysis.mcar {
+        out.write_all(mcar_section(&format!("遺漏值檢定{suffix}"), mcar, analysis).as_bytes())?;
+        explain(out, analysis, MCAR_EXPLANATION)?;
+    }
+    if let Some(normality) = &analysis.normality {
+        let title = format!("常態性檢定{suffix}");
+        out.write_all(normality_section(&title, normality, analysis).as_bytes())?;
+    }
+    if let Some(icc) = &analysis.icc {
+        let title = format!("組內相關係數{suffix}");
+        out.write_all(icc_section(&title, icc, analysis).as_bytes())?;
+    }
+    if let Some(paired_t_test) = &analysis.paired_t_test {
+        let title = format!("成對樣本 t 檢定{suffix}");
+        out.write_all(paired_t_test_section(&title, paired_t_test, analysis).as_bytes())?;
+    }
+    if let Some(comparison) = &analysis.group_comparison {
+        let title = format!("組間比較{suffix}");
+        out.write_all(group_comparison_section(&title, comparison, analysis).as_bytes())?;
+    }
+    if let Some(regression) = &analysis.regression {
+        let title = format!("迴歸分析{suffix}");
+        out.write_all(regression_section(&title, regression, analysis).as_bytes())?;
+    }
+    if let Some(condition_number) = analysis.condition_number {
+        let title = format!("條件數{suffix}");
+        out.write_all(condition_number_section(&title, condition_number).as_bytes())?;
+    }
+    Ok(())
+}
+
+fn response_rate_section(
+    title: &str,
+    response_rates: &[ResponseRate],
+    analysis: &Analysis,
+) -> String {
+    let rate_table = DataFrame::new(vec![
+        Series::new(
+            "欄位",
+            response_rates
+                .iter()
+                .map(|response_rate| response_rate.field.as_str())
+                .collect::<Vec<&str>>(),
+        ),
+        Series::new(
+            "有效回答",
+            response_rates
+                .iter()
+                .map(|response_rate| {
+                    format!("{} / {}", response_rate.answered, response_rate.total)
+                })
+                .collect::<Vec<String>>(),
+        ),
+        Series::new(
+            "回收率",
+            response_rates
+                .iter()
+                .map(|response_rate| {
+                    let rate = format!("{:.1}%", response_rate.rate() * 100.0);
+                    if response_rate.rate() < analysis.min_response_rate {
+                        format!("**{rate}**")
+                    } else {
+                        rate
+                    }
+                })
+                .collect::<Vec<String>>(),
+        ),
+    ])
+    .unwrap();
+    format!(
+        "## {title} \n\n{}\n\n粗體為低於 {}% 的回收率，該欄位的結果可能不可靠。\n\n",
+        table(&rate_table, analysis.table_style),
+        analysis.min_response_rate * 100.0
+    )
+}
+
+fn icc_section(title: &str, icc: &Result<Icc, &'static str>, analysis: &Analysis) -> String {
+    let icc = match icc {
+        Ok(icc) => icc,
+        Err(reason) => return format!("## {title} \n\nICC：{reason}\n\n"),
+    };
+    let estimates = [("ICC(2,1)", &icc.single), ("ICC(2,k)", &icc.average)];
+    let estimate_table = DataFrame::new(vec![
+        Series::new("", estimates.map(|(name, _)| name).to_vec()),
+        Series::new(
+            "ICC",
+            estimates
+                .map(|(_, estimate)| number(estimate.value, analysis))
+                .to_vec(),
+        ),
+        Series::new(
+            "95% CI",
+            estimates
+                .map(|(_, estimate)| {
+                    format!(
+                        "[{}, {}]",
+                        number(estimate.lower, analysis),
+                        number(estimate.upper, analysis)
+                    )
+                })
+                .to_vec(),
+        ),
+    ])
+    .unwrap();
+    format!(
+        "## {title} \n\n{} 位評分者、{} 筆完整評分（雙因子隨機效果模型）。\n\n{}\n\nF({}, {}) = {}，p value = {}\n\n",
+        icc.raters,
+        icc.subjects,
+        table(&estimate_table, analysis.table_style),
+        icc.degrees_of_freedom.0,
+        icc.degrees_of_freedom.1,
+        number(icc.f, analysis),
+        number(icc.p_value, analysis)
+    )
+}
+
+fn paired_t_test_section(
+    title: &str,
+    paired_t_test: &Result<PairedTTest, String>,
+    analysis: &Analysis,
+) -> String {
+    let test = match paired_t_test {
+        Ok(test) => test,
+        Err(reason) => return format!("## {title} \n\n成對樣本 t 檢定：{reason}\n\n"),
+    };
+    format!(
+        "## {title} \n\n比較同一批受訪者在「{}」與「{}」的兩次測量（成對樣本，非獨立樣本），共 {} 筆兩欄皆有回答的資料。\n\n平均差異（後 − 前）= {}，95% CI [{}, {}]\n\nt({}) = {}，p value = {}\n\n",
+        test.pre,
+        test.post,
+        test.pairs,
+        number(test.mean_difference, analysis),
+        number(test.interval.0, analysis),
+        number(test.interval.1, analysis),
+        test.degrees_of_freedom,
+        number(test.t, analysis),
+        number(test.p_value, analysis)
+    )
+}
+
+fn normality_section(title: &str, normality: &[Normality], analysis: &Analysis) -> String {
+    let number = |value: f64| number(value, analysis);
+    let tests = |statistic: fn((f64, f64)) -> f64| {
+        normality
+            .iter()
+            .map(|normality| {
+                normality
+                    .test
+                    .as_ref()
+                    .map_or_else(|_| String::from("—"), |&test| number(statistic(test)))
+            })
+            .collect::<Vec<String>>()
+    };
+    let normality_table = DataFrame::new(vec![
+        Series::new(
+            "欄位",
+            normality
+                .iter()
+                .map(|normality| normality.field.as_str())
+                .collect::<Vec<&str>>(),
+        ),
+        Series::new(
+            "N",
+            normality
+                .iter()
+                .map(|normality| normality.n as u64)
+                .collect::<Vec<u64>>(),
+        ),
+        Series::new("W", tests(|(w, _)| w)),
+        Series::new("p value", tests(|(_, p_value)| p_value)),
+        Series::new(
+            "結果",
+            normality
+                .iter()
+                .map(|normality| match &normality.test {
+                    Ok((_, p_value)) if *p_value < analysis.alpha => {
+                        String::from("**拒絕常態，建議改用 Spearman 或 Kendall**")
+                    }
+                    Ok(_) => String::from("未拒絕常態"),
+                    Err(reason) => reason.clone(),
+                })
+                .collect::<Vec<String>>(),
+        ),
+    ])
+    .unwrap();
+    format!(
+        "## {title} \n\n{}\n\n以 Shapiro-Wilk 檢定各順序、等距與比率欄位的有效回答；p value 小於 {} 時拒絕常態假設，Pearson 相關的前提可能不成立。樣本超過 {SHAPIRO_MAX_N} 筆的欄位不檢定。\n\n",
+        table(&normality_table, analysis.table_style),
+        analysis.alpha
+    )
+}
+
+fn group_comparison_section(
+    title: &str,
+    comparison: &GroupComparison,
+    analysis: &Analysis,
+) -> String {
+    let number = |value: f64| number(value, analysis);
+    let group_table = DataFrame::new(vec![
+        Series::new(
+            &comparison.group,
+            comparison
+                .groups
+                .iter()
+                .map(|(label, ..)| label.as_str())
+                .collect::<Vec<&str>>(),
+        ),
+        Series::new(
+            "N",
+            comparison
+                .groups
+                .iter()
+                .map(|(_, n, ..)| *n as u64)
+                .collect::<Vec<u64>>(),
+        ),
+        Series::new(
+            "平均數",
+            comparison
+                .groups
+                .iter()
+                .map(|(_, _, mean, _)| number(*mean))
+                .collect::<Vec<String>>(),
+        ),
+        Series::new(
+            "標準差",
+            comparison
+                .groups
+                .iter()
+                .map(|(.., deviation)| number(*deviation))
+                .collect::<Vec<String>>(),
+        ),
+    ])
+    .unwrap();
+    let heading = format!(
+        "## {title} \n\n依「{}」分組比較「{}」的平均數。\n\n{}\n\n",
+        comparison.group,
+        comparison.metric,
+        table(&group_table, analysis.table_style)
+    );
+    let test = match &comparison.test {
+        Ok(test) => test,
+        Err(reason) => return format!("{heading}組間比較：{reason}\n\n"),
+    };
+    let method = if test.statistic_name == "t" {
+        "獨立樣本 t 檢定"
+    } else {
+        "單因子變異數分析"
+    };
+    let degrees_of_freedom = test
+        .degrees_of_freedom
+        .iter()
+        .map(f64::to_string)
+        .collect::<Vec<String>>()
+        .join(", ");
+    let levene = test.levene.map_or_else(
+        || String::from("Levene 檢定：無法計算"),
+        |(statistic, p_value)| {
+            let note = if p_value < analysis.alpha {
+                "，各組變異數不相等，等變異數假設可能不成立"
+            } else {
+                ""
+            };
+            format!(
+                "Levene 檢定：W = {}，p value = {}{note}",
+                number(statistic),
+                number(p_value)
+            )
+        },
+    );
+    format!(
+        "{heading}{method}：{}({degrees_of_freedom}) = {}，p value = {}，η² = {}，ω² = {}\n\n{levene}\n\n",
+        test.statistic_name,
+        number(test.statistic),
+        number(test.p_value),
+        number(test.eta_squared),
+        number(test.omega_squared)
+    )
+}
+
+fn regression_section(title: &str, regression: &Regression, analysis: &Analysis) -> String {
+    let fit = match &regression.fit {
+        Ok(fit) => fit,
+        Err(reason) => return format!("## {title} \n\n迴歸分析：{reason}\n\n"),
+    };
+    let number = |value: f64| number(value, analysis);
+    let column = |statistic: fn(&regression::Coefficient) -> f64| {
+        fit.coefficients
+            .iter()
+            .map(|coefficient| number(statistic(coefficient)))
+            .collect::<Vec<String>>()
+    };
+    let coefficient_table = DataFrame::new(vec![
+        Series::new(
+            "變數",
+            once("（截距）")
+                .chain(regression.predictors.iter().map(String::as_str))
+                .collect::<Vec<&str>>(),
+        ),
+        Series::new("係數", column(|coefficient| coefficient.estimate)),
+        Series::new("標準誤", column(|coefficient| coefficient.standard_error)),
+        Series::new("t", column(|coefficient| coefficient.t)),
+        Series::new("p value", column(|coefficient| coefficient.p_value)),
+    ])
+    .unwrap();
+    format!(
+        "## {title} \n\n依變數：{}，{} 筆完整資料。\n\n{}\n\nR² = {}，調整後 R² = {}，F({}, {}) = {}，p value = {}\n\n",
+        regression.dependent,
+        fit.n,
+        table(&coefficient_table, analysis.table_style),
+        number(fit.r_squared),
+        number(fit.adjusted_r_squared),
+        fit.degrees_of_freedom.0,
+        fit.degrees_of_freedom.1,
+        number(fit.f),
+        number(fit.p_value)
+    )
+}
+
+fn condition_number_section(title: &str, condition_number: f64) -> String {
+    if condition_number.is_finite() {
+        format!("## {title} \n\n因子分析變數之相關矩陣的條件數（最大與最小特徵值之比）為 {condition_number:.2}。\n\n")
+    } else {
+        format!("## {title} \n\n因子分析變數之相關矩陣為奇異矩陣，條件數無限大。\n\n")
+    }
+}
+
+/// KMO and Bartlett's test of the data the factor analysis was fitted to, if it was fitted to
+/// the data.
+fn adequacy_section(title: &str, analysis: &Analysis) -> String {
+    let Some(Ok(FactorSolution {
+        adequacy: Some(adequacy),
+        ..
+    })) = &analysis.factor
+    else {
+        return String::new();
+    };
+    format!(
+        "## {title} \n\nKMO = {}\n\nBartlett 球形檢定：χ²({}) = {}，p value = {}\n\n",
+        number(adequacy.kmo, analysis),
+        adequacy.degrees_of_freedom,
+        number(adequacy.chi_square, analysis),
+        number(adequacy.p_value, analysis)
+    )
+}
+
+fn factor_analysis_section(
+    title: &str,
+    factor_analysis: &Result<FactorSolution, PythonError>,
+    analysis: &Analysis,
+) -> String {
+    let style = analysis.table_style;
+    let loading_table = if analysis.group_loadings {
+        grouped_loading_table
+    } else {
+        loading_table
+    };
+    match factor_analysis {
+        Ok(solution) => format!(
+            "## {title} \n\n{}\n\n### 共同性\n\n{}\n\n### 解釋變異量\n\n{}\n\n### 特徵值\n\n{}\n\n",
+            table(
+                &localize(
+                    &loading_table(
+                        &solution.loadings,
+                        analysis.loading_cutoff,
+                        analysis.precision
+                    ),
+                    analysis
+                ),
+                style
+            ),
+            table(&localize(&communality_table(solution), analysis), style),
+            table(&localize(&factor_variance_table(solution), analysis), style),
+            table(&localize(&eigenvalue_table(solution), analysis), style)
+        ),
+        Err(error) => format!("## {title} \n\n因子分析失敗：{error}\n\n"),
+    }
+}
+
+fn loading_table(loadings: &DataFrame, loading_cutoff: Option<f64>, precision: usize) -> DataFrame {
+    let Some(loading_cutoff) = loading_cutoff else {
+        return loadings.clone();
+    };
+    DataFrame::new(
+        loadings
+            .get_columns()
+            .iter()
+            .map(|column| {
+                Series::new(
+                    column.name(),
+                    column
+                        .f64()
+                        .unwrap()
+                        .into_iter()
+                        .map(|loading| {
+                            loading
+                                .filter(|loading| loading.abs() >= loading_cutoff)
+                                .map(|loading| format!("{loading:.precision$}"))
+                                .unwrap_or_default()
+                        })
+                        .collect::<Vec<String>>(),
+                )
+            })
+            .collect::<Vec<Series>>(),
+    )
+    .unwrap()
+}
+
+/// One row per item instead of per factor: every item is assigned to the factor it loads on most
+/// strongly, and each factor's items follow a header row in descending order of that loading.
+fn grouped_loading_table(
+    loadings: &DataFrame,
+    loading_cutoff: Option<f64>,
+    precision: usize,
+) -> DataFrame {
+    let items = loadings
+        .get_columns()
+        .iter()
+        .map(|column| {
+            let loadings = column
+                .f64()
+                .unwrap()
+                .into_no_null_iter()
+                .collect::<Vec<f64>>();
+            let dominant = (0..loadings.len())
+                .max_by(|&a, &b| loadings[a].abs().total_cmp(&loadings[b].abs()))
+                .unwrap_or_default();
+            (column.name(), dominant, loadings)
+        })
+        .collect::<Vec<_>>();
+    let factors = loadings.height();
+    let format_loading = |loading: f64| {
+        if loading_cutoff.is_some_and(|loading_cutoff| loading.abs() < loading_cutoff) {
+            String::new()
+        } else {
+            format!("{loading:.precision$}")
+        }
+    };
+    let mut rows = Vec::new();
+    for factor in 0..factors {
+        let mut group = items
+            .iter()
+            .filter(|(_, dominant, _)| *dominant == factor)
+            .collect::<Vec<_>>();
+        if group.is_empty() {
+            continue;
+        }
+        group.sort_by(|(_, _, a), (_, _, b)| b[factor].abs().total_cmp(&a[factor].abs()));
+        let mut header = vec![format!("**因子 {}**", factor + 1)];
+        header.resize(factors + 1, String::new());
+        rows.push(header);
+        for (name, _, loadings) in group {
+            let mut row = vec![(*name).to_owned()];
+            row.extend(loadings.iter().map(|loading| format_loading(*loading)));
+            rows.push(row);
+        }
+    }
+    let mut columns = vec![Series::new(
+        "欄位",
+        rows.iter()
+            .map(|row| row[0].as_str())
+            .collect::<Vec<&str>>(),
+    )];
+    columns.extend((1..=factors).map(|factor| {
+        Series::new(
+            &format!("因子 {factor}"),
+            rows.iter()
+                .map(|row| row[factor].as_str())
+                .collect::<Vec<&str>>(),
+        )
+    }));
+    DataFrame::new(columns).unwrap()
+}
+
+fn communality_table(solution: &FactorSolution) -> DataFrame {
+    DataFrame::new(vec![
+        Series::new("欄位", solution.loadings.get_column_names()),
+        Series::new("共同性", &solution.communalities),
+    ])
+    .unwrap()
+}
+
+fn eigenvalue_table(solution: &FactorSolution) -> DataFrame {
+    DataFrame::new(vec![
+        Series::new(
+            "成分",
+            (1..=solution.eigenvalues.len() as u64).collect::<Vec<u64>>(),
+        ),
+        Series::new("特徵值", &solution.eigenvalues),
+    ])
+    .unwrap()
+}
+
+fn factor_variance_table(solution: &FactorSolution) -> DataFrame {
+    let mut columns = vec![Series::new(
+        "",
+        ["平方和負荷量", "解釋變異比例", "累積解釋變異比例"],
+    )];
+    let factors = solution.variance.first().map_or(0, Vec::len);
+    for factor in 0..factors {
+        columns.push(Series::new(
+            &format!("因子 {}", factor + 1),
+            solution
+                .variance
+                .iter()
+                .map(|row| row[factor])
+                .collect::<Vec<f64>>(),
+        ));
+    }
+    DataFrame::new(columns).unwrap()
+}
+
+fn significance_differences(
+    names: &[String],
+    cells: &[Vec<(&CorrelationValue, &CorrelationValue)>],
+    label: &str,
+    compare_label: &str,
+) -> String {
+    let mut differences = String::new();
+    for (index, values) in cells.iter().enumerate() {
+        for (other_index, (value, compare_value)) in values.iter().enumerate().skip(index + 1) {
+            if value.is_significant() == compare_value.is_significant() {
+                continue;
+            }
+            let (significant, not_significant) = if value.is_significant() {
+                (label, compare_label)
+            } else {
+                (compare_label, label)
+            };
+            writeln!(
+                differences,
+                "- {} × {}：於 {significant} 顯著，於 {not_significant} 不顯著",
+                names[index], names[other_index]
+            )
+            .unwrap();
+        }
+    }
+    differences
+}
+
+fn warning_section(title: &str, warnings: &[String]) -> String {
+    let mut section = format!("## {title}\n\n");
+    for warning in warnings {
+        writeln!(section, "- {warning}").unwrap();
+    }
+    section.push('\n');
+    section
+}
+
+/// Spearman-Brown's prophecy: the reliability of a scale `factor` times as long as one with
+/// reliability `alpha`.
+fn spearman_brown(alpha: f64, factor: f64) -> f64 {
+    factor * alpha / (factor - 1.0).mul_add(alpha, 1.0)
+}
+
+fn reliability_table(reliability: &[Reliability], prophecy_factor: Option<f64>) -> DataFrame {
+    let mut columns = vec![
+        Series::new(
+            "構面",
+            reliability
+                .iter()
+                .map(|reliability| reliability.group.clone())
+                .collect::<Vec<String>>(),
+        ),
+        Series::new(
+            "題數",
+            reliability
+                .iter()
+                .map(|reliability| reliability.items as u64)
+                .collect::<Vec<u64>>(),
+        ),
+        Series::new(
+            "Cronbach's α",
+            reliability
+                .iter()
+                .map(|reliability| reliability.alpha)
+                .collect::<Vec<f64>>(),
+        ),
+        Series::new(
+            "McDonald's ω",
+            reliability
+                .iter()
+                .map(|reliability| {
+                    reliability
+                        .omega
+                        .as_ref()
+                        .and_then(|omega| omega.as_ref().ok())
+                        .copied()
+                })
+                .collect::<Vec<Option<f64>>>(),
+        ),
+    ];
+    if let Some(factor) = prophecy_factor {
+        columns.push(Series::new(
+            &format!("Spearman-Brown（{factor} 倍題數）"),
+            reliability
+                .iter()
+                .map(|reliability| spearman_brown(reliability.alpha, factor))
+                .collect::<Vec<f64>>(),
+        ));
+    }
+    DataFrame::new(columns).unwrap()
+}
+
+/// Replaced by the table's title in `--caption`.
+const TITLE_PLACEHOLDER: &str = "{title}";
+
+/// A correlation table between the `--caption` and the `--legend`.
+fn captioned_section(title: &str, table: &str, analysis: &Analysis) -> String {
+    format!(
+        "## {title} \n\n{}\n\n{table}\n\n{}\n\n",
+        analysis.caption.replace(TITLE_PLACEHOLDER, title),
+        analysis.legend
+    )
+}
+
+fn correlation_section(title: &str, matrix: &CorrelationMatrix, analysis: &Analysis) -> String {
+    let mut section = captioned_section(
+        title,
+        &paged_table(
+            &matrix_table(&matrix.names, &matrix.names, &matrix.cells, |value| {
+                format!("{value}")
+            }),
+            1,
+            analysis,
+        ),
+        analysis,
+    );
+    let suppressed = matrix
+        .upper_triangle()
+        .filter(|(_, _, value)| matches!(value, CorrelationValue::InsufficientSample))
+        .count();
+    if suppressed > 0 {
+        writeln!(section, "共 {suppressed} 個相關係數因樣本不足而未計算。\n").unwrap();
+    }
+    section.push_str(&pair_count_notes(title, matrix.upper_triangle(), analysis));
+    section
+}
+
+/// Lists the `--spearman-detail` tie proportions of the fields in `matrix`, if any.
+fn tie_notes(title: &str, matrix: &CorrelationMatrix, analysis: &Analysis) -> String {
+    let mut notes = String::new();
+    for (name, distinct, proportion) in &analysis.tie_proportions {
+        if matrix.names.contains(name) {
+            writeln!(
+                notes,
+                "- {name}：共 {distinct} 種數值，{:.1}% 的觀察值與其他觀察值同分",
+                proportion * 100.0
+            )
+            .unwrap();
+        }
+    }
+    if notes.is_empty() {
+        notes
+    } else {
+        format!(
+            "### {title} 同分比例\n\n{notes}\nscipy 的 `spearmanr` 將同分的觀察值給予其等級的平均（average rank）後再計算 Pearson 相關；同分比例越高，等級能區分的程度越低，可考慮改以多分格相關分析。\n\n"
+        )
+    }
+}
+
+/// Lists the pair counts of the cells computed with `--kendall-detail`, if any.
+fn pair_count_notes<'a>(
+    title: &str,
+    pairs: impl Iterator<Item = (&'a str, &'a str, &'a CorrelationValue)>,
+    analysis: &Analysis,
+) -> String {
+    let mut notes = String::new();
+    for (name, other_name, value) in pairs {
+        let CorrelationValue::Valid(result) = value else {
+            continue;
+        };
+        let Some(counts) = result.pair_counts else {
+            continue;
+        };
+        writeln!(
+            notes,
+            "- {name} × {other_name}：一致 {} 對、不一致 {} 對、僅 {name} 同分 {} 對、僅 {other_name} 同分 {} 對、兩者皆同分 {} 對，原生計算 τ = {}",
+            counts.concordant, counts.discordant, counts.x_ties, counts.y_ties, counts.joint_ties, number(counts.tau, analysis)
+        )
+        .unwrap();
+    }
+    if notes.is_empty() {
+        notes
+    } else {
+        format!("### {title} 配對計數\n\n{notes}\n")
+    }
+}
+
+/// APA tables drop the leading zero of values that cannot exceed one and star the p-value.
+fn apa_correlation_section(title: &str, matrix: &CorrelationMatrix, analysis: &Analysis) -> String {
+    let moments = &analysis.moments;
+    let apa_number = |value: f64, locale: NumberLocale| {
+        let decimal = locale.separators().1;
+        format_number(value, 2, locale).replacen(&format!("0{decimal}"), decimal, 1)
+    };
+    let size = matrix.names.len();
+    let mut columns = vec![
+        Series::new(
+            "變數",
+            matrix
+                .names
+                .iter()
+                .enumerate()
+                .map(|(index, name)| format!("{}. {name}", index + 1))
+                .collect::<Vec<String>>(),
+        ),
+        Series::new(
+            "M",
+            matrix
+                .names
+                .iter()
+                .map(|name| format!("{:.2}", moments[name].0))
+                .collect::<Vec<String>>(),
+        ),
+        Series::new(
+            "SD",
+            matrix
+                .names
+                .iter()
+                .map(|name| format!("{:.2}", moments[name].1))
+                .collect::<Vec<String>>(),
+        ),
+    ];
+    columns.extend((0..size.saturating_sub(1)).map(|column| {
+        Series::new(
+            &(column + 1).to_string(),
+            (0..size)
+                .map(|row| match &matrix.cells[row][column] {
+                    _ if row < column => String::new(),
+                    _ if row == column => String::from("—"),
+                    CorrelationValue::Valid(result) => {
+                        let stars = match result.tested_p_value() {
+                            p_value if p_value < 0.001 => "***",
+                            p_value if p_value < 0.01 => "**",
+                            p_value if p_value < 0.05 => "*",
+                            _ => "",
+                        };
+                        format!("{}{stars}", apa_number(result.r, result.locale))
+                    }
+                    value => value.to_string(),
+                })
+                .collect::<Vec<String>>(),
+        )
+    }));
+    captioned_section(
+        title,
+        &paged_table(&DataFrame::new(columns).unwrap(), 3, analysis),
+        analysis,
+    )
+}
+
+/// Renders the columns after the first `labels` in `--page-size` blocks, the way wide
+/// spreadsheets print, repeating the label columns in every block.
+fn paged_table(dataframe: &DataFrame, labels: usize, analysis: &Analysis) -> String {
+    let columns = dataframe.get_columns();
+    let (label_columns, value_columns) = columns.split_at(labels);
+    let Some(page_size) = analysis
+        .page_size
+        .filter(|&page_size| value_columns.len() > page_size)
+    else {
+        return table(dataframe, analysis.table_style);
+    };
+    value_columns
+        .chunks(page_size)
+        .enumerate()
+        .map(|(page, page_columns)| {
+            let first = page * page_size + 1;
+            let block = DataFrame::new(label_columns.iter().chain(page_columns).cloned().collect())
+                .unwrap();
+            format!(
+                "第 {first}–{} 欄（共 {} 欄）\n\n{}",
+                first + page_columns.len() - 1,
+                value_columns.len(),
+                table(&block, analysis.table_style)
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n\n")
+}
+
+fn row_selection_notes(subject: &str, analysis: &Analysis) -> Vec<String> {
+    let mut notes = Vec::new();
+    if let Some(removed) = analysis.duplicates {
+        notes.push(format!(
+            "> {subject}已移除 {removed} 筆重複的資料，各組重複資料只保留第一筆。\n\n"
+        ));
+    }
+    if let Some((filter, kept, total)) = &analysis.filter {
+        notes.push(format!(
+            "> {subject}以篩選條件 `` {filter} `` 保留 {kept} 筆資料（共 {total} 筆）。\n\n"
+        ));
+    }
+    if let Some((size, total)) = analysis.sample {
+        notes.push(format!(
+            "> {subject}以隨機抽出的 {size} 筆資料（共 {total} 筆）為基礎。\n\n"
+        ));
+    }
+    if analysis.offline {
+        notes.push(format!(
+            "> {subject}以離線模式產生，需要 Python 的相關係數、McDonald's ω 與因子分析皆已略過。\n\n"
+        ));
+    }
+    if analysis.standardized {
+        notes.push(format!(
+            "> {subject}的各欄位已先標準化為 z 分數再進行計算。\n\n"
+        ));
+    }
+    if let Some((column, method)) = &analysis.detrend {
+        let method = match method {
+            DetrendMethod::Difference => "取一階差分",
+            DetrendMethod::Linear => "去除線性趨勢",
+        };
+        notes.push(format!(
+            "> {subject}的相關係數、信度與因子分析以依 {column} 排序並{method}的資料計算，敘述統計仍為原始資料。\n\n"
+        ));
+    }
+    if let Some(version) = &analysis.scipy_version {
+        notes.push(format!(
+            "> {subject}以 scipy {version} 的 `pearsonr`、`spearmanr` 與 `kendalltau`（雙尾檢定，`kendalltau` 為 `method=\"auto\"`）計算相關係數。\n\n"
+        ));
+    }
+    notes
+}
+
+pub fn render_comparison(
+    (label, analysis): (&str, &Analysis),
+    (compare_label, compare_analysis): (&str, &Analysis),
+    out: &mut impl io::Write,
+) -> io::Result<()> {
+    for (label, analysis) in [(label, analysis), (compare_label, compare_analysis)] {
+        for note in row_selection_notes(&format!("{label} "), analysis) {
+            out.write_all(note.as_bytes())?;
+        }
+    }
+    for (label, analysis) in [(label, analysis), (compare_label, compare_analysis)] {
+        if !analysis.warnings.is_empty() {
+            out.write_all(
+                warning_section(&format!("警告 ({label})"), &analysis.warnings).as_bytes(),
+            )?;
+        }
+    }
+    let style = analysis.table_style;
+    if let (Some(description), Some(compare_description)) =
+        (&analysis.description, &compare_analysis.description)
+    {
+        let weighted_description = analysis
+            .weighted_description
+            .as_ref()
+            .zip(compare_analysis.weighted_description.as_ref())
+            .map(|(weighted, compare_weighted)| {
+                compare_descriptions((label, weighted), (compare_label, compare_weighted))
+            });
+        description_sections(
+            out,
+            analysis,
+            &compare_descriptions((label, description), (compare_label, compare_description)),
+            weighted_description.as_ref(),
+        )?;
+    }
+
+    for ((coefficient, matrix), (_, compare_matrix)) in analysis
+        .matrices()
+        .into_iter()
+        .zip(compare_analysis.matrices())
+    {
+        let (Some(matrix), Some(compare_matrix)) = (matrix, compare_matrix) else {
+            continue;
+        };
+        let title = matrix_title(analysis, coefficient, matrix.comparisons);
+        let cells = matrix
+            .cells
+            .iter()
+            .zip(&compare_matrix.cells)
+            .map(|(values, compare_values)| values.iter().zip(compare_values).collect())
+            .collect::<Vec<Vec<_>>>();
+        let matrix_block = matrix_table(
+            &matrix.names,
+            &matrix.names,
+            &cells,
+            |(value, compare_value)| {
+                format!("{label}:<br>{value}<br>{compare_label}:<br>{compare_value}")
+            },
+        );
+        out.write_all(
+            captioned_section(&title, &table(&matrix_block, style), analysis).as_bytes(),
+        )?;
+
+        let differences = significance_differences(&matrix.names, &cells, label, compare_label);
+        if !differences.is_empty() {
+            out.write_all(format!("### {title} 顯著性差異\n\n{differences}\n").as_bytes())?;
+        }
+        explain(out, analysis, correlation_explanation(coefficient))?;
+    }
+
+    for (label, analysis) in [(label, analysis), (compare_label, compare_analysis)] {
+        if !analysis.reliability.is_empty() {
+            let reliability = reliability_table(&analysis.reliability, analysis.spearman_brown);
+            out.write_all(
+                table_section(&format!("信度分析 ({label})"), &reliability, style).as_bytes(),
+            )?;
+            explain(out, analysis, RELIABILITY_EXPLANATION)?;
+        }
+    }
+    for (label, analysis) in [(label, analysis), (compare_label, compare_analysis)] {
+        diagnostic_sections(out, analysis, &format!(" ({label})"))?;
+    }
+    for (label, analysis) in [(label, analysis), (compare_label, compare_analysis)] {
+        let title = format!("因子分析適切性 ({label})");
+        out.write_all(adequacy_section(&title, analysis).as_bytes())?;
+        if let Some(factor_analysis) = &analysis.factor {
+            out.write_all(
+                factor_analysis_section(&format!("因子分析 ({label})"), factor_analysis, analysis)
+                    .as_bytes(),
+            )?;
+            explain(out, analysis, FACTOR_EXPLANATION)?;
+        }
+    }
+    for (label, analysis) in [(label, analysis), (compare_label, compare_analysis)] {
+        let python_warnings = analysis.python_warnings();
+        if !python_warnings.is_empty() {
+            out.write_all(
+                warning_section(&format!("Python 警告 ({label})"), &python_warnings).as_bytes(),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// With `--weights` the unweighted and weighted descriptions are shown one after the other, each
+/// labeled, so neither is mistaken for the other.
+fn description_sections(
+    out: &mut impl io::Write,
+    analysis: &Analysis,
+    description: &DataFrame,
+    weighted_description: Option<&DataFrame>,
+) -> io::Result<()> {
+    let style = analysis.table_style;
+    let description = table(&localize(description, analysis), style);
+    match (weighted_description, &analysis.weights) {
+        (Some(weighted_description), Some(weights)) => {
+            let weighted_description = table(&localize(weighted_description, analysis), style);
+            out.write_all(
+                format!(
+                    "## 敘述統計（未加權）\n\n{description}\n\n## 敘述統計（以 {weights} 加權）\n\n{weighted_description}\n\n"
+                )
+                .as_bytes(),
+            )?;
+        }
+        _ => out.write_all(format!("## 敘述統計\n\n{description}\n\n").as_bytes())?,
+    }
+    explain(out, analysis, DESCRIBE_EXPLANATION)
+}
+
+/// The label of the missing values in the frequency tables and crosstabs.
+const MISSING_LABEL: &str = "(缺失)";
+
+fn frequency_sections(out: &mut impl io::Write, analysis: &Analysis) -> io::Result<()> {
+    if analysis.frequencies.is_empty() && analysis.crosstabs.is_empty() {
+        return Ok(());
+    }
+    out.write_all("## 次數分配 \n\n".as_bytes())?;
+    for frequency in &analysis.frequencies {
+        let table = table(&frequency_table(frequency), analysis.table_style);
+        out.write_all(format!("### {} \n\n{table}\n\n", frequency.field).as_bytes())?;
+    }
+    for crosstab in &analysis.crosstabs {
+        let table = table(&crosstab_table(crosstab), analysis.table_style);
+        out.write_all(
+            format!(
+                "### {} × {} \n\n{table}\n\n",
+                crosstab.rows, crosstab.columns
+            )
+            .as_bytes(),
+        )?;
+    }
+    explain(out, analysis, FREQUENCY_EXPLANATION)
+}
+
+fn level_label(level: Option<&String>) -> &str {
+    level.map_or(MISSING_LABEL, String::as_str)
+}
+
+fn frequency_table(frequency: &Frequency) -> DataFrame {
+    let total = frequency.total();
+    DataFrame::new(vec![
+        Series::new(
+            "類別",
+            frequency
+                .counts
+                .iter()
+                .map(|(level, _)| level_label(level.as_ref()))
+                .collect::<Vec<&str>>(),
+        ),
+        Series::new(
+            "次數",
+            frequency
+                .counts
+                .iter()
+                .map(|(_, count)| *count as u64)
+                .collect::<Vec<u64>>(),
+        ),
+        Series::new(
+            "百分比",
+            frequency
+                .counts
+                .iter()
+                .map(|(_, count)| format!("{:.1}%", *count as f64 / total as f64 * 100.0))
+                .collect::<Vec<String>>(),
+        ),
+    ])
+    .unwrap()
+}
+
+/// The counts with a 總計 row and column.
+fn crosstab_table(crosstab: &Crosstab) -> DataFrame {
+    let mut labels = crosstab
+        .row_levels
+        .iter()
+        .map(|level| level_label(level.as_ref()))
+        .collect::<Vec<&str>>();
+    labels.push("總計");
+    let mut columns = vec![Series::new(
+        &format!("{} \\ {}", crosstab.rows, crosstab.columns),
+        labels,
+    )];
+    let total_row = |counts: Vec<u64>| {
+        let total = counts.iter().sum();
+        counts.into_iter().chain(once(total)).collect::<Vec<u64>>()
+    };
+    for (index, level) in crosstab.column_levels.iter().enumerate() {
+        columns.push(Series::new(
+            level_label(level.as_ref()),
+            total_row(
+                crosstab
+                    .counts
+                    .iter()
+                    .map(|counts| counts[index] as u64)
+                    .collect(),
+            ),
+        ));
+    }
+    columns.push(Series::new(
+        "總計",
+        total_row(
+            crosstab
+                .counts
+                .iter()
+                .map(|counts| counts.iter().sum::<usize>() as u64)
+                .collect(),
+        ),
+    ));
+    DataFrame::new(columns).unwrap()
+}
+
+fn compare_descriptions(
+    (label, description): (&str, &DataFrame),
+    (compare_label, compare_description): (&str, &DataFrame),
+) -> DataFrame {
+    let mut description_columns = Vec::new();
+    for (series, compare_series) in description
+        .get_columns()
+        .iter()
+        .zip(compare_description.get_columns())
+    {
+        if series.name() == "describe" {
+            description_columns.push(series.clone());
+        } else {
+            let name = series.name().to_owned();
+            description_columns.push(series.clone().rename(&format!("{name} ({label})")).clone());
+            description_columns.push(
+                compare_series
+                    .clone()
+                    .rename(&format!("{name} ({compare_label})"))
+                    .clone(),
+            );
+        }
+    }
+    DataFrame::new(description_columns).unwrap()
+}
+
+/// The rendering time is measured up to this section, which is written last.
+pub fn timing_section(
+    timings: &[(String, Duration)],
+    rendering: Duration,
+    style: TableStyle,
+) -> String {
+    let steps = timings
+        .iter()
+        .map(|(step, _)| step.as_str())
+        .chain(once("產生報告"))
+        .collect::<Vec<&str>>();
+    let seconds = timings
+        .iter()
+        .map(|(_, duration)| duration)
+        .chain(once(&rendering))
+        .map(|duration| format!("{:.3}", duration.as_secs_f64()))
+        .collect::<Vec<String>>();
+    let timing_table = DataFrame::new(vec![
+        Series::new("步驟", steps),
+        Series::new("秒數", seconds),
+    ])
+    .unwrap();
+    format!("## 執行時間\n\n{}\n", table(&timing_table, style))
+}
+
+pub fn json_report(source_file_name: &str, analysis: &Analysis) -> JsonReport {
+    let pairs = analysis.correlation_pairs();
+    let mut variables = Vec::new();
+    for (name, other_name, _, _) in &pairs {
+        for name in [name, other_name] {
+            if !variables.iter().any(|variable| variable == name) {
+                variables.push((*name).to_owned());
+            }
+        }
+    }
+    let correlations = pairs
+        .into_iter()
+        .map(|(name, other_name, coefficient, value)| {
+            let result = match value {
+                CorrelationValue::Valid(result) => Some(result),
+                _ => None,
+            };
+            JsonCorrelation {
+                method: coefficient.to_owned(),
+                var1: name.to_owned(),
+                var2: other_name.to_owned(),
+                r: result.map(|result| result.r),
+                p_value: result.map(|result| result.p_value),
+                adjusted_p_value: result.and_then(|result| result.adjusted_p_value),
+                n: result.map(|result| result.n),
+                significant: value.is_significant(),
+                pair_counts: result.and_then(|result| result.pair_counts),
+                standard_error: result.and_then(|result| result.standard_error),
+                ci_low: result.and_then(|result| result.ci_low),
+                ci_high: result.and_then(|result| result.ci_high),
+                chi_square: result
+                    .and_then(|result| result.chi_square)
+                    .map(|(statistic, _)| statistic),
+                degrees_of_freedom: result
+                    .and_then(|result| result.chi_square)
+                    .map(|(_, df)| df),
+            }
+        })
+        .collect();
+    let factor_loadings = match &analysis.factor {
+        Some(Ok(solution)) => solution
+            .loadings
+            .get_columns()
+            .iter()
+            .map(|column| {
+                (
+                    column.name().to_owned(),
+                    column.f64().unwrap().into_no_null_iter().collect(),
+                )
+            })
+            .collect(),
+        _ => BTreeMap::new(),
+    };
+    JsonReport {
+        source: source_file_name.to_owned(),
+        variables,
+        correlations,
+        descriptives: analysis
+            .description
+            .as_ref()
+            .map(json_descriptives)
+            .unwrap_or_default(),
+        factor_loadings,
+        scipy_version: analysis.scipy_version.clone(),
+    }
+}
+
+/// The `describe` table turned around: one entry per numeric field, mapping each statistic to
+/// its value. Text fields and missing statistics are left out.
+fn json_descriptives(description: &DataFrame) -> BTreeMap<String, BTreeMap<String, f64>> {
+    let Ok(statistics) = description.column("describe").and_then(Series::utf8) else {
+        return BTreeMap::new();
+    };
+    description
+        .get_columns()
+        .iter()
+        .filter_map(|column| {
+            let values = column.f64().ok()?;
+            let statistics = statistics
+                .into_no_null_iter()
+                .zip(values)
+                .filter_map(|(statistic, value)| Some((statistic.to_owned(), value?)))
+                .collect();
+            Some((column.name().to_owned(), statistics))
+        })
+        .collect()
+}
+
+/// The average ranks scipy's `spearmanr` uses, for every non-nominal field.
+pub fn write_ranks(
+    file_name: &str,
+    data: (&[Vec<f64>], &[Vec<bool>]),
+    fields: &[&Field],
+) -> Result<(), AnalysisError> {
+    write_csv(Path::new(file_name), &mut ranks(data, fields))
+}
+
+/// The average ranks of the observed values of every non-nominal field; missing values stay
+/// empty instead of tying for the lowest rank.
+fn ranks((processed_data, observed): (&[Vec<f64>], &[Vec<bool>]), fields: &[&Field]) -> DataFrame {
+    DataFrame::new(
+        fields
+            .iter()
+            .zip(processed_data.iter().zip(observed))
+            .filter(|(field, _)| field.scale != Scale::Nominal)
+            .map(|(field, (data, observed))| {
+                let values = data
+                    .iter()
+                    .zip(observed)
+                    .map(|(value, &observed)| observed.then_some(*value))
+                    .collect::<Vec<Option<f64>>>();
+                Series::new(&field.name, values).rank(
+                    RankOptions {
+                        method: RankMethod::Average,
+                        descending: false,
+                    },
+                    None,
+                )
+            })
+            .collect::<Vec<Series>>(),
+    )
+    .unwrap()
+}
+
+/// One row per observation, so a single correlation can be recomputed elsewhere.
+pub fn write_pair(
+    file_name: &str,
+    (var1, x): (&str, &[f64]),
+    (var2, y): (&str, &[f64]),
+) -> Result<(), AnalysisError> {
+    let mut dataframe = DataFrame::new(vec![
+        Series::new("row", (1..=x.len() as u64).collect::<Vec<u64>>()),
+        Series::new(var1, x),
+        Series::new(var2, y),
+    ])
+    .unwrap();
+    write_csv(Path::new(file_name), &mut dataframe)
+}
+
+pub fn write_tidy(file_name: &str, analysis: &Analysis) -> Result<(), AnalysisError> {
+    let mut var1 = Vec::new();
+    let mut var2 = Vec::new();
+    let mut coefficient_type = Vec::new();
+    let mut r = Vec::new();
+    let mut p = Vec::new();
+    let mut n = Vec::new();
+    for (name, other_name, coefficient, value) in analysis.correlation_pairs() {
+        var1.push(name);
+        var2.push(other_name);
+        coefficient_type.push(coefficient);
+        if let CorrelationValue::Valid(result) = value {
+            r.push(Some(result.r));
+            p.push(Some(result.p_value));
+            n.push(u64::try_from(result.n).ok());
+        } else {
+            r.push(None);
+            p.push(None);
+            n.push(None);
+        }
+    }
+    let mut dataframe = DataFrame::new(vec![
+        Series::new("var1", var1),
+        Series::new("var2", var2),
+        Series::new("coefficient_type", coefficient_type),
+        Series::new("r", r),
+        Series::new("p", p),
+        Series::new("n", n),
+    ])
+    .unwrap();
+    write_csv(Path::new(file_name), &mut dataframe)
+}
+
+/// Writes each computed correlation matrix for `--format csv` to the path `path` gives for its
+/// name in [`CSV_MATRICES`].
+pub fn write_matrices(
+    path: impl Fn(&str) -> PathBuf,
+    analysis: &Analysis,
+) -> Result<(), AnalysisError> {
+    let [pearson, ordinal, nominal] = analysis.matrices().map(|(_, matrix)| {
+        matrix.as_ref().map(|matrix| {
+            let cells = matrix
+                .cells
+                .iter()
+                .map(|values| values.iter().collect())
+                .collect::<Vec<Vec<&CorrelationValue>>>();
+            matrix_csv_table(&matrix.names, &matrix.names, &cells)
+        })
+    });
+    let [cross, point_biserial] = [&analysis.cross, &analysis.point_biserial].map(|cross| {
+        cross.as_ref().map(|cross| {
+            let cells = cross
+                .cells
+                .iter()
+                .map(|values| values.iter().map(|(_, value)| value).collect())
+                .collect::<Vec<Vec<&CorrelationValue>>>();
+            matrix_csv_table(&cross.row_names, &cross.column_names, &cells)
+        })
+    });
+    let tables = [pearson, ordinal, nominal, cross, point_biserial];
+    for (matrix, table) in CSV_MATRICES.into_iter().zip(tables) {
+        let Some(mut table) = table else {
+            continue;
+        };
+        let file_name = path(matrix);
+        write_csv(&file_name, &mut table)?;
+    }
+    Ok(())
+}
+
+/// Each row variable takes an `r` row and a `p` row, left empty wherever there is no valid
+/// correlation. `cells` is indexed by column and then by row.
+fn matrix_csv_table(
+    row_names: &[String],
+    column_names: &[String],
+    cells: &[Vec<&CorrelationValue>],
+) -> DataFrame {
+    let statistics = |value: &CorrelationValue| match value {
+        CorrelationValue::Valid(result) => [Some(result.r), Some(result.p_value)],
+        CorrelationValue::Diagonal => [Some(1.0), None],
+        _ => [None, None],
+    };
+    let mut series_vec = vec![
+        Series::new(
+            "",
+            row_names
+                .iter()
+                .flat_map(|name| [name.as_str(); 2])
+                .collect::<Vec<&str>>(),
+        ),
+        Series::new(
+            "statistic",
+            row_names
+                .iter()
+                .flat_map(|_| ["r", "p"])
+                .collect::<Vec<&str>>(),
+        ),
+    ];
+    series_vec.extend(column_names.iter().zip(cells).map(|(name, values)| {
+        Series::new(
+            name,
+            values
+                .iter()
+                .flat_map(|value| statistics(value))
+                .collect::<Vec<Option<f64>>>(),
+        )
+    }));
+    DataFrame::new(series_vec).unwrap()
+}
+
+fn write_csv(file_name: &Path, dataframe: &mut DataFrame) -> Result<(), AnalysisError> {
+    let file = File::create(file_name).map_err(|error| AnalysisError::Io {
+        file_name: file_name.display().to_string(),
+        error,
+    })?;
+    CsvWriter::new(file)
+        .finish(dataframe)
+        .map_err(|error| AnalysisError::io(file_name.display(), error))
+}
+
+/// Every variable is a node, and every significant correlation an edge labeled with its
+/// coefficient and r.
+pub fn write_network(file_name: &str, analysis: &Analysis) -> Result<(), AnalysisError> {
+    let quote = |name: &str| format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""));
+    let pairs = analysis.correlation_pairs();
+    let mut graph = String::from("graph correlations {\n");
+    let mut nodes = Vec::new();
+    for (name, other_name, _, _) in &pairs {
+        for name in [name, other_name] {
+            if !nodes.contains(name) {
+                nodes.push(name);
+                writeln!(graph, "    {};", quote(name)).unwrap();
+            }
+        }
+    }
+    for (name, other_name, coefficient, value) in &pairs {
+        let CorrelationValue::Valid(result) = value else {
+            continue;
+        };
+        if !value.is_significant() {
+            continue;
+        }
+        writeln!(
+            graph,
+            "    {} -- {} [label=\"{coefficient} {:.2}\", penwidth={:.2}, color=\"{}\"];",
+            quote(name),
+            quote(other_name),
+            result.r,
+            result.r.abs().mul_add(4.0, 1.0),
+            if result.r < 0.0 { "#d62728" } else { "#1f77b4" }
+        )
+        .unwrap();
+    }
+    graph.push_str("}\n");
+    std::fs::write(file_name, graph).map_err(|error| AnalysisError::Io {
+        file_name: file_name.to_owned(),
+        error,
+    })
+}
+
+/// Side of a heatmap cell, in pixels.
+const HEATMAP_CELL: f64 = 32.0;
+
+/// Roughly how wide `text` is at 12px: CJK characters take a full em, others about half.
+fn label_width(text: &str) -> f64 {
+    text.chars()
+        .map(|character| if character.is_ascii() { 7.0 } else { 12.0 })
+        .sum()
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Blue for positive and red for negative coefficients, fading to white at zero.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn heatmap_rgb(r: f64) -> Rgb {
+    let full = if r < 0.0 {
+        [178, 24, 43]
+    } else {
+        [33, 102, 172]
+    };
+    let weight = r.abs().min(1.0);
+    // The weighted channel lies between `full` and 255, so it always fits in a u8.
+    full.map(|full| weight.mul_add(f64::from(full) - 255.0, 255.0).round() as u8)
+}
+
+fn heatmap_color(r: f64) -> String {
+    let [red, green, blue] = heatmap_rgb(r);
+    format!("rgb({red},{green},{blue})")
+}
+
+/// The coefficient a heatmap cell is colored by, if it has one.
+const fn heatmap_r(value: &CorrelationValue) -> Option<f64> {
+    match value {
+        CorrelationValue::Valid(result) => Some(result.r),
+        CorrelationValue::Diagonal => Some(1.0),
+        _ => None,
+    }
+}
+
+/// Every computed correlation matrix as a heatmap, one below the other, in an SVG or, for a
+/// `.png` path, a PNG file. Cells without a coefficient are gray.
+pub fn write_heatmap(file_name: &str, analysis: &Analysis) -> Result<(), AnalysisError> {
+    let is_png = Path::new(file_name)
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("png"));
+    let contents = if is_png {
+        heatmap_png(analysis)
+    } else {
+        heatmap_svg(analysis).into_bytes()
+    };
+    std::fs::write(file_name, contents).map_err(|error| AnalysisError::Io {
+        file_name: file_name.to_owned(),
+        error,
+    })
+}
+
+fn heatmap_svg(analysis: &Analysis) -> String {
+    let mut blocks = String::new();
+    let mut width: f64 = 0.0;
+    let mut top = 10.0;
+    for (coefficient, matrix) in analysis.matrices() {
+        let Some(matrix) = matrix else {
+            continue;
+        };
+        let margin = matrix
+            .names
+            .iter()
+            .map(|name| label_width(name))
+            .fold(0.0, f64::max)
+            + 10.0;
+        let title = matrix_title(analysis, coefficient, matrix.comparisons);
+        writeln!(
+            blocks,
+            "<text x=\"10\" y=\"{}\" font-size=\"16\" font-weight=\"bold\">{}</text>",
+            top + 16.0,
+            escape_xml(&title)
+        )
+        .unwrap();
+        let grid_top = top + 30.0 + margin;
+        for (index, name) in matrix.names.iter().enumerate() {
+            let offset = (index as f64 + 0.5) * HEATMAP_CELL;
+            writeln!(
+                blocks,
+                "<text x=\"{}\" y=\"{}\" font-size=\"12\" text-anchor=\"end\" dominant-baseline=\"middle\">{}</text>",
+                margin,
+                grid_top + offset,
+                escape_xml(name)
+            )
+            .unwrap();
+            writeln!(
+                blocks,
+                "<text transform=\"translate({},{}) rotate(-90)\" font-size=\"12\" dominant-baseline=\"middle\">{}</text>",
+                margin + 10.0 + offset,
+                grid_top - 5.0,
+                escape_xml(name)
+            )
+            .unwrap();
+        }
+        for (row, cells) in matrix.cells.iter().enumerate() {
+            for (column, value) in cells.iter().enumerate() {
+                let r = heatmap_r(value);
+                let (x, y) = (
+                    (column as f64).mul_add(HEATMAP_CELL, margin + 10.0),
+                    (row as f64).mul_add(HEATMAP_CELL, grid_top),
+                );
+                let fill = r.map_or_else(|| String::from("#cccccc"), heatmap_color);
+                write!(
+                    blocks,
+                    "<rect x=\"{x}\" y=\"{y}\" width=\"{HEATMAP_CELL}\" height=\"{HEATMAP_CELL}\" fill=\"{fill}\" stroke=\"white\"/>"
+                )
+                .unwrap();
+                if let Some(r) = r {
+                    write!(
+                        blocks,
+                        "<text x=\"{}\" y=\"{}\" font-size=\"9\" text-anchor=\"middle\" dominant-baseline=\"middle\" fill=\"{}\">{r:.2}</text>",
+                        x + HEATMAP_CELL / 2.0,
+                        y + HEATMAP_CELL / 2.0,
+                        if r.abs() > 0.6 { "white" } else { "black" }
+                    )
+                    .unwrap();
+                }
+                blocks.push('\n');
+            }
+        }
+        let side = matrix.names.len() as f64 * HEATMAP_CELL;
+        width = width.max(margin + 20.0 + side);
+        top = grid_top + side + 20.0;
+    }
+    let legend = format!(
+        "<defs><linearGradient id=\"scale\"><stop offset=\"0\" stop-color=\"{}\"/><stop offset=\"0.5\" stop-color=\"white\"/><stop offset=\"1\" stop-color=\"{}\"/></linearGradient></defs>\n<rect x=\"10\" y=\"{top}\" width=\"200\" height=\"12\" fill=\"url(#scale)\" stroke=\"#999999\"/>\n<text x=\"10\" y=\"{}\" font-size=\"10\">−1</text><text x=\"110\" y=\"{}\" font-size=\"10\" text-anchor=\"middle\">0</text><text x=\"210\" y=\"{}\" font-size=\"10\" text-anchor=\"end\">1</text>\n",
+        heatmap_color(-1.0),
+        heatmap_color(1.0),
+        top + 26.0,
+        top + 26.0,
+        top + 26.0
+    );
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" font-family=\"sans-serif\">\n<rect width=\"100%\" height=\"100%\" fill=\"white\"/>\n{blocks}{legend}</svg>\n",
+        width.max(220.0),
+        top + 40.0
+    )
+}
+
+/// The heatmap drawn in the PNG's pixel font, which has no CJK glyphs: fields are numbered on
+/// both axes in matrix order instead of named, and the coefficient stands in for the title.
+fn heatmap_png(analysis: &Analysis) -> Vec<u8> {
+    const CELL: usize = 40;
+    const SCALE: usize = 2;
+    const PADDING: usize = 10;
+    const TEXT_HEIGHT: usize = GLYPH_HEIGHT * SCALE;
+    const WHITE: Rgb = [255, 255, 255];
+    const BLACK: Rgb = [0, 0, 0];
+    let matrices = analysis
+        .matrices()
+        .into_iter()
+        .filter_map(|(coefficient, matrix)| Some((coefficient, matrix.as_ref()?)))
+        .collect::<Vec<_>>();
+    let label_width = |count: usize| Canvas::text_width(&count.to_string(), SCALE) + PADDING;
+    let width = matrices
+        .iter()
+        .map(|(_, matrix)| {
+            let count = matrix.names.len();
+            label_width(count) + count * CELL
+        })
+        .fold(200, usize::max)
+        + 2 * PADDING;
+    let height = matrices
+        .iter()
+        .map(|(_, matrix)| 2 * (TEXT_HEIGHT + PADDING) + matrix.names.len() * CELL + PADDING)
+        .sum::<usize>()
+        + 2 * TEXT_HEIGHT
+        + 3 * PADDING;
+    let mut canvas = Canvas::new(width, height, WHITE);
+    let mut top = PADDING;
+    for (coefficient, matrix) in matrices {
+        canvas.text(
+            (PADDING, top),
+            &coefficient.name().to_uppercase(),
+            SCALE,
+            BLACK,
+        );
+        top += TEXT_HEIGHT + PADDING;
+        let left = PADDING + label_width(matrix.names.len());
+        let grid_top = top + TEXT_HEIGHT + PADDING;
+        for index in 0..matrix.names.len() {
+            let label = (index + 1).to_string();
+            let label_width = Canvas::text_width(&label, SCALE);
+            let offset = index * CELL + CELL / 2;
+            canvas.text((left + offset - label_width / 2, top), &label, SCALE, BLACK);
+            canvas.text(
+                (
+                    left - PADDING - label_width,
+                    grid_top + offset - TEXT_HEIGHT / 2,
+                ),
+                &label,
+                SCALE,
+                BLACK,
+            );
+        }
+        for (row, cells) in matrix.cells.iter().enumerate() {
+            for (column, value) in cells.iter().enumerate() {
+                let r = heatmap_r(value);
+                let (x, y) = (left + column * CELL, grid_top + row * CELL);
+                let fill = r.map_or([204, 204, 204], heatmap_rgb);
+                // Leave a white line between neighbouring cells.
+                canvas.fill_rect((x + 1, y + 1), (CELL - 1, CELL - 1), fill);
+                if let Some(r) = r {
+                    // Drop the leading zero so that every value fits in its cell.
+                    let text = format!("{r:.2}").replacen("0.", ".", 1);
+                    canvas.text(
+                        (
+                            x + (CELL - Canvas::text_width(&text, SCALE)) / 2,
+                            y + (CELL - TEXT_HEIGHT) / 2,
+                        ),
+                        &text,
+                        SCALE,
+                        if r.abs() > 0.6 { WHITE } else { BLACK },
+                    );
+                }
+            }
+        }
+        top = grid_top + matrix.names.len() * CELL + PADDING;
+    }
+    for offset in 0..200 {
+        let r = (offset as f64).mul_add(2.0 / 199.0, -1.0);
+        canvas.fill_rect((PADDING + offset, top), (1, TEXT_HEIGHT), heatmap_rgb(r));
+    }
+    top += TEXT_HEIGHT + PADDING / 2;
+    canvas.text((PADDING, top), "-1", SCALE, BLACK);
+    canvas.text(
+        (PADDING + 100 - Canvas::text_width("0", SCALE) / 2, top),
+        "0",
+        SCALE,
+        BLACK,
+    );
+    canvas.text(
+        (PADDING + 200 - Canvas::text_width("1", SCALE), top),
+        "1",
+        SCALE,
+        BLACK,
+    );
+    canvas.encode()
+}
+
+pub fn write_rolling(
+    file_name: &str,
+    correlations: &[RollingCorrelation],
+) -> Result<(), AnalysisError> {
+    let values = correlations
+        .iter()
+        .map(|correlation| match &correlation.value {
+            CorrelationValue::Valid(result) => Some(result),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let mut dataframe = DataFrame::new(vec![
+        Series::new(
+            "start",
+            correlations
+                .iter()
+                .map(|correlation| correlation.start.as_str())
+                .collect::<Vec<&str>>(),
+        ),
+        Series::new(
+            "end",
+            correlations
+                .iter()
+                .map(|correlation| correlation.end.as_str())
+                .collect::<Vec<&str>>(),
+        ),
+        Series::new(
+            "var1",
+            correlations
+                .iter()
+                .map(|correlation| correlation.var1.as_str())
+                .collect::<Vec<&str>>(),
+        ),
+        Series::new(
+            "var2",
+            correlations
+                .iter()
+                .map(|correlation| correlation.var2.as_str())
+                .collect::<Vec<&str>>(),
+        ),
+        Series::new(
+            "coefficient_type",
+            correlations
+                .iter()
+                .map(|correlation| correlation.coefficient.name())
+                .collect::<Vec<&str>>(),
+        ),
+        Series::new(
+            "r",
+            values
+                .iter()
+                .map(|result| result.map(|result| result.r))
+                .collect::<Vec<Option<f64>>>(),
+        ),
+        Series::new(
+            "p",
+            values
+                .iter()
+                .map(|result| result.map(|result| result.p_value))
+                .collect::<Vec<Option<f64>>>(),
+        ),
+        Series::new(
+            "n",
+            values
+                .iter()
+                .map(|result| result.and_then(|result| u64::try_from(result.n).ok()))
+                .collect::<Vec<Option<u64>>>(),
+        ),
+    ])
+    .unwrap();
+    write_csv(Path::new(file_name), &mut dataframe)
+}
+
+/// The Markdown report as a standalone HTML page. Only what the Markdown renderers emit is
+/// translated: headings, quotes, lists, pipe tables, and bold, italic or code text.
+pub fn html_report(title: &str, markdown: &str) -> String {
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html lang=\"zh-Hant\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>table {{ border-collapse: collapse; }} th, td {{ border: 1px solid #ccc; padding: 0.25em 0.5em; }}</style>\n</head>\n<body>\n",
+        inline_html(title)
+    );
+    let mut lines = markdown.lines().map(str::trim_end).peekable();
+    while let Some(line) = lines.next() {
+        if line.starts_with('|') {
+            html.push_str("<table>\n");
+            html_row(&mut html, line, "th");
+            while let Some(row) = lines.next_if(|line| line.starts_with('|')) {
+                if !row.chars().all(|character| "|-: ".contains(character)) {
+                    html_row(&mut html, row, "td");
+                }
+            }
+            html.push_str("</table>\n");
+        } else if line.starts_with("- ") {
+            html.push_str("<ul>\n");
+            let mut item = Some(line);
+            while let Some(line) = item {
+                writeln!(html, "<li>{}</li>", inline_html(&line[2..])).unwrap();
+                item = lines.next_if(|line| line.starts_with("- "));
+            }
+            html.push_str("</ul>\n");
+        } else if let Some(quote) = line.strip_prefix("> ") {
+            writeln!(
+                html,
+                "<blockquote><p>{}</p></blockquote>",
+                inline_html(quote)
+            )
+            .unwrap();
+        } else if let Some((level, heading)) =
+            ["### ", "## ", "# "].into_iter().find_map(|prefix| {
+                line.strip_prefix(prefix)
+                    .map(|heading| (prefix.len() - 1, heading))
+            })
+        {
+            writeln!(html, "<h{level}>{}</h{level}>", inline_html(heading.trim())).unwrap();
+        } else if !line.is_empty() {
+            writeln!(html, "<p>{}</p>", inline_html(line)).unwrap();
+        }
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+/// A pipe-table row, whose `\|` are literal pipes rather than cell separators.
+fn html_row(html: &mut String, row: &str, tag: &str) {
+    let row = row.trim().trim_start_matches('|');
+    let row = row.strip_suffix('|').unwrap_or(row);
+    let mut cells = vec![String::new()];
+    let mut characters = row.chars().peekable();
+    while let Some(character) = characters.next() {
+        match character {
+            '\\' if characters.peek() == Some(&'|') => {
+                cells.last_mut().unwrap().push('|');
+                characters.next();
+            }
+            '|' => cells.push(String::new()),
+            _ => cells.last_mut().unwrap().push(character),
+        }
+    }
+    html.push_str("<tr>");
+    for cell in cells {
+        write!(html, "<{tag}>{}</{tag}>", inline_html(cell.trim())).unwrap();
+    }
+    html.push_str("</tr>\n");
+}
+
+/// Escapes the text except for the `<br>` line breaks in table cells, then turns `**`, `*` and
+/// backtick spans into `<strong>`, `<em>` and `<code>`. Escaped `\*` stays a literal asterisk.
+fn inline_html(text: &str) -> String {
+    let escaped = text
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace("&lt;br&gt;", "<br>")
+        .replace("\\*", "&#42;");
+    [("**", "strong"), ("*", "em"), ("`", "code")]
+        .into_iter()
+        .fold(escaped, |text, (marker, tag)| {
+            paired_tags(&text, marker, tag)
+        })
+}
+
+/// Wraps the text between each pair of `marker`s in `tag`. Markers with nothing or whitespace
+/// just inside, such as APA significance stars, are left as they are.
+fn paired_tags(text: &str, marker: &str, tag: &str) -> String {
+    let mut html = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find(marker) {
+        let after = &rest[start + marker.len()..];
+        let span = after.find(marker).map(|end| &after[..end]).filter(|span| {
+            !span.is_empty()
+                && !span.starts_with(char::is_whitespace)
+                && !span.ends_with(char::is_whitespace)
+        });
+        if let Some(span) = span {
+            write!(html, "{}<{tag}>{span}</{tag}>", &rest[..start]).unwrap();
+            rest = &after[span.len() + marker.len()..];
+        } else {
+            html.push_str(&rest[..start + marker.len()]);
+            rest = after;
+        }
+    }
+    html.push_str(rest);
+    html
+}
+
+/// Markdown and ASCII tables are drawn by polars with the preset chosen in `set_env`; GitHub
+/// tables are compact pipe tables and CSV tables are written by [`CsvWriter`].
+pub fn table(dataframe: &DataFrame, style: TableStyle) -> String {
+    match style {
+        TableStyle::Markdown | TableStyle::Ascii => dataframe.to_string(),
+        TableStyle::Github => {
+            let row = |cells: Vec<String>| format!("| {} |", cells.join(" | "));
+            let columns = dataframe.get_columns();
+            let mut lines = vec![
+                row(columns
+                    .iter()
+                    .map(|column| column.name().replace('|', "\\|"))
+                    .collect()),
+                row(columns.iter().map(|_| String::from("---")).collect()),
+            ];
+            lines.extend((0..dataframe.height()).map(|index| {
+                row(columns
+                    .iter()
+                    .map(|column| column.str_value(index).unwrap().replace('|', "\\|"))
+                    .collect())
+            }));
+            lines.join("\n")
+        }
+        TableStyle::Csv => {
+            let mut buffer = Vec::new();
+            CsvWriter::new(&mut buffer)
+                .finish(&mut dataframe.clone())
+                .unwrap();
+            String::from_utf8(buffer).unwrap().trim_end().to_owned()
+        }
+    }
+}
+
+fn matrix_table<T>(
+    row_names: &[String],
+    column_names: &[String],
+    cells: &[Vec<T>],
+    render: impl Fn(&T) -> String,
+) -> DataFrame {
+    let mut series_vec = vec![Series::new("", row_names)];
+    series_vec.extend(column_names.iter().zip(cells).map(|(name, values)| {
+        Series::new(
+            name,
+            values
+                .iter()
+                .map(&render)
+                .collect::<Vec<String>>()
+                .as_slice(),
+        )
+    }));
+    DataFrame::new(series_vec).unwrap()
+}
+
+fn matrix_title(
+    analysis: &Analysis,
+    coefficient: Coefficient,
+    comparisons: Option<usize>,
+) -> String {
+    let title = match (coefficient, &analysis.weights) {
+        (Coefficient::Pearson | Coefficient::Kendall, Some(weights)) => {
+            format!("{}（以 {weights} 加權）", coefficient.name())
+        }
+        (Coefficient::ChiSquare, _) => String::from("卡方獨立性檢定（Cramér's V）"),
+        _ => String::from(coefficient.name()),
+    };
+    corrected_title(title, analysis, comparisons)
+}
+
+/// Notes the `--correction` and its number of comparisons after a section title.
+fn corrected_title(title: String, analysis: &Analysis, comparisons: Option<usize>) -> String {
+    match comparisons {
+        Some(comparisons) => format!(
+            "{title}（{} 校正，{comparisons} 個比較）",
+            analysis.correction.name()
+        ),
+        None => title,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        format_number, grouped_loading_table, heatmap_color, inline_html, matrix_csv_table, ranks,
+        spearman_brown,
+    };
+    use crate::{cli::NumberLocale, CorrelationResult, CorrelationValue, Field};
+    use polars::prelude::*;
+
+    #[test]
+    fn significant_negative_correlation_is_bold() {
+        let mut result = CorrelationResult::from((-0.9, 0.001, 30));
+        assert!(result.to_string().starts_with("**r: -0.90000**"));
+        result.apply_bold_rule(true);
+        assert!(result.to_string().starts_with("r: -0.90000"));
+    }
+
+    #[test]
+    fn ranks_leave_missing_values_empty() {
+        let fields: Vec<Field> =
+            serde_json::from_str(r#"[{"name": "x", "scale": "Ordinal"}]"#).unwrap();
+        let fields = fields.iter().collect::<Vec<&Field>>();
+        let data = [vec![3.0, 0.0, 1.0, 3.0]];
+        let observed = [vec![true, false, true, true]];
+        let ranks = ranks((&data, &observed), &fields);
+        let ranks = ranks.column("x").unwrap().f32().unwrap();
+        assert_eq!(
+            ranks.into_iter().collect::<Vec<_>>(),
+            [Some(2.5), None, Some(1.0), Some(2.5)]
+        );
+    }
+
+    #[test]
+    fn heatmap_diverges_from_white_at_zero() {
+        assert_eq!(heatmap_color(0.0), "rgb(255,255,255)");
+        assert_eq!(heatmap_color(1.0), "rgb(33,102,172)");
+        assert_eq!(heatmap_color(-1.0), "rgb(178,24,43)");
+        assert_eq!(heatmap_color(-0.5), "rgb(217,140,149)");
+    }
+
+    #[test]
+    fn csv_matrices_keep_r_and_p_as_numbers() {
+        let names = [String::from("a"), String::from("b")];
+        let valid = CorrelationValue::Valid(Box::new(CorrelationResult::from((0.5, 0.01, 30))));
+        let cells = [
+            vec![&CorrelationValue::Diagonal, &valid],
+            vec![&valid, &CorrelationValue::Constant],
+        ];
+        let table = matrix_csv_table(&names, &names, &cells);
+        let statistics = table.column("statistic").unwrap().utf8().unwrap();
+        assert_eq!(
+            statistics.into_no_null_iter().collect::<Vec<&str>>(),
+            ["r", "p", "r", "p"]
+        );
+        let a = table.column("a").unwrap().f64().unwrap();
+        assert_eq!(
+            a.into_iter().collect::<Vec<Option<f64>>>(),
+            [Some(1.0), None, Some(0.5), Some(0.01)]
+        );
+        assert_eq!(table.column("b").unwrap().null_count(), 2);
+    }
+
+    #[test]
+    fn every_result_shows_its_sample_size() {
+        let mut result = CorrelationResult::from((0.3, 0.2, 312));
+        assert!(result.to_string().ends_with("<br>N: 312"));
+        result.missing_rows = 8;
+        assert!(result.to_string().ends_with("<br>N: 312（排除 8 筆遺漏）"));
+    }
+
+    #[test]
+    fn loadings_are_grouped_by_dominant_factor() {
+        let loadings = DataFrame::new(vec![
+            Series::new("a", [0.2, 0.7]),
+            Series::new("b", [0.8, 0.1]),
+            Series::new("c", [-0.3, -0.9]),
+        ])
+        .unwrap();
+        let grouped = grouped_loading_table(&loadings, Some(0.25), 5);
+        let names = grouped.column("欄位").unwrap().utf8().unwrap();
+        assert_eq!(
+            names.into_no_null_iter().collect::<Vec<&str>>(),
+            ["**因子 1**", "b", "**因子 2**", "c", "a"]
+        );
+        let first = grouped.column("因子 1").unwrap().utf8().unwrap();
+        assert_eq!(first.get(4), Some(""));
+        assert_eq!(first.get(3), Some("-0.30000"));
+    }
+
+    #[test]
+    fn numbers_follow_the_locale() {
+        assert_eq!(format_number(-1234.5, 2, NumberLocale::Plain), "-1234.50");
+        assert_eq!(format_number(-1234.5, 2, NumberLocale::En), "-1,234.50");
+        assert_eq!(
+            format_number(1_234_567.0, 1, NumberLocale::De),
+            "1.234.567,0"
+        );
+        assert_eq!(format_number(0.12345, 3, NumberLocale::Fr), "0,123");
+    }
+
+    #[test]
+    fn significant_cells_become_strong_but_apa_stars_stay() {
+        assert_eq!(
+            inline_html("**r: 0.5** <br> **p value: 0.01**"),
+            "<strong>r: 0.5</strong> <br> <strong>p value: 0.01</strong>"
+        );
+        assert_eq!(inline_html(".55***"), ".55***");
+        assert_eq!(inline_html("\\* *p* < .05"), "&#42; <em>p</em> &lt; .05");
+    }
+
+    #[test]
+    fn doubling_a_scale_raises_its_reliability() {
+        assert!((spearman_brown(0.6, 2.0) - 0.75).abs() < 1e-12);
+        assert!((spearman_brown(0.75, 0.5) - 0.6).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,226 @@
+use crate::json::{JsonCorrelation, JsonReport};
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout},
+    style::{Modifier, Style},
+    widgets::{Block, Borders, Paragraph, Row, Table, TableState},
+    DefaultTerminal, Frame,
+};
+use std::{cmp::Ordering, io};
+
+#[derive(Clone, Copy)]
+enum SortKey {
+    Variables,
+    Strength,
+    PValue,
+}
+
+impl SortKey {
+    const fn next(self) -> Self {
+        match self {
+            Self::Variables => Self::Strength,
+            Self::Strength => Self::PValue,
+            Self::PValue => Self::Variables,
+        }
+    }
+
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Variables => "變數",
+            Self::Strength => "|r|",
+            Self::PValue => "p 值",
+        }
+    }
+}
+
+struct Browser<'a> {
+    report: &'a JsonReport,
+    visible: Vec<&'a JsonCorrelation>,
+    state: TableState,
+    sort: SortKey,
+    significant_only: bool,
+    variable: String,
+    editing: Option<String>,
+}
+
+pub fn browse(report: &JsonReport) -> io::Result<()> {
+    let mut browser = Browser {
+        report,
+        visible: Vec::new(),
+        state: TableState::default(),
+        sort: SortKey::Variables,
+        significant_only: false,
+        variable: String::new(),
+        editing: None,
+    };
+    browser.refresh();
+    ratatui::run(|terminal| browser.run(terminal))
+}
+
+impl Browser<'_> {
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            if let Some(editing) = &mut self.editing {
+                match key.code {
+                    KeyCode::Enter => {
+                        self.variable = editing.clone();
+                        self.editing = None;
+                        self.refresh();
+                    }
+                    KeyCode::Esc => self.editing = None,
+                    KeyCode::Backspace => {
+                        editing.pop();
+                    }
+                    KeyCode::Char(character) => editing.push(character),
+                    _ => {}
+                }
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('q') => return Ok(()),
+                KeyCode::Down | KeyCode::Char('j') => self.state.select_next(),
+                KeyCode::Up | KeyCode::Char('k') => self.state.select_previous(),
+                KeyCode::PageDown => self.state.scroll_down_by(20),
+                KeyCode::PageUp => self.state.scroll_up_by(20),
+                KeyCode::Char('s') => {
+                    self.sort = self.sort.next();
+                    self.refresh();
+                }
+                KeyCode::Char('f') => {
+                    self.significant_only = !self.significant_only;
+                    self.refresh();
+                }
+                KeyCode::Char('/') => self.editing = Some(String::new()),
+                KeyCode::Esc => {
+                    self.variable.clear();
+                    self.refresh();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn refresh(&mut self) {
+        self.visible = self
+            .report
+            .correlations
+            .iter()
+            .filter(|correlation| !self.significant_only || correlation.significant)
+            .filter(|correlation| {
+                correlation.var1.contains(&self.variable)
+                    || correlation.var2.contains(&self.variable)
+            })
+            .collect();
+        let strength = |correlation: &JsonCorrelation| correlation.r.map_or(-1.0, f64::abs);
+        let p_value = |correlation: &JsonCorrelation| correlation.p_value.unwrap_or(f64::INFINITY);
+        match self.sort {
+            SortKey::Variables => {}
+            SortKey::Strength => self.visible.sort_by(|a, b| {
+                strength(b)
+                    .partial_cmp(&strength(a))
+                    .unwrap_or(Ordering::Equal)
+            }),
+            SortKey::PValue => self.visible.sort_by(|a, b| {
+                p_value(a)
+                    .partial_cmp(&p_value(b))
+                    .unwrap_or(Ordering::Equal)
+            }),
+        }
+        self.state.select((!self.visible.is_empty()).then_some(0));
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [table_area, detail_area, status_area] = Layout::vertical([
+            Constraint::Min(3),
+            Constraint::Length(5),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let format = |value: Option<f64>| {
+            value.map_or_else(|| String::from("-"), |value| format!("{value:.5}"))
+        };
+        let rows = self.visible.iter().map(|correlation| {
+            let row = Row::new([
+                correlation.method.clone(),
+                correlation.var1.clone(),
+                correlation.var2.clone(),
+                format(correlation.r),
+                format(correlation.p_value),
+                correlation
+                    .n
+                    .map_or_else(|| String::from("-"), |n| n.to_string()),
+            ]);
+            if correlation.significant {
+                row.style(Style::new().add_modifier(Modifier::BOLD))
+            } else {
+                row
+            }
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(9),
+                Constraint::Fill(1),
+                Constraint::Fill(1),
+                Constraint::Length(9),
+                Constraint::Length(9),
+                Constraint::Length(5),
+            ],
+        )
+        .header(
+            Row::new(["係數", "變數 1", "變數 2", "r", "p", "N"])
+                .style(Style::new().add_modifier(Modifier::UNDERLINED)),
+        )
+        .block(Block::new().borders(Borders::ALL).title(format!(
+            " {}（{} / {}） ",
+            self.report.source,
+            self.visible.len(),
+            self.report.correlations.len()
+        )))
+        .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(table, table_area, &mut self.state);
+
+        let detail = self
+            .state
+            .selected()
+            .and_then(|index| self.visible.get(index))
+            .map_or_else(String::new, |correlation| {
+                format!(
+                    "{} × {}\n{}：r = {}，p = {}，校正後 p = {}，N = {}",
+                    correlation.var1,
+                    correlation.var2,
+                    correlation.method,
+                    format(correlation.r),
+                    format(correlation.p_value),
+                    format(correlation.adjusted_p_value),
+                    correlation
+                        .n
+                        .map_or_else(|| String::from("-"), |n| n.to_string()),
+                )
+            });
+        frame.render_widget(
+            Paragraph::new(detail).block(Block::new().borders(Borders::ALL)),
+            detail_area,
+        );
+
+        let status = self.editing.as_ref().map_or_else(
+            || {
+                format!(
+                    "↑↓ 移動  s 排序（{}）  f 只看顯著（{}）  / 搜尋變數  Esc 清除搜尋  q 離開",
+                    self.sort.name(),
+                    if self.significant_only { "開" } else { "關" }
+                )
+            },
+            |editing| format!("搜尋變數：{editing}"),
+        );
+        frame.render_widget(Paragraph::new(status), status_area);
+    }
+}
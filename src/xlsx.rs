@@ -0,0 +1,375 @@
+use flate2::read::DeflateDecoder;
+use regex::Regex;
+use std::{
+    collections::{BTreeMap, HashMap},
+    io::Read,
+};
+
+/// Converts a sheet of an .xlsx workbook to CSV, so it goes through the same `CsvReader` as CSV
+/// input. `sheet` names the sheet; the first sheet of the workbook is used without it.
+pub fn xlsx_to_csv(bytes: &[u8], sheet: Option<&str>) -> Result<Vec<u8>, String> {
+    let archive = Archive::new(bytes)?;
+    let sheets = tags(&archive.text("xl/workbook.xml")?, "sheet");
+    let names = sheets
+        .iter()
+        .filter_map(|sheet| sheet.get("name").cloned())
+        .collect::<Vec<String>>();
+    let found = match sheet {
+        Some(name) => sheets
+            .iter()
+            .find(|sheet| sheet.get("name").is_some_and(|sheet| sheet == name))
+            .ok_or_else(|| {
+                format!(
+                    "the workbook has no sheet named `{name}`, only: {}",
+                    names.join(", ")
+                )
+            })?,
+        None => sheets
+            .first()
+            .ok_or_else(|| String::from("the workbook has no sheets"))?,
+    };
+    let id = found
+        .get("r:id")
+        .ok_or_else(|| String::from("a sheet has no relationship id"))?;
+    let relationships = tags(&archive.text("xl/_rels/workbook.xml.rels")?, "Relationship");
+    let target = relationships
+        .iter()
+        .find(|relationship| relationship.get("Id") == Some(id))
+        .and_then(|relationship| relationship.get("Target"))
+        .ok_or_else(|| format!("the workbook does not say where sheet {id} is stored"))?;
+    let path = target
+        .strip_prefix('/')
+        .map_or_else(|| format!("xl/{target}"), str::to_owned);
+    let shared_strings = archive
+        .text("xl/sharedStrings.xml")
+        .map_or_else(|_| Vec::new(), |text| shared_strings(&text));
+    Ok(to_csv(&cells(&archive.text(&path)?, &shared_strings)))
+}
+
+/// The entries of a zip archive, read from its central directory. Zip64 archives are not
+/// supported, which only matters past 4 GB.
+struct Archive<'a> {
+    bytes: &'a [u8],
+    entries: HashMap<String, Entry>,
+}
+
+struct Entry {
+    method: u16,
+    compressed_size: usize,
+    header_offset: usize,
+}
+
+impl<'a> Archive<'a> {
+    fn new(bytes: &'a [u8]) -> Result<Self, String> {
+        let not_a_workbook = || String::from("not an .xlsx workbook");
+        let end = (0..=bytes.len().saturating_sub(22))
+            .rev()
+            .take(65_536)
+            .find(|&offset| bytes[offset..].starts_with(b"PK\x05\x06"))
+            .ok_or_else(not_a_workbook)?;
+        let count = usize::from(u16_at(bytes, end + 10)?);
+        let mut offset = u32_at(bytes, end + 16)?;
+        let mut entries = HashMap::new();
+        for _ in 0..count {
+            if !bytes
+                .get(offset..)
+                .is_some_and(|entry| entry.starts_with(b"PK\x01\x02"))
+            {
+                return Err(not_a_workbook());
+            }
+            let name_length = usize::from(u16_at(bytes, offset + 28)?);
+            let name = bytes
+                .get(offset + 46..offset + 46 + name_length)
+                .ok_or_else(not_a_workbook)?;
+            entries.insert(
+                String::from_utf8_lossy(name).into_owned(),
+                Entry {
+                    method: u16_at(bytes, offset + 10)?,
+                    compressed_size: u32_at(bytes, offset + 20)?,
+                    header_offset: u32_at(bytes, offset + 42)?,
+                },
+            );
+            offset += 46
+                + name_length
+                + usize::from(u16_at(bytes, offset + 30)?)
+                + usize::from(u16_at(bytes, offset + 32)?);
+        }
+        Ok(Self { bytes, entries })
+    }
+
+    fn text(&self, name: &str) -> Result<String, String> {
+        let entry = self
+            .entries
+            .get(name)
+            .ok_or_else(|| format!("the workbook has no {name}"))?;
+        let header = entry.header_offset;
+        let start = header
+            + 30
+            + usize::from(u16_at(self.bytes, header + 26)?)
+            + usize::from(u16_at(self.bytes, header + 28)?);
+        let data = self
+            .bytes
+            .get(start..start + entry.compressed_size)
+            .ok_or_else(|| format!("{name} is truncated"))?;
+        let mut text = String::new();
+        match entry.method {
+            0 => text.push_str(&String::from_utf8_lossy(data)),
+            8 => {
+                DeflateDecoder::new(data)
+                    .read_to_string(&mut text)
+                    .map_err(|error| format!("unable to decompress {name}: {error}"))?;
+            }
+            method => {
+                return Err(format!(
+                    "{name} uses unsupported compression method {method}"
+                ))
+            }
+        }
+        Ok(text)
+    }
+}
+
+fn u16_at(bytes: &[u8], offset: usize) -> Result<u16, String> {
+    bytes
+        .get(offset..offset + 2)
+        .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+        .ok_or_else(|| String::from("not an .xlsx workbook"))
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> Result<usize, String> {
+    bytes
+        .get(offset..offset + 4)
+        .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+        .ok_or_else(|| String::from("not an .xlsx workbook"))
+}
+
+/// The attributes of every `<tag …>` in `xml`.
+fn tags(xml: &str, tag: &str) -> Vec<HashMap<String, String>> {
+    Regex::new(&format!(r"<{tag}\b([^>]*)>"))
+        .unwrap()
+        .captures_iter(xml)
+        .map(|captures| attributes(&captures[1]))
+        .collect()
+}
+
+fn attributes(tag: &str) -> HashMap<String, String> {
+    Regex::new(r#"([\w:]+)\s*=\s*"([^"]*)""#)
+        .unwrap()
+        .captures_iter(tag)
+        .map(|captures| (captures[1].to_owned(), unescape(&captures[2])))
+        .collect()
+}
+
+/// The text of every `<t>` run in `xml`, without the phonetic guides of East Asian text.
+fn runs(xml: &str) -> String {
+    let xml = Regex::new(r"(?s)<rPh\b.*?</rPh>")
+        .unwrap()
+        .replace_all(xml, "");
+    Regex::new(r"(?s)<t(?:\s[^>]*)?>(.*?)</t>")
+        .unwrap()
+        .captures_iter(&xml)
+        .map(|captures| unescape(&captures[1]))
+        .collect()
+}
+
+fn shared_strings(xml: &str) -> Vec<String> {
+    Regex::new(r"(?s)<si>(.*?)</si>|<si/>")
+        .unwrap()
+        .captures_iter(xml)
+        .map(|captures| {
+            captures
+                .get(1)
+                .map_or_else(String::new, |si| runs(si.as_str()))
+        })
+        .collect()
+}
+
+/// The cell values of a worksheet by row and column, both from 0.
+fn cells(xml: &str, shared_strings: &[String]) -> BTreeMap<usize, BTreeMap<usize, String>> {
+    let row_pattern = Regex::new(r"(?s)<row\b([^>]*?)(?:/>|>(.*?)</row>)").unwrap();
+    let cell_pattern = Regex::new(r"(?s)<c\b([^>]*?)(?:/>|>(.*?)</c>)").unwrap();
+    let value_pattern = Regex::new(r"(?s)<v>(.*?)</v>").unwrap();
+    let mut rows = BTreeMap::new();
+    for (index, row) in row_pattern.captures_iter(xml).enumerate() {
+        let row_index = attributes(&row[1])
+            .get("r")
+            .and_then(|r| r.parse::<usize>().ok())
+            .map_or(index, |r| r - 1);
+        let Some(content) = row.get(2) else {
+            continue;
+        };
+        let mut values = BTreeMap::new();
+        for (index, cell) in cell_pattern.captures_iter(content.as_str()).enumerate() {
+            let attributes = attributes(&cell[1]);
+            let column = attributes
+                .get("r")
+                .and_then(|reference| column_index(reference))
+                .unwrap_or(index);
+            let content = cell.get(2).map_or("", |content| content.as_str());
+            let value = value_pattern
+                .captures(content)
+                .map(|value| unescape(&value[1]));
+            let value = match (attributes.get("t").map(String::as_str), value) {
+                (Some("inlineStr"), _) => runs(content),
+                (Some("s"), Some(value)) => value
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|index| shared_strings.get(index).cloned())
+                    .unwrap_or_default(),
+                (Some("b"), Some(value)) => {
+                    String::from(if value == "1" { "true" } else { "false" })
+                }
+                (Some("e"), _) | (_, None) => String::new(),
+                (_, Some(value)) => value,
+            };
+            values.insert(column, value);
+        }
+        if values.values().any(|value| !value.is_empty()) {
+            rows.insert(row_index, values);
+        }
+    }
+    rows
+}
+
+/// `AB12` → 27.
+fn column_index(reference: &str) -> Option<usize> {
+    let letters = reference
+        .chars()
+        .take_while(char::is_ascii_uppercase)
+        .collect::<Vec<char>>();
+    (!letters.is_empty()).then(|| {
+        letters.iter().fold(0, |index, &letter| {
+            index * 26 + (letter as usize - 'A' as usize + 1)
+        }) - 1
+    })
+}
+
+fn unescape(text: &str) -> String {
+    Regex::new(r"&(#x[0-9a-fA-F]+|#[0-9]+|amp|lt|gt|quot|apos);")
+        .unwrap()
+        .replace_all(text, |captures: &regex::Captures| {
+            let entity = &captures[1];
+            let code = entity
+                .strip_prefix("#x")
+                .map(|hex| u32::from_str_radix(hex, 16))
+                .or_else(|| entity.strip_prefix('#').map(str::parse));
+            match (entity, code) {
+                (_, Some(code)) => code
+                    .ok()
+                    .and_then(char::from_u32)
+                    .map_or_else(String::new, String::from),
+                ("amp", _) => String::from("&"),
+                ("lt", _) => String::from("<"),
+                ("gt", _) => String::from(">"),
+                ("quot", _) => String::from("\""),
+                _ => String::from("'"),
+            }
+        })
+        .into_owned()
+}
+
+fn to_csv(rows: &BTreeMap<usize, BTreeMap<usize, String>>) -> Vec<u8> {
+    let width = rows
+        .values()
+        .filter_map(|row| row.keys().next_back())
+        .max()
+        .map_or(0, |column| column + 1);
+    let mut csv = String::new();
+    for row in rows.values() {
+        let line = (0..width)
+            .map(|column| {
+                let value = row.get(&column).map_or("", String::as_str);
+                if value.contains([',', '"', '\n', '\r']) {
+                    format!("\"{}\"", value.replace('"', "\"\""))
+                } else {
+                    value.to_owned()
+                }
+            })
+            .collect::<Vec<String>>();
+        csv.push_str(&line.join(","));
+        csv.push('\n');
+    }
+    csv.into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::xlsx_to_csv;
+
+    /// A zip archive with every file stored uncompressed, which is all `Archive` needs.
+    fn stored_zip(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut zip = Vec::new();
+        let mut directory = Vec::new();
+        for (name, content) in files {
+            let offset = u32::try_from(zip.len()).unwrap();
+            let (name_length, size) = (
+                u16::try_from(name.len()).unwrap(),
+                u32::try_from(content.len()).unwrap(),
+            );
+            zip.extend(b"PK\x03\x04");
+            zip.extend([0; 14]);
+            zip.extend(size.to_le_bytes());
+            zip.extend(size.to_le_bytes());
+            zip.extend(name_length.to_le_bytes());
+            zip.extend([0; 2]);
+            zip.extend(name.as_bytes());
+            zip.extend(content.as_bytes());
+            directory.extend(b"PK\x01\x02");
+            directory.extend([0; 16]);
+            directory.extend(size.to_le_bytes());
+            directory.extend(size.to_le_bytes());
+            directory.extend(name_length.to_le_bytes());
+            directory.extend([0; 12]);
+            directory.extend(offset.to_le_bytes());
+            directory.extend(name.as_bytes());
+        }
+        let directory_offset = u32::try_from(zip.len()).unwrap();
+        let count = u16::try_from(files.len()).unwrap();
+        zip.extend(&directory);
+        zip.extend(b"PK\x05\x06");
+        zip.extend([0; 4]);
+        zip.extend(count.to_le_bytes());
+        zip.extend(count.to_le_bytes());
+        zip.extend(u32::try_from(directory.len()).unwrap().to_le_bytes());
+        zip.extend(directory_offset.to_le_bytes());
+        zip.extend([0; 2]);
+        zip
+    }
+
+    #[test]
+    fn sheets_become_csv() {
+        let workbook = stored_zip(&[
+            (
+                "xl/workbook.xml",
+                r#"<workbook><sheets><sheet name="說明" sheetId="1" r:id="rId1"/><sheet name="回覆" sheetId="2" r:id="rId2"/></sheets></workbook>"#,
+            ),
+            (
+                "xl/_rels/workbook.xml.rels",
+                r#"<Relationships><Relationship Id="rId1" Target="worksheets/sheet1.xml"/><Relationship Id="rId2" Target="/xl/worksheets/sheet2.xml"/></Relationships>"#,
+            ),
+            (
+                "xl/sharedStrings.xml",
+                r#"<sst><si><t>滿意度</t></si><si><r><t>年齡</t></r><r><t xml:space="preserve">, 歲</t></r></si></sst>"#,
+            ),
+            (
+                "xl/worksheets/sheet1.xml",
+                r#"<worksheet><sheetData><row r="1"><c r="A1" t="inlineStr"><is><t>請見回覆</t></is></c></row></sheetData></worksheet>"#,
+            ),
+            (
+                "xl/worksheets/sheet2.xml",
+                r#"<worksheet><sheetData><row r="1"><c r="A1" t="s"><v>0</v></c><c r="C1" t="s"><v>1</v></c></row><row r="2"><c r="A2"><v>4</v></c><c r="B2" t="str"><v>A &amp; B</v></c><c r="C2"><v>23.5</v></c></row><row r="3"/><row r="4"><c r="C4"><v>31</v></c></row></sheetData></worksheet>"#,
+            ),
+        ]);
+        assert_eq!(
+            xlsx_to_csv(&workbook, None).unwrap(),
+            "請見回覆\n".as_bytes()
+        );
+        assert_eq!(
+            String::from_utf8(xlsx_to_csv(&workbook, Some("回覆")).unwrap()).unwrap(),
+            "滿意度,,\"年齡, 歲\"\n4,A & B,23.5\n,,31\n"
+        );
+        assert!(xlsx_to_csv(&workbook, Some("工作表1"))
+            .unwrap_err()
+            .contains("說明, 回覆"));
+    }
+}
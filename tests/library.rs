@@ -0,0 +1,118 @@
+use clap::Parser;
+use ntpu_market_research_statistical_tool::{
+    run_correlations, AnalysisError, Cli, Command, CorrelationMatrix, CorrelationValue, Field,
+    Options,
+};
+use polars::prelude::*;
+use serde_json::{from_value, json};
+
+/// The reference values below are what scipy reports for this data, in which `y` has ties.
+fn dataframe() -> DataFrame {
+    df!(
+        "x" => [1.0, 2.0, 3.0, 4.0, 5.0],
+        "y" => [2.0, 4.0, 5.0, 4.0, 5.0]
+    )
+    .unwrap()
+}
+
+fn fields(scale: &str) -> Vec<Field> {
+    from_value(json!([
+        { "name": "x", "scale": scale },
+        { "name": "y", "scale": scale }
+    ]))
+    .unwrap()
+}
+
+fn options(flags: &[&str]) -> Options {
+    let arguments = ["tool", "analyze", "data.csv", "fields.json", "--offline"];
+    let Command::Analyze(options) = Cli::parse_from(arguments.iter().chain(flags)).command else {
+        unreachable!()
+    };
+    *options
+}
+
+fn assert_cell(matrix: Option<CorrelationMatrix>, r: f64, p_value: f64) {
+    let matrix = matrix.unwrap();
+    assert_eq!(matrix.names, ["x", "y"]);
+    assert!(matches!(matrix.cells[0][0], CorrelationValue::Diagonal));
+    assert!(matches!(matrix.cells[1][1], CorrelationValue::Diagonal));
+    for (row, column) in [(0, 1), (1, 0)] {
+        let CorrelationValue::Valid(result) = &matrix.cells[row][column] else {
+            panic!("expected a valid correlation");
+        };
+        assert!((result.r - r).abs() < 1e-9);
+        assert!((result.p_value - p_value).abs() < 1e-6);
+        assert_eq!(result.n, 5);
+    }
+}
+
+#[test]
+fn correlations_match_scipy() {
+    let matrices = run_correlations(
+        &dataframe(),
+        &fields("Interval"),
+        &options(&["--native-pearson"]),
+    )
+    .unwrap();
+    // scipy.stats.pearsonr(x, y)
+    assert_cell(
+        matrices.pearson,
+        0.774_596_669_241_483_4,
+        0.124_027_062_657_552_2,
+    );
+}
+
+#[test]
+fn native_engine_matches_scipy() {
+    let options = options(&["--engine", "native"]);
+    let pearson = run_correlations(&dataframe(), &fields("Interval"), &options)
+        .unwrap()
+        .pearson;
+    assert_cell(pearson, 0.774_596_669_241_483_4, 0.124_027_062_657_552_2);
+    // scipy.stats.spearmanr(x, y)
+    let spearman = run_correlations(&dataframe(), &fields("Ordinal"), &options)
+        .unwrap()
+        .spearman;
+    assert_cell(spearman, 0.737_864_787_372_621_8, 0.154_618_523_128_580_8);
+}
+
+#[test]
+fn scipy_engine_is_skipped_offline() {
+    let spearman = run_correlations(&dataframe(), &fields("Ordinal"), &options(&[]))
+        .unwrap()
+        .spearman;
+    assert!(matches!(
+        spearman.unwrap().cells[0][1],
+        CorrelationValue::Skipped
+    ));
+}
+
+#[test]
+fn missing_columns_are_named() {
+    let described: Vec<Field> = from_value(json!([
+        { "name": "x", "scale": "Interval" },
+        { "name": "z", "scale": "Interval" }
+    ]))
+    .unwrap();
+    let Err(error) = run_correlations(&dataframe(), &described, &options(&[])) else {
+        panic!("expected z to be missing");
+    };
+    assert!(matches!(&error, AnalysisError::MissingColumn(column) if column == "z"));
+    assert_eq!(error.to_string(), "Column z not found in the data.");
+    let weighted = options(&["--weights", "w"]);
+    let Err(error) = run_correlations(&dataframe(), &fields("Interval"), &weighted) else {
+        panic!("expected the weight column to be missing");
+    };
+    assert!(matches!(error, AnalysisError::MissingColumn(column) if column == "w"));
+}
+
+#[test]
+fn unknown_cross_correlation_fields_are_errors() {
+    let crossed = options(&["--rows", "x", "--cols", "z"]);
+    let Err(error) = run_correlations(&dataframe(), &fields("Interval"), &crossed) else {
+        panic!("expected z to be rejected");
+    };
+    assert!(matches!(error, AnalysisError::Usage(_)));
+    assert_eq!(error.to_string(), "z is not an analyzed field.");
+    assert_eq!(error.exit_code(), 1);
+}